@@ -1,9 +1,99 @@
 #include <pybind11/pybind11.h>
 #include <pybind11/stl.h> // For automatic type conversion
+#include <pybind11/functional.h> // For Python callables as std::function
+#include <algorithm>
+#include <cmath>
+#include <cstdint>
+#include <cstring>
+#include <stdexcept>
+#include <string>
+#include <string_view>
 #include "mpr_photo_editor/image_manager.h" // The new core logic header
+#include "mpr_photo_editor/image.h"
+#include "mpr_photo_editor/image_ops.h"
+#include "mpr_photo_editor/color.h"
+#include "mpr_photo_editor/image_io.h"
+#include "mpr_photo_editor/xmp.h"
+#include "mpr_photo_editor/dng_opcodes.h"
 
 namespace py = pybind11;
 
+// --- Buffer Conversion ---
+// Image buffers cross the Python boundary as `bytes` objects. Both directions
+// copy: operations work on their own vector and every result is a new `bytes`
+// object, so no Python buffer is ever aliased or modified in place. This keeps
+// history entries held by the editor's undo stack intact. Mutable buffers
+// (bytearray, numpy arrays) are rejected by the `bytes` parameters.
+// The `_inplace` point operations are the exception: they take a writable
+// buffer of unsigned bytes and modify it without copying.
+
+std::vector<uint8_t> to_buffer(const py::bytes& data) {
+    std::string_view view = data;
+    return std::vector<uint8_t>(view.begin(), view.end());
+}
+
+py::bytes to_bytes(const std::vector<uint8_t>& buffer) {
+    return py::bytes(reinterpret_cast<const char*>(buffer.data()), buffer.size());
+}
+
+// Wider samples (uint16 from linear decodes, float32 radiance) travel as
+// native-endian bytes as well.
+template <typename T>
+std::vector<T> to_samples(const py::bytes& data) {
+    std::string_view view = data;
+    if (view.size() % sizeof(T) != 0) {
+        throw std::invalid_argument("Buffer size is not a multiple of the sample size");
+    }
+    std::vector<T> samples(view.size() / sizeof(T));
+    std::memcpy(samples.data(), view.data(), view.size());
+    return samples;
+}
+
+// Checks that a Python buffer is writable, C-contiguous, holds uint8 samples,
+// and matches the given dimensions. The buffer stays exported, so it can be
+// neither resized nor freed, for as long as the returned info is held; keep it
+// alive while writing to `ptr` without the GIL.
+py::buffer_info writable_samples(const py::buffer& data, int width, int height, int channels) {
+    py::buffer_info info = data.request(true);
+    if (info.format != py::format_descriptor<uint8_t>::format()) {
+        throw std::invalid_argument("Buffer must hold uint8 samples, not format '" + info.format + "'");
+    }
+    if (info.ndim > 1) {
+        py::ssize_t stride = 1;
+        for (py::ssize_t i = info.ndim - 1; i >= 0; --i) {
+            if (info.shape[i] > 1 && info.strides[i] != stride) {
+                throw std::invalid_argument("Buffer must be C-contiguous");
+            }
+            stride *= info.shape[i];
+        }
+    }
+    size_t expected = static_cast<size_t>(std::max(width, 0)) * std::max(height, 0) * std::max(channels, 0);
+    if (static_cast<size_t>(info.size) != expected) {
+        throw std::invalid_argument("Buffer size " + std::to_string(info.size) + " does not match " +
+                                    std::to_string(width) + "x" + std::to_string(height) + "x" +
+                                    std::to_string(channels));
+    }
+    return info;
+}
+
+template <typename T>
+py::bytes samples_to_bytes(const std::vector<T>& samples) {
+    return py::bytes(reinterpret_cast<const char*>(samples.data()), samples.size() * sizeof(T));
+}
+
+// --- GIL Handling ---
+// Decoding and pixel operations release the GIL, so other Python threads (the
+// UI in particular) keep running and several images can be processed at once.
+// The work must not touch Python objects: convert arguments before the call
+// and results after it. Wrappers that neither take nor return Python objects
+// use py::call_guard in the module definition instead.
+
+template <typename Work>
+auto without_gil(Work work) {
+    py::gil_scoped_release release;
+    return work();
+}
+
 // --- Wrapper Functions ---
 // These functions act as the bridge between the C++ ImageManager and Python.
 // They handle the conversion of C++ types (like structs) to Python types (like dicts).
@@ -12,19 +102,99 @@ std::string get_libraw_version_wrapper() {
     return ImageManager::instance().get_libraw_version();
 }
 
+std::vector<std::string> get_supported_extensions_wrapper() {
+    return ImageManager::instance().get_supported_extensions();
+}
+
+int get_supported_camera_count_wrapper() {
+    return ImageManager::instance().get_supported_camera_count();
+}
+
+std::vector<std::string> get_supported_cameras_wrapper() {
+    return ImageManager::instance().get_supported_cameras();
+}
+
 uint64_t load_raw_image_wrapper(const std::string& filepath) {
     return ImageManager::instance().load_raw_image(filepath);
 }
 
+// Reads a file-like object to its end in chunks, holding the GIL for the
+// read() calls only, so streams that return fewer bytes than asked for (such
+// as sockets) work too. The decode itself runs without the GIL.
+uint64_t load_raw_image_from_stream_wrapper(const py::object& stream) {
+    constexpr py::ssize_t chunk_size = py::ssize_t(1) << 20;
+    py::object read = stream.attr("read");
+    std::vector<uint8_t> data;
+    for (;;) {
+        py::object chunk = read(chunk_size);
+        if (!py::isinstance<py::bytes>(chunk)) {
+            throw py::type_error("read() must return bytes; open files in binary mode");
+        }
+        std::string_view view = chunk.cast<py::bytes>();
+        if (view.empty()) {
+            break;
+        }
+        data.insert(data.end(), view.begin(), view.end());
+    }
+    return without_gil([&] { return ImageManager::instance().load_raw_image_from_buffer(std::move(data)); });
+}
+
 void release_raw_image_wrapper(uint64_t id) {
     ImageManager::instance().release_raw_image(id);
 }
 
-py::bytes get_thumbnail_wrapper(uint64_t id) {
-    ThumbnailData thumb_data = ImageManager::instance().get_thumbnail(id);
+void set_max_decode_pixels_wrapper(uint64_t pixels) {
+    ImageManager::instance().set_max_decode_pixels(pixels);
+}
+
+uint64_t get_max_decode_pixels_wrapper() {
+    return ImageManager::instance().get_max_decode_pixels();
+}
+
+size_t get_open_image_count_wrapper() {
+    return ImageManager::instance().get_open_image_count();
+}
+
+py::bytes get_thumbnail_wrapper(uint64_t id, bool apply_orientation) {
+    ThumbnailData thumb_data = without_gil([&] { return ImageManager::instance().get_thumbnail(id, apply_orientation); });
     return py::bytes(thumb_data.data.data(), thumb_data.data.size());
 }
 
+py::list list_thumbnails_wrapper(const std::string& filepath) {
+    std::vector<ThumbnailInfo> thumbnails = without_gil([&] { return ImageManager::instance().list_thumbnails(filepath); });
+    py::list result;
+    for (const ThumbnailInfo& info : thumbnails) {
+        py::dict entry;
+        entry["width"] = info.width;
+        entry["height"] = info.height;
+        entry["format"] = info.jpeg ? "jpeg" : "bitmap";
+        entry["size"] = info.size;
+        result.append(entry);
+    }
+    return result;
+}
+
+py::bytes extract_thumbnail_index_wrapper(const std::string& filepath, int index, bool apply_orientation) {
+    ThumbnailData thumb_data = without_gil([&] {
+        return ImageManager::instance().extract_thumbnail_index(filepath, index, apply_orientation);
+    });
+    return py::bytes(thumb_data.data.data(), thumb_data.data.size());
+}
+
+// Names for the EXIF MeteringMode codes; anything else is "unknown".
+const char* metering_mode_name(int code) {
+    switch (code) {
+        case 1: return "average";
+        case 2: return "center_weighted";
+        case 3: return "spot";
+        case 4: return "multi_spot";
+        case 5: return "pattern";
+        case 6: return "partial";
+        case 255: return "other";
+        default: return "unknown";
+    }
+}
+
 py::dict get_metadata_wrapper(uint64_t id) {
     Metadata meta_data = ImageManager::instance().get_metadata(id);
     py::dict meta;
@@ -33,15 +203,1191 @@ py::dict get_metadata_wrapper(uint64_t id) {
     meta["iso"] = meta_data.iso_speed;
     meta["shutter"] = meta_data.shutter;
     meta["aperture"] = meta_data.aperture;
+    meta["timestamp"] = meta_data.timestamp;
+    meta["focal_length"] = meta_data.focal_length.actual;
+    meta["focal_length_35mm"] = meta_data.focal_length.equivalent_35mm;
+    meta["flash_fired"] = meta_data.capture_flags.flash_fired;
+    meta["metering_mode"] = metering_mode_name(meta_data.capture_flags.metering_mode);
+    meta["lossy_compression"] = meta_data.lossy_compression;
+    meta["width"] = meta_data.width;
+    meta["height"] = meta_data.height;
     return meta;
 }
 
+// Pixel data with the format LibRaw produced it in: bits per sample, channel
+// count, and "layout" ("interleaved" or "planar"), plus the white levels.
+py::dict decoded_to_dict(const DecodedImage& decoded) {
+    py::dict result;
+    result["data"] = to_bytes(decoded.image.data);
+    result["width"] = decoded.image.width;
+    result["height"] = decoded.image.height;
+    result["channels"] = decoded.image.channels;
+    result["bits"] = decoded.bits;
+    result["layout"] = decoded.planar ? "planar" : "interleaved";
+    result["maximum"] = decoded.maximum;
+    result["data_maximum"] = decoded.data_maximum;
+    return result;
+}
+
+py::dict decode_image_wrapper(uint64_t id, bool diagnostics, const ProgressCallback& progress,
+                              float bright, const std::array<double, 2>& gamma, int bits, bool half_size,
+                              int quality, bool four_color_rgb, int dcb_iterations, bool dcb_enhance,
+                              int med_passes, float auto_bright_thr, bool use_camera_wb,
+                              bool use_camera_matrix, int user_flip, bool linear, bool planar,
+                              bool dng_opcodes) {
+    DecodeOptions options;
+    options.bright = bright;
+    options.gamma = gamma;
+    options.bits = bits;
+    options.half_size = half_size;
+    options.quality = quality;
+    options.four_color_rgb = four_color_rgb;
+    options.dcb_iterations = dcb_iterations;
+    options.dcb_enhance = dcb_enhance;
+    options.med_passes = med_passes;
+    options.auto_bright_thr = auto_bright_thr;
+    options.use_camera_wb = use_camera_wb;
+    options.use_camera_matrix = use_camera_matrix;
+    options.user_flip = user_flip;
+    options.linear = linear;
+    options.planar = planar;
+    options.dng_opcodes = dng_opcodes;
+    // pybind11 reacquires the GIL whenever the wrapped Python callable is invoked.
+    DecodedImage decoded = without_gil([&] {
+        return ImageManager::instance().decode_image(id, options, diagnostics, progress);
+    });
+    py::dict result = decoded_to_dict(decoded);
+    if (decoded.diagnostics) {
+        py::dict stats;
+        stats["process_ms"] = decoded.diagnostics->process_ms;
+        stats["copy_ms"] = decoded.diagnostics->copy_ms;
+        stats["buffer_size"] = decoded.diagnostics->buffer_size;
+        result["diagnostics"] = stats;
+    }
+    return result;
+}
+
+// The callback gets (y_offset, stripe, stripe_height) with the stripe as
+// bytes; the result is the format dict of decode_image without "data".
+py::dict decode_stripes_wrapper(uint64_t id, const py::function& callback, int stripe_height, int bits,
+                                bool half_size) {
+    DecodeOptions options;
+    options.bits = bits;
+    options.half_size = half_size;
+    DecodedImage decoded = without_gil([&] {
+        return ImageManager::instance().decode_stripes(
+            id, stripe_height, [&](int y_offset, const uint8_t* data, size_t size, int rows) {
+                py::gil_scoped_acquire acquire;
+                callback(y_offset, py::bytes(reinterpret_cast<const char*>(data), size), rows);
+            },
+            options);
+    });
+    py::dict result = decoded_to_dict(decoded);
+    result.attr("pop")("data");
+    return result;
+}
+
+float read_iso_wrapper(const std::string& filepath) {
+    return ImageManager::instance().read_iso(filepath);
+}
+
+// Unknown focal lengths are None rather than 0.
+py::dict read_focal_length_wrapper(const std::string& filepath) {
+    FocalLength focal_length = without_gil([&] { return ImageManager::instance().read_focal_length(filepath); });
+    auto known = [](float value) { return value > 0.0f ? py::cast(value) : py::none(); };
+    py::dict result;
+    result["focal_length"] = known(focal_length.actual);
+    result["focal_length_35mm"] = known(focal_length.equivalent_35mm);
+    return result;
+}
+
+py::dict read_capture_flags_wrapper(const std::string& filepath) {
+    CaptureFlags flags = without_gil([&] { return ImageManager::instance().read_capture_flags(filepath); });
+    py::dict result;
+    result["flash_fired"] = flags.flash_fired;
+    result["metering_mode"] = metering_mode_name(flags.metering_mode);
+    return result;
+}
+
+// Gain maps and warps as dicts of their main parameters, each with the
+// number of the opcode list (2 or 3) it comes from; the full gain grids stay
+// in C++.
+py::dict read_dng_opcodes_wrapper(const std::string& filepath) {
+    DngOpcodeLists lists = without_gil([&] { return read_dng_opcodes(filepath); });
+    py::list gain_maps;
+    py::list warps;
+    std::vector<uint32_t> skipped;
+    auto add = [&](int number, const DngOpcodes& opcodes) {
+        for (const DngGainMap& map : opcodes.gain_maps) {
+            py::dict entry;
+            entry["list"] = number;
+            entry["area"] = std::array<int, 4>{map.top, map.left, map.bottom, map.right};
+            entry["plane"] = map.plane;
+            entry["planes"] = map.planes;
+            entry["pitch"] = std::array<int, 2>{map.row_pitch, map.col_pitch};
+            entry["points"] = std::array<int, 2>{map.points_v, map.points_h};
+            entry["map_planes"] = map.map_planes;
+            auto [min_gain, max_gain] = std::minmax_element(map.gains.begin(), map.gains.end());
+            entry["min_gain"] = *min_gain;
+            entry["max_gain"] = *max_gain;
+            gain_maps.append(entry);
+        }
+        for (const DngWarpRectilinear& warp : opcodes.warps) {
+            py::dict entry;
+            entry["list"] = number;
+            entry["coefficients"] = warp.coefficients;
+            entry["center"] = std::array<double, 2>{warp.center_x, warp.center_y};
+            warps.append(entry);
+        }
+        skipped.insert(skipped.end(), opcodes.skipped.begin(), opcodes.skipped.end());
+    };
+    add(2, lists.list2);
+    add(3, lists.list3);
+    py::dict result;
+    result["gain_maps"] = gain_maps;
+    result["warps"] = warps;
+    result["skipped"] = skipped;
+    return result;
+}
+
+py::dict load_raw_bayer_wrapper(const std::string& filepath) {
+    RawBayerData bayer = without_gil([&] { return ImageManager::instance().load_raw_bayer(filepath); });
+    py::dict result;
+    // Native-endian uint16 samples, like 16-bit decodes.
+    result["data"] = samples_to_bytes(bayer.data);
+    result["raw_width"] = bayer.raw_width;
+    result["raw_height"] = bayer.raw_height;
+    result["left_margin"] = bayer.left_margin;
+    result["top_margin"] = bayer.top_margin;
+    result["width"] = bayer.width;
+    result["height"] = bayer.height;
+    result["cfa_pattern"] = bayer.cfa_pattern;
+    result["black"] = bayer.black;
+    result["maximum"] = bayer.maximum;
+    return result;
+}
+
+py::bytes decode_to_tiff_bytes_wrapper(const std::string& filepath, int bits) {
+    return to_bytes(without_gil([&] { return ImageManager::instance().decode_to_tiff(filepath, bits); }));
+}
+
+py::dict decode_linear16_wrapper(const std::string& filepath, float bright) {
+    Linear16Image image = without_gil([&] { return ImageManager::instance().decode_linear16(filepath, bright); });
+    py::dict result;
+    result["data"] = samples_to_bytes(image.data);
+    result["width"] = image.width;
+    result["height"] = image.height;
+    result["channels"] = 3;
+    result["max_value"] = image.max_value;
+    return result;
+}
+
+py::dict load_raw_f32_wrapper(const std::string& filepath, bool normalize, bool half_size) {
+    DecodeOptions options;
+    options.half_size = half_size;
+    Float32Image image = without_gil([&] { return ImageManager::instance().load_raw_f32(filepath, normalize, options); });
+    py::dict result;
+    // Native float32 samples, e.g. for array.array("f") or numpy.frombuffer.
+    result["data"] = samples_to_bytes(image.data);
+    result["width"] = image.width;
+    result["height"] = image.height;
+    result["channels"] = image.channels;
+    return result;
+}
+
+py::dict load_raw_wb_compare_wrapper(const std::string& filepath, bool half_size) {
+    WhiteBalanceComparison comparison = without_gil([&] {
+        return ImageManager::instance().load_raw_wb_compare(filepath, half_size);
+    });
+    auto to_dict = [](const DecodedImage& decoded, const std::array<float, 4>& multipliers) {
+        py::dict result = decoded_to_dict(decoded);
+        result["multipliers"] = multipliers;
+        return result;
+    };
+    py::dict result;
+    result["camera"] = to_dict(comparison.camera, comparison.camera_multipliers);
+    result["auto"] = to_dict(comparison.automatic, comparison.auto_multipliers);
+    return result;
+}
+
+py::bytes make_contact_thumb_wrapper(const std::string& filepath, int max_dim, int quality) {
+    return to_bytes(without_gil([&] { return ImageManager::instance().make_contact_thumb(filepath, max_dim, quality); }));
+}
+
+py::bytes thumbnail_or_halfsize_wrapper(const std::string& filepath, int min_dim, int quality) {
+    return to_bytes(without_gil([&] {
+        return ImageManager::instance().thumbnail_or_halfsize(filepath, min_dim, quality);
+    }));
+}
+
+py::dict load_best_preview_wrapper(const std::string& filepath, int target_dim, int quality) {
+    BestPreview preview = without_gil([&] {
+        return ImageManager::instance().load_best_preview(filepath, target_dim, quality);
+    });
+    py::dict result;
+    result["data"] = to_bytes(preview.jpeg);
+    result["width"] = preview.width;
+    result["height"] = preview.height;
+    switch (preview.source) {
+        case PreviewSource::Thumbnail: result["source"] = "thumbnail"; break;
+        case PreviewSource::HalfSize: result["source"] = "half_size"; break;
+        case PreviewSource::Full: result["source"] = "full"; break;
+    }
+    return result;
+}
+
+void set_cache_size_wrapper(double megabytes) {
+    if (!(megabytes >= 0.0) || !std::isfinite(megabytes)) {
+        throw std::invalid_argument("Cache size must be finite and not negative");
+    }
+    // Sizes beyond the address space mean no limit; casting them would be
+    // undefined.
+    double bytes = megabytes * (1 << 20);
+    size_t capacity = bytes >= static_cast<double>(SIZE_MAX) ? SIZE_MAX : static_cast<size_t>(bytes);
+    ImageManager::instance().set_preview_cache_size(capacity);
+}
+
+size_t cache_hits_wrapper() {
+    return ImageManager::instance().preview_cache_stats().hits;
+}
+
+void clear_cache_wrapper() {
+    ImageManager::instance().clear_preview_cache();
+}
+
+py::dict cache_stats_wrapper() {
+    PreviewCacheStats stats = ImageManager::instance().preview_cache_stats();
+    py::dict result;
+    result["hits"] = stats.hits;
+    result["misses"] = stats.misses;
+    result["entries"] = stats.entries;
+    result["bytes"] = stats.bytes;
+    return result;
+}
+
+py::dict load_raw_with_timeout_wrapper(const std::string& filepath, int timeout_ms, bool half_size) {
+    DecodeOptions options;
+    options.half_size = half_size;
+    DecodedImage decoded = without_gil([&] {
+        return ImageManager::instance().load_raw_with_timeout(filepath, timeout_ms, options);
+    });
+    return decoded_to_dict(decoded);
+}
+
+// --- Image Operation Wrappers ---
+// Pixel buffers are passed as bytes together with their dimensions.
+
+py::bytes split_tone_wrapper(const py::bytes& image, int width, int height,
+                             float shadow_hue, float shadow_sat,
+                             float highlight_hue, float highlight_sat, float balance) {
+    std::vector<uint8_t> input = to_buffer(image);
+    return to_bytes(without_gil([&] {
+        return split_tone(input, width, height, shadow_hue, shadow_sat, highlight_hue, highlight_sat, balance);
+    }));
+}
+
+py::bytes color_balance_wrapper(const py::bytes& image, int width, int height,
+                                const std::array<float, 3>& shadows,
+                                const std::array<float, 3>& midtones,
+                                const std::array<float, 3>& highlights) {
+    std::vector<uint8_t> input = to_buffer(image);
+    return to_bytes(without_gil([&] { return color_balance(input, width, height, shadows, midtones, highlights); }));
+}
+
+py::bytes adjust_vibrance_wrapper(const py::bytes& image, int width, int height, float amount) {
+    std::vector<uint8_t> input = to_buffer(image);
+    return to_bytes(without_gil([&] { return adjust_vibrance(input, width, height, amount); }));
+}
+
+py::bytes denoise_wavelet_wrapper(const py::bytes& image, int width, int height, float strength) {
+    std::vector<uint8_t> input = to_buffer(image);
+    return to_bytes(without_gil([&] { return denoise_wavelet(input, width, height, strength); }));
+}
+
+py::bytes denoise_ycbcr_wrapper(const py::bytes& image, int width, int height,
+                                float luma_strength, float chroma_strength, EdgeMode edge) {
+    std::vector<uint8_t> input = to_buffer(image);
+    return to_bytes(without_gil([&] {
+        return denoise_ycbcr(input, width, height, luma_strength, chroma_strength, edge);
+    }));
+}
+
+py::bytes fix_hot_pixels_wrapper(const py::bytes& image, int width, int height, int channels, int threshold,
+                                 EdgeMode edge) {
+    std::vector<uint8_t> input = to_buffer(image);
+    return to_bytes(without_gil([&] { return fix_hot_pixels(input, width, height, channels, threshold, edge); }));
+}
+
+py::bytes adjust_brightness_wrapper(const py::bytes& image, int width, int height, int channels, float amount) {
+    std::vector<uint8_t> input = to_buffer(image);
+    return to_bytes(without_gil([&] { return adjust_brightness(input, width, height, channels, amount); }));
+}
+
+void adjust_brightness_inplace_wrapper(const py::buffer& image, int width, int height, int channels, float amount) {
+    py::buffer_info samples = writable_samples(image, width, height, channels);
+    uint8_t* data = static_cast<uint8_t*>(samples.ptr);
+    without_gil([&] { adjust_brightness_inplace(data, width, height, channels, amount); });
+}
+
+py::bytes adjust_contrast_wrapper(const py::bytes& image, int width, int height, int channels, float amount) {
+    std::vector<uint8_t> input = to_buffer(image);
+    return to_bytes(without_gil([&] { return adjust_contrast(input, width, height, channels, amount); }));
+}
+
+void adjust_contrast_inplace_wrapper(const py::buffer& image, int width, int height, int channels, float amount) {
+    py::buffer_info samples = writable_samples(image, width, height, channels);
+    uint8_t* data = static_cast<uint8_t*>(samples.ptr);
+    without_gil([&] { adjust_contrast_inplace(data, width, height, channels, amount); });
+}
+
+py::bytes adjust_gamma_wrapper(const py::bytes& image, int width, int height, int channels, float gamma) {
+    std::vector<uint8_t> input = to_buffer(image);
+    return to_bytes(without_gil([&] { return adjust_gamma(input, width, height, channels, gamma); }));
+}
+
+void adjust_gamma_inplace_wrapper(const py::buffer& image, int width, int height, int channels, float gamma) {
+    py::buffer_info samples = writable_samples(image, width, height, channels);
+    uint8_t* data = static_cast<uint8_t*>(samples.ptr);
+    without_gil([&] { adjust_gamma_inplace(data, width, height, channels, gamma); });
+}
+
+py::bytes invert_wrapper(const py::bytes& image, int width, int height, int channels) {
+    std::vector<uint8_t> input = to_buffer(image);
+    return to_bytes(without_gil([&] { return invert(input, width, height, channels); }));
+}
+
+void invert_inplace_wrapper(const py::buffer& image, int width, int height, int channels) {
+    py::buffer_info samples = writable_samples(image, width, height, channels);
+    uint8_t* data = static_cast<uint8_t*>(samples.ptr);
+    without_gil([&] { invert_inplace(data, width, height, channels); });
+}
+
+py::bytes srgb_to_linear_wrapper(const py::bytes& image, int width, int height, int channels) {
+    std::vector<uint8_t> input = to_buffer(image);
+    return to_bytes(without_gil([&] { return srgb_to_linear(input, width, height, channels); }));
+}
+
+void srgb_to_linear_inplace_wrapper(const py::buffer& image, int width, int height, int channels) {
+    py::buffer_info samples = writable_samples(image, width, height, channels);
+    uint8_t* data = static_cast<uint8_t*>(samples.ptr);
+    without_gil([&] { srgb_to_linear_inplace(data, width, height, channels); });
+}
+
+py::bytes linear_to_srgb_wrapper(const py::bytes& image, int width, int height, int channels) {
+    std::vector<uint8_t> input = to_buffer(image);
+    return to_bytes(without_gil([&] { return linear_to_srgb(input, width, height, channels); }));
+}
+
+void linear_to_srgb_inplace_wrapper(const py::buffer& image, int width, int height, int channels) {
+    py::buffer_info samples = writable_samples(image, width, height, channels);
+    uint8_t* data = static_cast<uint8_t*>(samples.ptr);
+    without_gil([&] { linear_to_srgb_inplace(data, width, height, channels); });
+}
+
+py::bytes sharpen_wrapper(const py::bytes& image, int width, int height, int channels, float amount,
+                          float radius, EdgeMode edge) {
+    std::vector<uint8_t> input = to_buffer(image);
+    return to_bytes(without_gil([&] { return sharpen(input, width, height, channels, amount, radius, edge); }));
+}
+
+py::bytes sharpen_masked_wrapper(const py::bytes& image, int width, int height, float amount, float radius,
+                                 float threshold, EdgeMode edge) {
+    std::vector<uint8_t> input = to_buffer(image);
+    return to_bytes(without_gil([&] {
+        return sharpen_masked(input, width, height, amount, radius, threshold, edge);
+    }));
+}
+
+py::bytes box_blur_wrapper(const py::bytes& image, int width, int height, int channels, int radius,
+                           EdgeMode edge) {
+    std::vector<uint8_t> input = to_buffer(image);
+    return to_bytes(without_gil([&] { return box_blur(input, width, height, channels, radius, edge); }));
+}
+
+py::bytes crop_image_wrapper(const py::bytes& image, int width, int height, int channels,
+                             const std::array<int, 4>& rect) {
+    std::vector<uint8_t> input = to_buffer(image);
+    CropRect crop{rect[0], rect[1], rect[2], rect[3]};
+    return to_bytes(without_gil([&] { return crop_image(input, width, height, channels, crop); }));
+}
+
+py::bytes resize_image_wrapper(const py::bytes& image, int width, int height, int channels,
+                               int new_width, int new_height, bool linear_light) {
+    std::vector<uint8_t> input = to_buffer(image);
+    return to_bytes(without_gil([&] {
+        return resize_image(input, width, height, channels, new_width, new_height, linear_light);
+    }));
+}
+
+py::bytes seam_carve_wrapper(const py::bytes& image, int width, int height, int channels,
+                             int target_width, int target_height, EdgeMode edge) {
+    std::vector<uint8_t> input = to_buffer(image);
+    return to_bytes(without_gil([&] {
+        return seam_carve(input, width, height, channels, target_width, target_height, edge);
+    }));
+}
+
+py::tuple orient_image_wrapper(const py::bytes& image, int width, int height, int channels, int flip) {
+    std::vector<uint8_t> input = to_buffer(image);
+    ImageBuffer oriented = without_gil([&] { return orient_image(input, width, height, channels, flip); });
+    return py::make_tuple(to_bytes(oriented.data), oriented.width, oriented.height);
+}
+
+py::tuple normalize_orientation_wrapper(const py::bytes& image, int width, int height, int channels,
+                                        int orientation) {
+    std::vector<uint8_t> input = to_buffer(image);
+    ImageBuffer upright = without_gil([&] {
+        return normalize_orientation(input, width, height, channels, orientation);
+    });
+    return py::make_tuple(to_bytes(upright.data), upright.width, upright.height);
+}
+
+std::vector<float> sample_bilinear_wrapper(const py::bytes& image, int width, int height, int channels,
+                                           float fx, float fy) {
+    return sample_bilinear(to_buffer(image), width, height, channels, fx, fy);
+}
+
+std::vector<float> sample_bicubic_wrapper(const py::bytes& image, int width, int height, int channels,
+                                          float fx, float fy) {
+    return sample_bicubic(to_buffer(image), width, height, channels, fx, fy);
+}
+
+py::list build_pyramid_wrapper(const py::bytes& image, int width, int height, int channels, int levels) {
+    std::vector<uint8_t> input = to_buffer(image);
+    std::vector<ImageBuffer> pyramid = without_gil([&] { return build_pyramid(input, width, height, channels, levels); });
+    py::list result;
+    for (const ImageBuffer& level : pyramid) {
+        result.append(py::make_tuple(to_bytes(level.data), level.width, level.height));
+    }
+    return result;
+}
+
+py::bytes blend_laplacian_wrapper(const py::bytes& a, const py::bytes& b, const py::bytes& mask,
+                                  int width, int height, int channels, int levels) {
+    std::vector<uint8_t> input_a = to_buffer(a);
+    std::vector<uint8_t> input_b = to_buffer(b);
+    std::vector<uint8_t> input_mask = to_buffer(mask);
+    return to_bytes(without_gil([&] {
+        return blend_laplacian(input_a, input_b, input_mask, width, height, channels, levels);
+    }));
+}
+
+std::vector<std::vector<uint8_t>> to_buffers(const std::vector<py::bytes>& frames) {
+    std::vector<std::vector<uint8_t>> buffers;
+    buffers.reserve(frames.size());
+    for (const py::bytes& frame : frames) {
+        buffers.push_back(to_buffer(frame));
+    }
+    return buffers;
+}
+
+py::bytes average_stack_wrapper(const std::vector<py::bytes>& frames, int width, int height, int channels) {
+    std::vector<std::vector<uint8_t>> inputs = to_buffers(frames);
+    return to_bytes(without_gil([&] { return average_stack(inputs, width, height, channels); }));
+}
+
+py::bytes stack_wrapper(const std::vector<py::bytes>& frames, int width, int height, int channels, StackMode mode) {
+    std::vector<std::vector<uint8_t>> inputs = to_buffers(frames);
+    return to_bytes(without_gil([&] { return stack(inputs, width, height, channels, mode); }));
+}
+
+py::bytes focus_stack_wrapper(const std::vector<py::bytes>& frames, int width, int height, int channels,
+                              EdgeMode edge) {
+    std::vector<std::vector<uint8_t>> inputs = to_buffers(frames);
+    return to_bytes(without_gil([&] { return focus_stack(inputs, width, height, channels, edge); }));
+}
+
+py::bytes subtract_dark_frame_wrapper(const py::bytes& image, const py::bytes& dark, int width, int height,
+                                      int channels) {
+    std::vector<uint8_t> input = to_buffer(image);
+    std::vector<uint8_t> dark_frame = to_buffer(dark);
+    return to_bytes(without_gil([&] {
+        return subtract_dark_frame(input, dark_frame, width, height, channels);
+    }));
+}
+
+py::bytes flat_field_correct_wrapper(const py::bytes& image, const py::bytes& flat, int width, int height,
+                                     int channels) {
+    std::vector<uint8_t> input = to_buffer(image);
+    std::vector<uint8_t> flat_frame = to_buffer(flat);
+    return to_bytes(without_gil([&] {
+        return flat_field_correct(input, flat_frame, width, height, channels);
+    }));
+}
+
+py::bytes merge_hdr_wrapper(const std::vector<py::bytes>& frames, const std::vector<float>& exposures,
+                            int width, int height) {
+    std::vector<std::vector<uint16_t>> inputs;
+    inputs.reserve(frames.size());
+    for (const py::bytes& frame : frames) {
+        inputs.push_back(to_samples<uint16_t>(frame));
+    }
+    return samples_to_bytes(without_gil([&] { return merge_hdr(inputs, exposures, width, height); }));
+}
+
+py::bytes tonemap_wrapper(const py::bytes& radiance, int width, int height, ToneMapOperator op,
+                          float key, float white, float bias, float base_stops, float detail) {
+    std::vector<float> input = to_samples<float>(radiance);
+    ToneMapParams params;
+    params.key = key;
+    params.white = white;
+    params.bias = bias;
+    params.base_stops = base_stops;
+    params.detail = detail;
+    return to_bytes(without_gil([&] { return tonemap(input, width, height, op, params); }));
+}
+
+py::bytes develop_wrapper(const py::bytes& image, int width, int height, const DevelopSettings& settings) {
+    std::vector<uint8_t> input = to_buffer(image);
+    return to_bytes(without_gil([&] { return develop(input, width, height, settings); }));
+}
+
+// Lets Python pass plain dicts such as {"exposure": 0.5}; missing keys keep
+// their defaults. Unknown keys are rejected so typos do not go unnoticed,
+// unless `ignore_unknown` is set for presets written by other versions.
+DevelopSettings develop_settings_from_dict(const py::dict& values, bool ignore_unknown = false) {
+    DevelopSettings settings;
+    for (auto item : values) {
+        std::string name = py::str(item.first);
+        if (!py::isinstance<py::float_>(item.second) && !py::isinstance<py::int_>(item.second)) {
+            throw std::invalid_argument("Develop setting " + name + " must be a number");
+        }
+        float number = item.second.cast<float>();
+        if (name == "exposure") settings.exposure = number;
+        else if (name == "contrast") settings.contrast = number;
+        else if (name == "saturation") settings.saturation = number;
+        else if (name == "temp") settings.temp = number;
+        else if (name == "tint") settings.tint = number;
+        else if (name == "highlights") settings.highlights = number;
+        else if (name == "shadows") settings.shadows = number;
+        else if (name == "sharpen") settings.sharpen = number;
+        else if (!ignore_unknown) throw std::invalid_argument("Unknown develop setting: " + name);
+    }
+    return settings;
+}
+
+py::bytes develop_dict_wrapper(const py::bytes& image, int width, int height, const py::dict& settings) {
+    return develop_wrapper(image, width, height, develop_settings_from_dict(settings));
+}
+
+// Presets are small JSON objects with the DevelopSettings field names. They
+// are parsed with Python's json module; a JSONDecodeError is a ValueError.
+py::bytes develop_from_json_wrapper(const py::bytes& image, int width, int height, const std::string& json) {
+    py::object parsed = py::module_::import("json").attr("loads")(json);
+    if (!py::isinstance<py::dict>(parsed)) {
+        throw std::invalid_argument("A develop preset must be a JSON object");
+    }
+    return develop_wrapper(image, width, height, develop_settings_from_dict(parsed.cast<py::dict>(), true));
+}
+
+std::vector<py::bytes> develop_batch_wrapper(const std::vector<std::string>& filepaths,
+                                             const DevelopSettings& settings, int quality) {
+    std::vector<std::vector<uint8_t>> jpegs = without_gil([&] {
+        return ImageManager::instance().develop_batch(filepaths, settings, quality);
+    });
+    std::vector<py::bytes> result;
+    result.reserve(jpegs.size());
+    for (const std::vector<uint8_t>& jpeg : jpegs) {
+        result.push_back(to_bytes(jpeg));
+    }
+    return result;
+}
+
+std::vector<py::bytes> develop_batch_dict_wrapper(const std::vector<std::string>& filepaths,
+                                                  const py::dict& settings, int quality) {
+    return develop_batch_wrapper(filepaths, develop_settings_from_dict(settings), quality);
+}
+
+py::dict begin_develop_wrapper(const std::string& filepath, const DevelopSettings& settings) {
+    DevelopPreview started = without_gil([&] { return ImageManager::instance().begin_develop(filepath, settings); });
+    py::dict result;
+    result["data"] = to_bytes(started.preview.data);
+    result["width"] = started.preview.width;
+    result["height"] = started.preview.height;
+    result["session"] = started.session;
+    return result;
+}
+
+py::dict begin_develop_dict_wrapper(const std::string& filepath, const py::dict& settings) {
+    return begin_develop_wrapper(filepath, develop_settings_from_dict(settings));
+}
+
+py::dict finish_develop_wrapper(uint64_t session) {
+    ImageBuffer image = without_gil([&] { return ImageManager::instance().finish_develop(session); });
+    py::dict result;
+    result["data"] = to_bytes(image.data);
+    result["width"] = image.width;
+    result["height"] = image.height;
+    return result;
+}
+
+std::string develop_settings_to_json_wrapper(const DevelopSettings& settings) {
+    py::dict values;
+    values["exposure"] = settings.exposure;
+    values["contrast"] = settings.contrast;
+    values["saturation"] = settings.saturation;
+    values["temp"] = settings.temp;
+    values["tint"] = settings.tint;
+    values["highlights"] = settings.highlights;
+    values["shadows"] = settings.shadows;
+    values["sharpen"] = settings.sharpen;
+    return py::str(py::module_::import("json").attr("dumps")(values));
+}
+
+float suggest_denoise_strength_wrapper(float iso) {
+    return suggest_denoise_strength(iso);
+}
+
+float suggest_exposure_wrapper(const py::bytes& image, int width, int height) {
+    std::vector<uint8_t> input = to_buffer(image);
+    return without_gil([&] { return suggest_exposure(input, width, height); });
+}
+
+std::optional<std::array<float, 3>> sample_neutral_multipliers_wrapper(const py::bytes& image, int width, int height,
+                                                                       int x, int y, int radius) {
+    std::vector<uint8_t> input = to_buffer(image);
+    return without_gil([&] { return sample_neutral_multipliers(input, width, height, x, y, radius); });
+}
+
+py::list image_stats_wrapper(const py::bytes& image, int width, int height, int channels,
+                             uint8_t low, uint8_t high) {
+    std::vector<uint8_t> input = to_buffer(image);
+    std::vector<ChannelStats> stats = without_gil([&] {
+        return image_stats(input, width, height, channels, low, high);
+    });
+    py::list result;
+    for (const ChannelStats& channel : stats) {
+        py::dict entry;
+        entry["mean"] = channel.mean;
+        entry["min"] = channel.min;
+        entry["max"] = channel.max;
+        entry["clipped_low"] = channel.clipped_low;
+        entry["clipped_high"] = channel.clipped_high;
+        entry["clipped_low_percent"] = channel.clipped_low_percent;
+        entry["clipped_high_percent"] = channel.clipped_high_percent;
+        result.append(entry);
+    }
+    return result;
+}
+
+uint64_t phash_wrapper(const py::bytes& image, int width, int height) {
+    std::vector<uint8_t> input = to_buffer(image);
+    return without_gil([&] { return phash(input, width, height); });
+}
+
+py::bytes render_histogram_wrapper(const py::bytes& image, int width, int height, int channels,
+                                   int out_width, int out_height) {
+    std::vector<uint8_t> input = to_buffer(image);
+    return to_bytes(without_gil([&] {
+        return render_histogram(input, width, height, channels, out_width, out_height);
+    }));
+}
+
+py::bytes render_waveform_wrapper(const py::bytes& image, int width, int height, int out_height) {
+    std::vector<uint8_t> input = to_buffer(image);
+    return to_bytes(without_gil([&] { return render_waveform(input, width, height, out_height); }));
+}
+
+py::bytes render_vectorscope_wrapper(const py::bytes& image, int width, int height, int size) {
+    std::vector<uint8_t> input = to_buffer(image);
+    return to_bytes(without_gil([&] { return render_vectorscope(input, width, height, size); }));
+}
+
+// Runs a Python callable over the image for prototyping filters without
+// rebuilding the backend. The callable receives `bytes` holding up to
+// `batch_pixels` interleaved pixels and must return `bytes` of the same
+// length. Even with batching every call goes through the interpreter, so this
+// is orders of magnitude slower than a native operation.
+py::bytes apply_python_filter_wrapper(const py::bytes& image, int width, int height, int channels,
+                                      const py::function& func, int batch_pixels) {
+    if (width <= 0 || height <= 0 || channels <= 0) {
+        throw std::invalid_argument("Image dimensions and channel count must be positive");
+    }
+    if (batch_pixels <= 0) {
+        throw std::invalid_argument("Batch size must be positive");
+    }
+    std::string_view input = image;
+    size_t expected = static_cast<size_t>(width) * height * channels;
+    if (input.size() != expected) {
+        throw std::invalid_argument("Buffer size " + std::to_string(input.size()) +
+                                    " does not match " + std::to_string(width) + "x" +
+                                    std::to_string(height) + "x" + std::to_string(channels));
+    }
+
+    std::string output;
+    output.reserve(expected);
+    size_t batch_size = static_cast<size_t>(batch_pixels) * channels;
+    for (size_t offset = 0; offset < expected; offset += batch_size) {
+        size_t length = std::min(batch_size, expected - offset);
+        py::object returned = func(py::bytes(input.data() + offset, length));
+        if (!py::isinstance<py::bytes>(returned)) {
+            throw py::type_error("Filter callable must return bytes");
+        }
+        std::string_view batch = returned.cast<py::bytes>();
+        if (batch.size() != length) {
+            throw std::invalid_argument("Filter callable returned " + std::to_string(batch.size()) +
+                                        " bytes for a batch of " + std::to_string(length));
+        }
+        output.append(batch);
+    }
+    return py::bytes(output);
+}
+
+// Whole-buffer color conversions; the per-pixel variants are bound directly.
+std::vector<float> rgb_buffer_to_ycbcr_wrapper(const py::bytes& image, int width, int height) {
+    return rgb_to_ycbcr(to_buffer(image), width, height);
+}
+
+py::bytes ycbcr_buffer_to_rgb_wrapper(const std::vector<float>& data, int width, int height) {
+    return to_bytes(ycbcr_to_rgb(data, width, height));
+}
+
+std::vector<float> rgb_buffer_to_hsl_wrapper(const py::bytes& image, int width, int height) {
+    return rgb_to_hsl(to_buffer(image), width, height);
+}
+
+py::bytes hsl_buffer_to_rgb_wrapper(const std::vector<float>& data, int width, int height) {
+    return to_bytes(hsl_to_rgb(data, width, height));
+}
+
+std::vector<float> rgb_buffer_to_hsv_wrapper(const py::bytes& image, int width, int height) {
+    return rgb_to_hsv(to_buffer(image), width, height);
+}
+
+py::bytes hsv_buffer_to_rgb_wrapper(const std::vector<float>& data, int width, int height) {
+    return to_bytes(hsv_to_rgb(data, width, height));
+}
+
+std::vector<float> rgb_buffer_to_lab_wrapper(const py::bytes& image, int width, int height) {
+    return rgb_to_lab(to_buffer(image), width, height);
+}
+
+py::bytes lab_buffer_to_rgb_wrapper(const std::vector<float>& data, int width, int height) {
+    return to_bytes(lab_to_rgb(data, width, height));
+}
+
+py::tuple multipliers_to_temp_tint_wrapper(const Color& multipliers) {
+    TempTint result = multipliers_to_temp_tint(multipliers);
+    return py::make_tuple(result.temperature, result.tint);
+}
+
+// --- Encoding Wrappers ---
+
+std::string preview_jpeg_base64_wrapper(const py::bytes& image, int width, int height, int quality) {
+    return preview_jpeg_base64(to_buffer(image), width, height, quality);
+}
+
+// Reads the keys of a get_metadata dict that go into EXIF; missing keys stay
+// empty, so callers can pass just the values they have.
+Metadata metadata_from_dict(const py::dict& meta) {
+    Metadata result;
+    if (meta.contains("make")) result.make = meta["make"].cast<std::string>();
+    if (meta.contains("model")) result.model = meta["model"].cast<std::string>();
+    if (meta.contains("iso")) result.iso_speed = meta["iso"].cast<float>();
+    if (meta.contains("shutter")) result.shutter = meta["shutter"].cast<float>();
+    if (meta.contains("aperture")) result.aperture = meta["aperture"].cast<float>();
+    if (meta.contains("timestamp")) result.timestamp = meta["timestamp"].cast<int64_t>();
+    return result;
+}
+
+py::bytes encode_jpeg_wrapper(const py::bytes& image, int width, int height, int channels, int quality,
+                              ChromaSubsampling subsampling, const std::optional<py::dict>& metadata,
+                              const py::bytes& icc_profile, int dpi) {
+    std::vector<uint8_t> input = to_buffer(image);
+    std::vector<uint8_t> profile = to_buffer(icc_profile);
+    std::optional<Metadata> exif;
+    if (metadata) {
+        exif = metadata_from_dict(*metadata);
+    }
+    return to_bytes(without_gil([&] {
+        return encode_jpeg(input, width, height, channels, quality, subsampling, exif, profile, dpi);
+    }));
+}
+
+py::bytes encode_tiff_wrapper(const py::bytes& image, int width, int height, int channels, int bits,
+                              const py::bytes& icc_profile, int dpi) {
+    std::vector<uint8_t> input = to_buffer(image);
+    std::vector<uint8_t> profile = to_buffer(icc_profile);
+    return to_bytes(without_gil([&] { return encode_tiff(input, width, height, channels, bits, profile, dpi); }));
+}
+
+py::bytes export_region_wrapper(const py::bytes& image, int width, int height, int channels,
+                                const std::array<int, 4>& crop, int out_width, int out_height,
+                                float sharpen_amount, ExportFormat format, int quality, int dpi) {
+    std::vector<uint8_t> input = to_buffer(image);
+    CropRect rect{crop[0], crop[1], crop[2], crop[3]};
+    return to_bytes(without_gil([&] {
+        return export_region(input, width, height, channels, rect, out_width, out_height, sharpen_amount,
+                             format, quality, dpi);
+    }));
+}
+
+void write_tiff_wrapper(const std::string& filepath, const py::bytes& image, int width, int height,
+                        int channels, int bits, const py::bytes& icc_profile, int dpi) {
+    std::vector<uint8_t> input = to_buffer(image);
+    std::vector<uint8_t> profile = to_buffer(icc_profile);
+    without_gil([&] { write_tiff(filepath, input, width, height, channels, bits, profile, dpi); });
+}
+
+py::bytes srgb_icc_profile_wrapper() {
+    return to_bytes(srgb_icc_profile());
+}
+
+// --- Sidecar Wrappers ---
+
+py::dict read_xmp_sidecar_wrapper(const std::string& raw_path) {
+    XmpSidecar sidecar = read_xmp_sidecar(raw_path);
+    py::dict result;
+    result["rating"] = sidecar.rating;
+    result["label"] = sidecar.label;
+    result["keywords"] = sidecar.keywords;
+    return result;
+}
+
+// --- Image Class Wrappers ---
+// PyImage wraps Image. Its edits return the C++ object itself, which pybind11
+// maps back to the existing Python object, so chained calls act on one image.
+// Edits are queued until render(), save(), or reading `data`.
+
+Image image_from_bytes(const py::bytes& data, int width, int height, int channels) {
+    return Image(to_buffer(data), width, height, channels);
+}
+
+Image image_from_handle(uint64_t id, bool half_size) {
+    DecodeOptions options;
+    options.half_size = half_size;
+    DecodedImage decoded = without_gil([&] { return ImageManager::instance().decode_image(id, options); });
+    return Image(std::move(decoded.image.data), decoded.image.width, decoded.image.height,
+                 decoded.image.channels);
+}
+
+py::bytes image_data(Image& image) {
+    return to_bytes(without_gil([&] { return image.data(); }));
+}
+
+Image& image_crop(Image& image, int x, int y, int width, int height) {
+    return image.crop(CropRect{x, y, width, height});
+}
 
 PYBIND11_MODULE(cpp_backend_python_bindings, m) {
     m.doc() = "C++ backend for MPR Photo Editor using LibRaw";
+    py::register_exception<DecodeTimeout>(m, "DecodeTimeout", PyExc_TimeoutError);
     m.def("get_libraw_version", &get_libraw_version_wrapper, "Returns the LibRaw version string");
-    m.def("load_raw_image", &load_raw_image_wrapper, "Loads a raw image and returns a handle ID");
+    m.def("get_supported_extensions", &get_supported_extensions_wrapper, "Returns the lowercase RAW file extensions (without dot) the backend can open");
+    m.def("get_supported_camera_count", &get_supported_camera_count_wrapper, "Returns the number of camera models LibRaw supports");
+    m.def("get_supported_cameras", &get_supported_cameras_wrapper, "Returns the names of the camera models LibRaw supports");
+    m.def("load_raw_image", &load_raw_image_wrapper, "Loads a raw image and returns a handle ID",
+          py::call_guard<py::gil_scoped_release>());
+    m.def("load_raw_image_from_stream", &load_raw_image_from_stream_wrapper, "Loads a raw image from a file-like object with read(), such as io.BytesIO, and returns a handle ID",
+          py::arg("stream"));
     m.def("release_raw_image", &release_raw_image_wrapper, "Releases a raw image handle");
-    m.def("get_thumbnail", &get_thumbnail_wrapper, "Extracts the thumbnail from a raw image");
+    m.def("set_max_decode_pixels", &set_max_decode_pixels_wrapper, "Rejects raw files whose sensor has more than this many pixels before reading their data; 0 removes the limit",
+          py::arg("pixels"));
+    m.def("get_max_decode_pixels", &get_max_decode_pixels_wrapper, "Returns the sensor pixel limit for raw files; 0 means no limit");
+    m.def("get_open_image_count", &get_open_image_count_wrapper, "Returns the number of raw image handles currently held");
+    m.def("get_thumbnail", &get_thumbnail_wrapper, "Extracts the thumbnail from a raw image, rotated upright unless apply_orientation is False",
+          py::arg("id"), py::arg("apply_orientation") = true);
+    m.def("list_thumbnails", &list_thumbnails_wrapper, "Lists the previews embedded in a raw file as dicts with width, height, format, and size",
+          py::arg("filepath"));
+    m.def("extract_thumbnail_index", &extract_thumbnail_index_wrapper, "Extracts one of the embedded previews of a raw file by its index in list_thumbnails",
+          py::arg("filepath"), py::arg("index"), py::arg("apply_orientation") = true);
     m.def("get_metadata", &get_metadata_wrapper, "Extracts metadata from a raw image");
+    m.def("decode_image", &decode_image_wrapper, "Demosaics and color converts a raw image into a pixel buffer",
+          py::arg("id"), py::arg("diagnostics") = false, py::arg("progress") = nullptr,
+          py::arg("bright") = 1.0f, py::arg("gamma") = std::array<double, 2>{0.45, 4.5},
+          py::arg("bits") = 8, py::arg("half_size") = false, py::arg("quality") = -1,
+          py::arg("four_color_rgb") = false, py::arg("dcb_iterations") = -1, py::arg("dcb_enhance") = false,
+          py::arg("med_passes") = 0, py::arg("auto_bright_thr") = 0.01f, py::arg("use_camera_wb") = false,
+          py::arg("use_camera_matrix") = true, py::arg("user_flip") = -1, py::arg("linear") = false,
+          py::arg("planar") = false, py::arg("dng_opcodes") = false);
+    m.def("decode_stripes", &decode_stripes_wrapper, "Decodes a raw image and passes it to callback(y_offset, stripe, stripe_height) in stripes of rows instead of returning one buffer; returns the format",
+          py::arg("id"), py::arg("callback"), py::arg("stripe_height") = 256, py::arg("bits") = 8,
+          py::arg("half_size") = false);
+    m.def("read_iso", &read_iso_wrapper, "Reads the ISO speed of a raw file without decoding it",
+          py::call_guard<py::gil_scoped_release>());
+    m.def("read_focal_length", &read_focal_length_wrapper, "Reads the focal length and its 35mm equivalent in millimetres from a raw file, None where unknown",
+          py::arg("filepath"));
+    m.def("read_capture_flags", &read_capture_flags_wrapper, "Reads whether the flash fired and the metering mode (e.g. \"spot\", \"pattern\") from a raw file",
+          py::arg("filepath"));
+    m.def("read_dng_opcodes", &read_dng_opcodes_wrapper, "Lists the gain maps and lens distortion corrections in a DNG file's opcode lists, and the IDs of unsupported opcodes",
+          py::arg("filepath"));
+    m.def("load_raw_bayer", &load_raw_bayer_wrapper, "Reads the undemosaiced, sensor-linear 16-bit Bayer data and CFA pattern of a raw file",
+          py::arg("filepath"));
+    m.def("decode_to_tiff_bytes", &decode_to_tiff_bytes_wrapper, "Decodes a raw file into a complete uncompressed TIFF file in memory",
+          py::arg("filepath"), py::arg("bits") = 16);
+    m.def("decode_linear16", &decode_linear16_wrapper, "Decodes a raw file to linear 16-bit RGB with camera white balance and no auto-brightness, for HDR merging",
+          py::arg("filepath"), py::arg("bright") = 1.0f);
+    m.def("load_raw_f32", &load_raw_f32_wrapper, "Decodes a raw file to float32 samples, scaled to [0, 1] if normalize is set, else with their 16-bit values",
+          py::arg("filepath"), py::arg("normalize") = true, py::arg("half_size") = false);
+    m.def("load_raw_wb_compare", &load_raw_wb_compare_wrapper, "Decodes a raw file with camera and with auto white balance for comparison",
+          py::arg("filepath"), py::arg("half_size") = true);
+    m.def("make_contact_thumb", &make_contact_thumb_wrapper, "Decodes a raw file at half size and returns a JPEG no larger than max_dim",
+          py::arg("filepath"), py::arg("max_dim"), py::arg("quality") = 85);
+    m.def("load_best_preview", &load_best_preview_wrapper, "Returns the fastest of embedded preview, half-size decode, and full decode whose longer side reaches target_dim, as a dict with the JPEG data, width, height, and source",
+          py::arg("filepath"), py::arg("target_dim"), py::arg("quality") = 90);
+    m.def("set_cache_size", &set_cache_size_wrapper, "Sets how many megabytes of previews load_best_preview and thumbnail_or_halfsize keep cached; 0 disables the cache",
+          py::arg("megabytes"));
+    m.def("cache_hits", &cache_hits_wrapper, "Returns how many previews have been served from the preview cache");
+    m.def("clear_cache", &clear_cache_wrapper, "Drops all cached previews, keeping the hit and miss counts");
+    m.def("cache_stats", &cache_stats_wrapper, "Returns the preview cache's hits, misses, entries, and bytes used as a dict");
+    m.def("load_raw_with_timeout", &load_raw_with_timeout_wrapper, "Decodes a raw file like decode_image, raising DecodeTimeout (a TimeoutError) if it takes longer than timeout_ms",
+          py::arg("filepath"), py::arg("timeout_ms"), py::arg("half_size") = false);
+    m.def("thumbnail_or_halfsize", &thumbnail_or_halfsize_wrapper, "Returns the upright embedded preview as a JPEG if its longer side is at least min_dim, else a half-size decode",
+          py::arg("filepath"), py::arg("min_dim"), py::arg("quality") = 90);
+
+    // Image operations
+    m.def("split_tone", &split_tone_wrapper, "Tints shadows and highlights of an RGB image with separate hues",
+          py::arg("image"), py::arg("width"), py::arg("height"),
+          py::arg("shadow_hue"), py::arg("shadow_sat"),
+          py::arg("highlight_hue"), py::arg("highlight_sat"), py::arg("balance") = 0.0f);
+    m.def("color_balance", &color_balance_wrapper, "Shifts colors in the shadows, midtones, and highlights of an RGB image",
+          py::arg("image"), py::arg("width"), py::arg("height"),
+          py::arg("shadows"), py::arg("midtones"), py::arg("highlights"));
+    m.def("adjust_vibrance", &adjust_vibrance_wrapper, "Changes saturation most for muted colors and least for saturated colors and skin tones, keeping luminance",
+          py::arg("image"), py::arg("width"), py::arg("height"), py::arg("amount"));
+    m.def("adjust_brightness", &adjust_brightness_wrapper, "Adds amount * 255 to every sample",
+          py::arg("image"), py::arg("width"), py::arg("height"), py::arg("channels"), py::arg("amount"));
+    m.def("adjust_brightness_inplace", &adjust_brightness_inplace_wrapper, "Adds amount * 255 to every sample of a writable buffer in place",
+          py::arg("image"), py::arg("width"), py::arg("height"), py::arg("channels"), py::arg("amount"));
+    m.def("adjust_contrast", &adjust_contrast_wrapper, "Scales the distance of every sample from middle gray by 1 + amount",
+          py::arg("image"), py::arg("width"), py::arg("height"), py::arg("channels"), py::arg("amount"));
+    m.def("adjust_contrast_inplace", &adjust_contrast_inplace_wrapper, "Scales the distance of every sample from middle gray by 1 + amount in place",
+          py::arg("image"), py::arg("width"), py::arg("height"), py::arg("channels"), py::arg("amount"));
+    m.def("adjust_gamma", &adjust_gamma_wrapper, "Raises normalized samples to the power 1 / gamma",
+          py::arg("image"), py::arg("width"), py::arg("height"), py::arg("channels"), py::arg("gamma"));
+    m.def("adjust_gamma_inplace", &adjust_gamma_inplace_wrapper, "Raises normalized samples to the power 1 / gamma in place",
+          py::arg("image"), py::arg("width"), py::arg("height"), py::arg("channels"), py::arg("gamma"));
+    m.def("invert", &invert_wrapper, "Inverts every sample",
+          py::arg("image"), py::arg("width"), py::arg("height"), py::arg("channels"));
+    m.def("invert_inplace", &invert_inplace_wrapper, "Inverts every sample of a writable buffer in place",
+          py::arg("image"), py::arg("width"), py::arg("height"), py::arg("channels"));
+    m.def("srgb_to_linear", &srgb_to_linear_wrapper, "Decodes sRGB samples to linear light (0-255) with the piecewise sRGB curve",
+          py::arg("image"), py::arg("width"), py::arg("height"), py::arg("channels"));
+    m.def("srgb_to_linear_inplace", &srgb_to_linear_inplace_wrapper, "Decodes sRGB samples of a writable buffer to linear light in place",
+          py::arg("image"), py::arg("width"), py::arg("height"), py::arg("channels"));
+    m.def("linear_to_srgb", &linear_to_srgb_wrapper, "Encodes linear light samples (0-255) with the piecewise sRGB curve",
+          py::arg("image"), py::arg("width"), py::arg("height"), py::arg("channels"));
+    m.def("linear_to_srgb_inplace", &linear_to_srgb_inplace_wrapper, "Encodes linear light samples of a writable buffer with the sRGB curve in place",
+          py::arg("image"), py::arg("width"), py::arg("height"), py::arg("channels"));
+    py::enum_<EdgeMode>(m, "EdgeMode")
+        .value("CLAMP", EdgeMode::Clamp)
+        .value("REFLECT", EdgeMode::Reflect)
+        .value("WRAP", EdgeMode::Wrap)
+        .value("CONSTANT", EdgeMode::Constant);
+    m.def("box_blur", &box_blur_wrapper, "Box-blurs an image in constant time per pixel",
+          py::arg("image"), py::arg("width"), py::arg("height"), py::arg("channels"), py::arg("radius"),
+          py::arg("edge") = EdgeMode::Clamp);
+    m.def("sharpen", &sharpen_wrapper, "Sharpens an image with an unsharp mask of the given amount and Gaussian radius",
+          py::arg("image"), py::arg("width"), py::arg("height"), py::arg("channels"), py::arg("amount"),
+          py::arg("radius") = 1.0f, py::arg("edge") = EdgeMode::Clamp);
+    m.def("sharpen_masked", &sharpen_masked_wrapper, "Sharpens the luminance of an RGB image only where its gradient shows detail above the threshold",
+          py::arg("image"), py::arg("width"), py::arg("height"), py::arg("amount"), py::arg("radius") = 1.0f,
+          py::arg("threshold") = 0.05f, py::arg("edge") = EdgeMode::Clamp);
+    m.def("denoise_wavelet", &denoise_wavelet_wrapper, "Reduces luminance noise of an RGB image with Haar wavelets",
+          py::arg("image"), py::arg("width"), py::arg("height"), py::arg("strength") = 1.0f);
+    m.def("denoise_ycbcr", &denoise_ycbcr_wrapper, "Smooths luma and chroma of an RGB image separately in YCbCr",
+          py::arg("image"), py::arg("width"), py::arg("height"),
+          py::arg("luma_strength"), py::arg("chroma_strength"), py::arg("edge") = EdgeMode::Clamp);
+    m.def("fix_hot_pixels", &fix_hot_pixels_wrapper, "Replaces samples that stand out from their 8 neighbours by more than the threshold with the neighbours' median",
+          py::arg("image"), py::arg("width"), py::arg("height"), py::arg("channels"), py::arg("threshold"),
+          py::arg("edge") = EdgeMode::Clamp);
+    m.def("crop_image", &crop_image_wrapper, "Copies the pixels inside an (x, y, width, height) rectangle",
+          py::arg("image"), py::arg("width"), py::arg("height"), py::arg("channels"), py::arg("rect"));
+    m.def("resize_image", &resize_image_wrapper, "Resamples an image to new dimensions by area averaging, of linear light if linear_light is set",
+          py::arg("image"), py::arg("width"), py::arg("height"), py::arg("channels"),
+          py::arg("new_width"), py::arg("new_height"), py::arg("linear_light") = false);
+    m.def("seam_carve", &seam_carve_wrapper, "Narrows (and with target_height > 0 shortens) an image by removing low-energy seams",
+          py::arg("image"), py::arg("width"), py::arg("height"), py::arg("channels"),
+          py::arg("target_width"), py::arg("target_height") = 0, py::arg("edge") = EdgeMode::Clamp);
+    m.def("straighten_angle", &straighten_angle, "Returns the counter-clockwise rotation in degrees that levels the line through two points",
+          py::arg("x0"), py::arg("y0"), py::arg("x1"), py::arg("y1"));
+    m.def("orient_image", &orient_image_wrapper, "Rotates/mirrors an image by a LibRaw flip value, returning (buffer, width, height)",
+          py::arg("image"), py::arg("width"), py::arg("height"), py::arg("channels"), py::arg("flip"));
+    m.def("normalize_orientation", &normalize_orientation_wrapper, "Turns an image with an EXIF orientation (0-8) upright, returning (buffer, width, height)",
+          py::arg("image"), py::arg("width"), py::arg("height"), py::arg("channels"), py::arg("orientation"));
+    m.def("sample_bilinear", &sample_bilinear_wrapper, "Bilinearly interpolates one pixel at fractional coordinates, clamped to the image",
+          py::arg("image"), py::arg("width"), py::arg("height"), py::arg("channels"), py::arg("fx"), py::arg("fy"));
+    m.def("sample_bicubic", &sample_bicubic_wrapper, "Bicubically interpolates one pixel at fractional coordinates, clamped to the image",
+          py::arg("image"), py::arg("width"), py::arg("height"), py::arg("channels"), py::arg("fx"), py::arg("fy"));
+    m.def("build_pyramid", &build_pyramid_wrapper, "Builds a Gaussian pyramid as a list of (buffer, width, height)",
+          py::arg("image"), py::arg("width"), py::arg("height"), py::arg("channels"), py::arg("levels"));
+    m.def("blend_laplacian", &blend_laplacian_wrapper, "Seamlessly blends two images with a Laplacian pyramid",
+          py::arg("a"), py::arg("b"), py::arg("mask"), py::arg("width"), py::arg("height"),
+          py::arg("channels"), py::arg("levels"));
+    m.def("average_stack", &average_stack_wrapper, "Averages aligned frames of equal size to reduce noise",
+          py::arg("frames"), py::arg("width"), py::arg("height"), py::arg("channels"));
+    py::enum_<StackMode>(m, "StackMode")
+        .value("MEAN", StackMode::Mean)
+        .value("MAX", StackMode::Max)
+        .value("MIN", StackMode::Min)
+        .value("MEDIAN", StackMode::Median);
+    m.def("stack", &stack_wrapper, "Combines aligned frames per sample by mean, max, min, or median",
+          py::arg("frames"), py::arg("width"), py::arg("height"), py::arg("channels"), py::arg("mode"));
+    m.def("focus_stack", &focus_stack_wrapper, "Merges frames with different focus distances into one sharp image",
+          py::arg("frames"), py::arg("width"), py::arg("height"), py::arg("channels"),
+          py::arg("edge") = EdgeMode::Clamp);
+    m.def("subtract_dark_frame", &subtract_dark_frame_wrapper, "Subtracts a dark frame from an image, clamping at zero",
+          py::arg("image"), py::arg("dark"), py::arg("width"), py::arg("height"), py::arg("channels"));
+    m.def("flat_field_correct", &flat_field_correct_wrapper, "Divides an image by a flat-field frame normalized to its mean per channel",
+          py::arg("image"), py::arg("flat"), py::arg("width"), py::arg("height"), py::arg("channels"));
+    m.def("merge_hdr", &merge_hdr_wrapper, "Merges bracketed linear 16-bit RGB frames into float32 radiance, weighted by exposure",
+          py::arg("frames"), py::arg("exposures"), py::arg("width"), py::arg("height"));
+    py::enum_<ToneMapOperator>(m, "ToneMapOperator")
+        .value("REINHARD", ToneMapOperator::Reinhard)
+        .value("DRAGO", ToneMapOperator::Drago)
+        .value("LOCAL", ToneMapOperator::Local);
+    m.def("tonemap", &tonemap_wrapper, "Maps float32 RGB radiance to an 8-bit sRGB image with a global or local operator",
+          py::arg("radiance"), py::arg("width"), py::arg("height"),
+          py::arg("operator") = ToneMapOperator::Reinhard, py::arg("key") = 0.18f,
+          py::arg("white") = 0.0f, py::arg("bias") = 0.85f, py::arg("base_stops") = 5.0f,
+          py::arg("detail") = 1.0f);
+    m.def("suggest_denoise_strength", &suggest_denoise_strength_wrapper, "Suggests a denoise strength in [0, 1) for an ISO value",
+          py::arg("iso"));
+    m.def("suggest_exposure", &suggest_exposure_wrapper, "Suggests an exposure change in stops that centers the midtones without clipping",
+          py::arg("image"), py::arg("width"), py::arg("height"));
+    m.def("sample_neutral_multipliers", &sample_neutral_multipliers_wrapper, "Returns the (r, g, b) multipliers, green 1, that would make the picked area of an RGB image neutral, or None where it is too dark",
+          py::arg("image"), py::arg("width"), py::arg("height"), py::arg("x"), py::arg("y"), py::arg("radius") = 1);
+    m.def("image_stats", &image_stats_wrapper, "Per-channel mean, range, and clipped pixel counts and percentages",
+          py::arg("image"), py::arg("width"), py::arg("height"), py::arg("channels"),
+          py::arg("low") = 0, py::arg("high") = 255);
+    m.def("phash", &phash_wrapper, "Computes a 64-bit DCT-based perceptual hash of an RGB image for duplicate detection",
+          py::arg("image"), py::arg("width"), py::arg("height"));
+    m.def("hash_distance", &hash_distance, "Returns the number of differing bits between two 64-bit image hashes",
+          py::arg("a"), py::arg("b"));
+    m.def("render_histogram", &render_histogram_wrapper, "Draws the per-channel histograms of an image into an RGB buffer of out_width x out_height",
+          py::arg("image"), py::arg("width"), py::arg("height"), py::arg("channels"),
+          py::arg("out_width"), py::arg("out_height"));
+    m.def("render_waveform", &render_waveform_wrapper, "Draws the luma waveform of an RGB image into an RGB buffer of width x out_height",
+          py::arg("image"), py::arg("width"), py::arg("height"), py::arg("out_height"));
+    m.def("render_vectorscope", &render_vectorscope_wrapper, "Plots the Cb/Cr chroma of an RGB image into a size x size RGB buffer",
+          py::arg("image"), py::arg("width"), py::arg("height"), py::arg("size"));
+    py::class_<DevelopSettings>(m, "DevelopSettings", "Basic develop adjustments; all default to 0 (no change)")
+        .def(py::init<>())
+        .def_readwrite("exposure", &DevelopSettings::exposure)
+        .def_readwrite("contrast", &DevelopSettings::contrast)
+        .def_readwrite("saturation", &DevelopSettings::saturation)
+        .def_readwrite("temp", &DevelopSettings::temp)
+        .def_readwrite("tint", &DevelopSettings::tint)
+        .def_readwrite("highlights", &DevelopSettings::highlights)
+        .def_readwrite("shadows", &DevelopSettings::shadows)
+        .def_readwrite("sharpen", &DevelopSettings::sharpen);
+    m.def("develop", &develop_wrapper, "Applies all develop settings to an RGB image in one pass",
+          py::arg("image"), py::arg("width"), py::arg("height"), py::arg("settings"));
+    m.def("develop", &develop_dict_wrapper, "Applies develop settings given as a dict to an RGB image in one pass",
+          py::arg("image"), py::arg("width"), py::arg("height"), py::arg("settings"));
+    m.def("develop_from_json", &develop_from_json_wrapper, "Applies a JSON develop preset to an RGB image; unknown fields are ignored",
+          py::arg("image"), py::arg("width"), py::arg("height"), py::arg("json"));
+    m.def("develop_batch", &develop_batch_wrapper, "Decodes and develops several raw files with the same settings in parallel, returning JPEGs",
+          py::arg("filepaths"), py::arg("settings"), py::arg("quality") = 90);
+    m.def("develop_batch", &develop_batch_dict_wrapper, "Decodes and develops several raw files with the same settings (a dict) in parallel, returning JPEGs",
+          py::arg("filepaths"), py::arg("settings"), py::arg("quality") = 90);
+    m.def("begin_develop", &begin_develop_wrapper, "Develops a half-size preview of a raw file, returning the RGB data, width, height, and a session for finish_develop",
+          py::arg("filepath"), py::arg("settings"));
+    m.def("begin_develop", &begin_develop_dict_wrapper, "Develops a half-size preview of a raw file with settings given as a dict, returning the RGB data, width, height, and a session for finish_develop",
+          py::arg("filepath"), py::arg("settings"));
+    m.def("finish_develop", &finish_develop_wrapper, "Develops a begin_develop session at full resolution without decoding the file again, and ends the session",
+          py::arg("session"));
+    m.def("develop_settings_to_json", &develop_settings_to_json_wrapper, "Serializes develop settings as a JSON preset",
+          py::arg("settings"));
+    m.def("apply_python_filter", &apply_python_filter_wrapper,
+          "Applies a Python callable to batches of pixels (bytes in, bytes out); slow, meant for prototyping",
+          py::arg("image"), py::arg("width"), py::arg("height"), py::arg("channels"), py::arg("func"),
+          py::arg("batch_pixels") = 4096);
+
+    // Color space conversions. Each name accepts either a single (a, b, c)
+    // pixel or a whole buffer with its width and height.
+    m.def("rgb_to_ycbcr", py::overload_cast<const Color&>(&rgb_to_ycbcr), "Converts an RGB pixel to YCbCr", py::arg("rgb"));
+    m.def("rgb_to_ycbcr", &rgb_buffer_to_ycbcr_wrapper, "Converts an RGB buffer to a flat list of YCbCr values",
+          py::arg("image"), py::arg("width"), py::arg("height"));
+    m.def("ycbcr_to_rgb", py::overload_cast<const Color&>(&ycbcr_to_rgb), "Converts a YCbCr pixel to RGB", py::arg("ycbcr"));
+    m.def("ycbcr_to_rgb", &ycbcr_buffer_to_rgb_wrapper, "Converts a flat list of YCbCr values to an RGB buffer",
+          py::arg("data"), py::arg("width"), py::arg("height"));
+    m.def("rgb_to_hsl", py::overload_cast<const Color&>(&rgb_to_hsl), "Converts an RGB pixel to HSL", py::arg("rgb"));
+    m.def("rgb_to_hsl", &rgb_buffer_to_hsl_wrapper, "Converts an RGB buffer to a flat list of HSL values",
+          py::arg("image"), py::arg("width"), py::arg("height"));
+    m.def("hsl_to_rgb", py::overload_cast<const Color&>(&hsl_to_rgb), "Converts an HSL pixel to RGB", py::arg("hsl"));
+    m.def("hsl_to_rgb", &hsl_buffer_to_rgb_wrapper, "Converts a flat list of HSL values to an RGB buffer",
+          py::arg("data"), py::arg("width"), py::arg("height"));
+    m.def("rgb_to_hsv", py::overload_cast<const Color&>(&rgb_to_hsv), "Converts an RGB pixel to HSV", py::arg("rgb"));
+    m.def("rgb_to_hsv", &rgb_buffer_to_hsv_wrapper, "Converts an RGB buffer to a flat list of HSV values",
+          py::arg("image"), py::arg("width"), py::arg("height"));
+    m.def("hsv_to_rgb", py::overload_cast<const Color&>(&hsv_to_rgb), "Converts an HSV pixel to RGB", py::arg("hsv"));
+    m.def("hsv_to_rgb", &hsv_buffer_to_rgb_wrapper, "Converts a flat list of HSV values to an RGB buffer",
+          py::arg("data"), py::arg("width"), py::arg("height"));
+    m.def("rgb_to_lab", py::overload_cast<const Color&>(&rgb_to_lab), "Converts an RGB pixel to CIE Lab", py::arg("rgb"));
+    m.def("rgb_to_lab", &rgb_buffer_to_lab_wrapper, "Converts an RGB buffer to a flat list of CIE Lab values",
+          py::arg("image"), py::arg("width"), py::arg("height"));
+    m.def("lab_to_rgb", py::overload_cast<const Color&>(&lab_to_rgb), "Converts a CIE Lab pixel to RGB", py::arg("lab"));
+    m.def("lab_to_rgb", &lab_buffer_to_rgb_wrapper, "Converts a flat list of CIE Lab values to an RGB buffer",
+          py::arg("data"), py::arg("width"), py::arg("height"));
+
+    // White balance
+    m.def("temp_tint_to_multipliers", &temp_tint_to_multipliers, "Returns the (r, g, b) multipliers, green 1, that neutralize light of a temperature in Kelvin and a tint",
+          py::arg("temperature"), py::arg("tint") = 0.0f);
+    m.def("multipliers_to_temp_tint", &multipliers_to_temp_tint_wrapper, "Returns the (temperature, tint) of the light that (r, g, b) multipliers neutralize",
+          py::arg("multipliers"));
+
+    // Encoding
+    m.def("preview_jpeg_base64", &preview_jpeg_base64_wrapper, "Encodes an RGB image as a base64 JPEG string",
+          py::arg("image"), py::arg("width"), py::arg("height"), py::arg("quality") = 85);
+    py::enum_<ChromaSubsampling>(m, "ChromaSubsampling")
+        .value("S444", ChromaSubsampling::S444)
+        .value("S422", ChromaSubsampling::S422)
+        .value("S420", ChromaSubsampling::S420);
+    m.def("encode_jpeg", &encode_jpeg_wrapper, "Encodes a gray, RGB, or RGBA image as a baseline JPEG, optionally with EXIF from a get_metadata dict",
+          py::arg("image"), py::arg("width"), py::arg("height"), py::arg("channels"), py::arg("quality") = 90,
+          py::arg("subsampling") = ChromaSubsampling::S420, py::arg("metadata") = py::none(),
+          py::arg("icc_profile") = py::bytes(), py::arg("dpi") = 72);
+    m.def("encode_tiff", &encode_tiff_wrapper, "Encodes an 8- or 16-bit image as an uncompressed TIFF",
+          py::arg("image"), py::arg("width"), py::arg("height"), py::arg("channels"), py::arg("bits") = 8,
+          py::arg("icc_profile") = py::bytes(), py::arg("dpi") = 72);
+    py::enum_<ExportFormat>(m, "ExportFormat")
+        .value("JPEG", ExportFormat::Jpeg)
+        .value("TIFF", ExportFormat::Tiff);
+    m.def("export_region", &export_region_wrapper, "Crops an image to an (x, y, width, height) rectangle, resizes, sharpens, and encodes it in one call",
+          py::arg("image"), py::arg("width"), py::arg("height"), py::arg("channels"), py::arg("crop"),
+          py::arg("out_width"), py::arg("out_height"), py::arg("sharpen_amount") = 0.0f,
+          py::arg("format") = ExportFormat::Jpeg, py::arg("quality") = 90, py::arg("dpi") = 72);
+    m.def("write_tiff", &write_tiff_wrapper, "Writes an 8- or 16-bit image to an uncompressed TIFF file, streaming it in strips",
+          py::arg("filepath"), py::arg("image"), py::arg("width"), py::arg("height"), py::arg("channels"),
+          py::arg("bits") = 8, py::arg("icc_profile") = py::bytes(), py::arg("dpi") = 72);
+    m.def("srgb_icc_profile", &srgb_icc_profile_wrapper, "Returns a compact sRGB ICC profile to embed with icc_profile");
+
+    // Metadata sidecars
+    m.def("xmp_sidecar_path", &xmp_sidecar_path, "Returns the path of the XMP sidecar that belongs to a raw file",
+          py::arg("raw_path"));
+    m.def("write_xmp_sidecar", &write_xmp_sidecar, "Writes the rating (-1 to 5), keywords, and label to the raw file's XMP sidecar and returns its path",
+          py::arg("raw_path"), py::arg("rating"), py::arg("keywords") = std::vector<std::string>(),
+          py::arg("label") = "");
+    m.def("read_xmp_sidecar", &read_xmp_sidecar_wrapper, "Reads rating, label, and keywords from the raw file's XMP sidecar; missing values are empty",
+          py::arg("raw_path"));
+
+    // Editable image
+    py::class_<Image>(m, "PyImage", "An 8-bit image with its dimensions; edits are queued, return the image for chaining, and run on render()")
+        .def(py::init(&image_from_bytes), py::arg("data"), py::arg("width"), py::arg("height"), py::arg("channels"))
+        .def_static("from_handle", &image_from_handle, "Decodes a loaded raw image into an 8-bit PyImage",
+                    py::arg("id"), py::arg("half_size") = false)
+        .def_property_readonly("width", &Image::width)
+        .def_property_readonly("height", &Image::height)
+        .def_property_readonly("channels", &Image::channels)
+        .def_property_readonly("data", &image_data)
+        .def("brightness", &Image::brightness, "Adds amount * 255 to every sample",
+             py::arg("amount"), py::return_value_policy::reference_internal)
+        .def("contrast", &Image::contrast, "Scales the distance of every sample from middle gray by 1 + amount",
+             py::arg("amount"), py::return_value_policy::reference_internal)
+        .def("gamma", &Image::gamma, "Raises normalized samples to the power 1 / gamma",
+             py::arg("gamma"), py::return_value_policy::reference_internal)
+        .def("invert", &Image::invert, "Inverts every sample", py::return_value_policy::reference_internal)
+        .def("crop", &image_crop, "Keeps only the pixels inside the rectangle",
+             py::arg("x"), py::arg("y"), py::arg("width"), py::arg("height"),
+             py::return_value_policy::reference_internal)
+        .def("resize", &Image::resize, "Resamples the image by area averaging",
+             py::arg("width"), py::arg("height"), py::return_value_policy::reference_internal)
+        .def("render", &Image::render, "Applies the queued edits, fusing runs of point operations into one pass",
+             py::call_guard<py::gil_scoped_release>(), py::return_value_policy::reference_internal)
+        .def("save", &Image::save, "Renders, then writes a JPEG or TIFF file, chosen by the extension",
+             py::arg("filepath"), py::arg("quality") = 90, py::call_guard<py::gil_scoped_release>(),
+             py::return_value_policy::reference_internal)
+        .def_property_readonly("buffer_passes", &Image::buffer_passes,
+                               "How many passes over the pixels rendering has made so far");
 }
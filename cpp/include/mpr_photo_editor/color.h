@@ -0,0 +1,63 @@
+#ifndef MPR_COLOR_H
+#define MPR_COLOR_H
+
+#include <array>
+#include <cstdint>
+#include <vector>
+
+// --- Color Space Conversions ---
+// RGB is gamma encoded sRGB normalized to [0, 1]. The other spaces are:
+//   YCbCr: full-range BT.601 (as in JPEG), Y in [0, 1], Cb/Cr in [-0.5, 0.5]
+//   HSL/HSV: hue in degrees [0, 360), the other components in [0, 1]
+//   Lab: CIE L*a*b* relative to D65, L in [0, 100], a/b roughly in [-128, 128]
+
+using Color = std::array<float, 3>;
+
+Color rgb_to_ycbcr(const Color& rgb);
+Color ycbcr_to_rgb(const Color& ycbcr);
+Color rgb_to_hsl(const Color& rgb);
+Color hsl_to_rgb(const Color& hsl);
+Color rgb_to_hsv(const Color& rgb);
+Color hsv_to_rgb(const Color& hsv);
+Color rgb_to_lab(const Color& rgb);
+Color lab_to_rgb(const Color& lab);
+
+// Whole-buffer variants convert interleaved 8-bit RGB images to interleaved
+// float triples in the target space, and back (rounding and clamping to 8 bits).
+std::vector<float> rgb_to_ycbcr(const std::vector<uint8_t>& image, int width, int height);
+std::vector<uint8_t> ycbcr_to_rgb(const std::vector<float>& data, int width, int height);
+std::vector<float> rgb_to_hsl(const std::vector<uint8_t>& image, int width, int height);
+std::vector<uint8_t> hsl_to_rgb(const std::vector<float>& data, int width, int height);
+std::vector<float> rgb_to_hsv(const std::vector<uint8_t>& image, int width, int height);
+std::vector<uint8_t> hsv_to_rgb(const std::vector<float>& data, int width, int height);
+std::vector<float> rgb_to_lab(const std::vector<uint8_t>& image, int width, int height);
+std::vector<uint8_t> lab_to_rgb(const std::vector<float>& data, int width, int height);
+
+// --- White Balance ---
+// Converts between a color temperature and tint, as white balance controls
+// present it, and channel multipliers for linear sRGB data, normalized so
+// green is 1. The illuminant is a point on the Planckian (black body) locus,
+// found with the cubic approximation of Kim et al., offset perpendicular to
+// it by the tint. The tint is that offset in CIE 1960 uv (Duv) times 3000,
+// with the usual range about +-150. Positive tints describe greenish light,
+// so their multipliers make the image more magenta, like the tint of
+// DevelopSettings. D65 lies slightly off the locus, at 6504 K and a tint of
+// about 10. The multipliers are those that turn the illuminant's white
+// neutral.
+// Raw data would also need its camera's color matrix, so for raw channels
+// this is an approximation.
+
+struct TempTint {
+    float temperature = 0.0f; // Kelvin, in [1667, 25000].
+    float tint = 0.0f;
+};
+
+// Throws std::invalid_argument for temperatures outside [1667, 25000] K and
+// for tints whose illuminant lies outside the sRGB gamut.
+Color temp_tint_to_multipliers(float temperature, float tint);
+
+// The inverse: the temperature and tint whose multipliers are closest to
+// `multipliers` (all positive), clamped to the temperature range.
+TempTint multipliers_to_temp_tint(const Color& multipliers);
+
+#endif // MPR_COLOR_H
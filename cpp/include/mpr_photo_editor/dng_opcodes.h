@@ -0,0 +1,93 @@
+#ifndef MPR_DNG_OPCODES_H
+#define MPR_DNG_OPCODES_H
+
+#include <array>
+#include <cstdint>
+#include <string>
+#include <vector>
+
+// --- DNG Opcodes ---
+// DNG files can carry corrections for the raw converter to apply, as lists of
+// opcodes: OpcodeList2 on the linear sensor data, OpcodeList3 after
+// demosaicing. LibRaw only applies them when built with the Adobe DNG SDK.
+// The two opcodes lens corrections are made of are supported here; others are
+// recorded by ID and skipped.
+
+// GainMap (opcode 9): multiplies the samples of an area by a gain that is
+// interpolated bilinearly from a grid, usually to correct vignetting. The area
+// is in pixels of the full-size image; the grid is placed in coordinates
+// relative to the image, (0, 0) at its top-left and (1, 1) at its bottom-right.
+struct DngGainMap {
+    int top = 0;
+    int left = 0;
+    int bottom = 0;
+    int right = 0;
+    int plane = 0;  // The first plane (channel) affected,
+    int planes = 1; // and how many.
+    // Only every row_pitch-th row and col_pitch-th column of the area, counted
+    // from its top-left, is affected. On Bayer data a pitch of 2 selects one
+    // color of the CFA.
+    int row_pitch = 1;
+    int col_pitch = 1;
+    int points_v = 0;
+    int points_h = 0;
+    double spacing_v = 0.0;
+    double spacing_h = 0.0;
+    double origin_v = 0.0;
+    double origin_h = 0.0;
+    int map_planes = 1;
+    // points_v x points_h x map_planes gains, row by row, planes interleaved.
+    std::vector<float> gains;
+
+    // The gain of `map_plane` at relative position (v, h). Positions outside
+    // the grid take the gain of its nearest edge.
+    float gain_at(double v, double h, int map_plane) const;
+};
+
+// WarpRectilinear (opcode 1): corrects radial and tangential lens distortion.
+// Each plane has its own coefficients, or one set applies to all planes.
+struct DngWarpRectilinear {
+    // kr0, kr1, kr2, kr3 (radial) and kt0, kt1 (tangential) per plane.
+    std::vector<std::array<double, 6>> coefficients;
+    // The optical center, relative to the image.
+    double center_x = 0.5;
+    double center_y = 0.5;
+};
+
+struct DngOpcodes {
+    std::vector<DngGainMap> gain_maps;
+    std::vector<DngWarpRectilinear> warps;
+    std::vector<uint32_t> skipped; // IDs of the unsupported opcodes, in order.
+};
+
+struct DngOpcodeLists {
+    DngOpcodes list2; // OpcodeList2: for the linear CFA data, before white balance.
+    DngOpcodes list3; // OpcodeList3: for the demosaiced camera colors.
+};
+
+// Parses one opcode list as stored in its tag. Malformed lists throw
+// std::invalid_argument.
+DngOpcodes parse_dng_opcode_list(const std::vector<uint8_t>& data);
+
+// Reads OpcodeList2 and OpcodeList3 of the full-resolution image of a DNG
+// file. Files without them, including raw formats that are not DNG, give
+// empty lists; files that cannot be read throw std::runtime_error.
+DngOpcodeLists read_dng_opcodes(const std::string& filepath);
+
+// Applies a gain map to an interleaved 16-bit image. The image may be smaller
+// than the full-size `full_width` x `full_height` image the map was made for,
+// such as a half-size decode; positions are scaled accordingly. With
+// `channel` >= 0 only that channel is changed, with the gains of the map's
+// first plane; that is how a map for one CFA color applies once the caller
+// has found the channel holding that color. With -1 the map's planes select
+// the channels and its pitches the pixels, which needs a full-size image:
+// a map with a pitch on a smaller one throws std::invalid_argument.
+void apply_gain_map(const DngGainMap& map, int channel, uint16_t* image, int width, int height,
+                    int channels, int full_width, int full_height);
+
+// Resamples an interleaved 16-bit image in place to undo the distortion
+// `warp` describes.
+void apply_warp_rectilinear(const DngWarpRectilinear& warp, uint16_t* image, int width, int height,
+                            int channels);
+
+#endif // MPR_DNG_OPCODES_H
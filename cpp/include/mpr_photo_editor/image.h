@@ -0,0 +1,84 @@
+#ifndef MPR_IMAGE_H
+#define MPR_IMAGE_H
+
+#include "image_ops.h"
+#include "image_types.h"
+#include <cstddef>
+#include <cstdint>
+#include <mutex>
+#include <string>
+#include <vector>
+
+// --- Editable Image ---
+// An 8-bit image together with its dimensions, so a sequence of edits does
+// not have to pass them to every call. Each edit returns the image itself,
+// which allows chaining:
+//
+//     image.brightness(0.1f).contrast(0.2f).crop({0, 0, 640, 480}).save("out.jpg");
+//
+// Edits are only queued, and checked, when they are called; the pixels change
+// on render(), which save() and data() call first. Rendering fuses each run of
+// point operations (brightness, contrast, gamma, invert) into a single lookup
+// table, so such a run reads and writes the buffer once instead of once per
+// edit. The result is exactly that of applying the edits one by one.
+//
+// Unlike the image operations, edits do not keep the previous pixels; copy
+// the Image first to keep them.
+//
+// An Image may be used from several threads: queueing, rendering, and saving
+// each hold its lock, so an edit queued while another thread renders waits
+// for the render and applies on the next one.
+class Image {
+public:
+    // Takes over `data`, which must hold width x height x channels samples.
+    Image(std::vector<uint8_t> data, int width, int height, int channels);
+    Image(const Image& other);
+    Image(Image&& other) noexcept;
+    Image& operator=(Image other) noexcept;
+
+    // The dimensions after all queued edits.
+    int width() const;
+    int height() const;
+    int channels() const;
+
+    // Renders the queued edits and returns a copy of the pixels.
+    std::vector<uint8_t> data();
+
+    Image& brightness(float amount);
+    Image& contrast(float amount);
+    Image& gamma(float gamma);
+    Image& invert();
+    Image& crop(const CropRect& rect);
+    Image& resize(int new_width, int new_height);
+
+    // Applies the queued edits to the pixels.
+    Image& render();
+
+    // Renders, then writes a JPEG (".jpg", ".jpeg") or 8-bit TIFF (".tif",
+    // ".tiff") file, chosen by the extension of `filepath`. `quality` applies
+    // to JPEG only.
+    Image& save(const std::string& filepath, int quality = 90);
+
+    // How many times rendering has gone over the pixel buffer so far: once per
+    // fused run of point operations and once per crop or resize.
+    size_t buffer_passes() const;
+
+private:
+    struct Edit {
+        enum class Kind { Lut, Crop, Resize } kind;
+        ToneLut lut{};
+        CropRect rect; // Crop: the rectangle. Resize: only the size is used.
+    };
+
+    Image& queue_lut(const ToneLut& lut);
+    void render_locked();
+
+    mutable std::mutex mutex;
+    ImageBuffer buffer;
+    std::vector<Edit> pending;
+    int out_width = 0;
+    int out_height = 0;
+    size_t passes = 0;
+};
+
+#endif // MPR_IMAGE_H
@@ -0,0 +1,84 @@
+#ifndef MPR_IMAGE_IO_H
+#define MPR_IMAGE_IO_H
+
+#include "image_types.h"
+#include <cstdint>
+#include <optional>
+#include <string>
+#include <vector>
+
+// --- Encoding ---
+// Encoders take interleaved 8-bit buffers like the image operations and
+// return the complete file contents in memory. Those with an `icc_profile`
+// parameter embed it, so color-managed viewers know how to interpret the
+// pixels; an empty profile embeds nothing. `dpi` (1 to 65535) is stored as
+// the resolution, which sets the physical size of a print.
+
+// Resolution of the two chroma components of a color JPEG relative to luma.
+enum class ChromaSubsampling {
+    S444, // Full resolution; keeps fine color detail, e.g. for print.
+    S422, // Half the horizontal resolution.
+    S420, // Half the resolution in both directions: the smallest files.
+};
+
+// Encodes a 1 (gray), 3 (RGB), or 4 (RGBA, alpha dropped) channel image as a
+// baseline JPEG. `quality` ranges from 1 to 100. `subsampling` has no effect
+// on grayscale images.
+// With `exif` set, the file gets an EXIF (APP1) segment with the make, model,
+// ISO, shutter speed, aperture, and capture time of the original shot; empty
+// and zero fields are left out. The image dimensions are not copied, since
+// an export is usually cropped or resized.
+std::vector<uint8_t> encode_jpeg(const std::vector<uint8_t>& image, int width, int height,
+                                 int channels, int quality,
+                                 ChromaSubsampling subsampling = ChromaSubsampling::S420,
+                                 const std::optional<Metadata>& exif = std::nullopt,
+                                 const std::vector<uint8_t>& icc_profile = {}, int dpi = 72);
+
+// Decodes a baseline or progressive JPEG into an RGB buffer. Grayscale and
+// CMYK files are converted to RGB by libjpeg.
+ImageBuffer decode_jpeg(const std::vector<uint8_t>& data);
+
+// Encodes an RGB image as JPEG and returns it as a base64 string, ready to be
+// used in a `data:image/jpeg;base64,` URL.
+std::string preview_jpeg_base64(const std::vector<uint8_t>& image, int width, int height, int quality);
+
+// Encodes an uncompressed baseline TIFF with 1 (gray), 3 (RGB), or 4 (RGBA)
+// channels and 8 or 16 bits per sample. For 16 bits `image` holds
+// native-endian uint16 samples, as returned by 16-bit decodes; the file is
+// written in the host byte order, which every TIFF reader supports.
+std::vector<uint8_t> encode_tiff(const std::vector<uint8_t>& image, int width, int height,
+                                 int channels, int bits, const std::vector<uint8_t>& icc_profile = {},
+                                 int dpi = 72);
+
+// Writes the same file as encode_tiff to `filepath`, streaming the pixels in
+// strips of rows instead of assembling the whole file in memory first. Use it
+// for large exports, where the encoded copy would double the memory needed.
+void write_tiff(const std::string& filepath, const std::vector<uint8_t>& image, int width, int height,
+                int channels, int bits, const std::vector<uint8_t>& icc_profile = {}, int dpi = 72);
+
+// --- Color Profiles ---
+
+// A compact ICC v2 display profile for sRGB (D65 primaries adapted to the
+// D50 connection space, and the sRGB tone curve as a 1024-entry table), for
+// the encoders' `icc_profile` parameter.
+std::vector<uint8_t> srgb_icc_profile();
+
+// --- Export ---
+
+enum class ExportFormat {
+    Jpeg,
+    Tiff, // 8 bits per sample, uncompressed.
+};
+
+// Crops `image` to `crop`, resizes the crop to `out_width` x `out_height`,
+// sharpens it with an unsharp mask of `sharpen_amount` (0 skips this step),
+// and encodes it, all in one call. Sharpening after the resize restores the
+// crispness that downscaling takes away. `quality` applies to JPEG only.
+std::vector<uint8_t> export_region(const std::vector<uint8_t>& image, int width, int height,
+                                   int channels, const CropRect& crop, int out_width, int out_height,
+                                   float sharpen_amount, ExportFormat format, int quality, int dpi = 72);
+
+// Standard (RFC 4648) base64 with padding.
+std::string base64_encode(const std::vector<uint8_t>& data);
+
+#endif // MPR_IMAGE_IO_H
@@ -3,9 +3,17 @@
 
 #include "image_types.h"
 #include <string>
+#include <cstddef>
 #include <cstdint>
 #include <memory>
+#include <vector>
 
+struct DevelopSettings;
+
+// Owns the loaded raw images. All methods may be called from any thread.
+// Calls on different images, and the file-based functions, run in parallel;
+// calls on the same image wait for each other. With a non-reentrant LibRaw
+// build all decoding is serialised instead.
 class ImageManager {
 public:
     static ImageManager& instance();
@@ -14,15 +22,79 @@ public:
     void operator=(const ImageManager&) = delete;
 
     std::string get_libraw_version();
+    std::vector<std::string> get_supported_extensions();
+    int get_supported_camera_count();
+    std::vector<std::string> get_supported_cameras();
     uint64_t load_raw_image(const std::string& filepath);
+    // Loads a raw file from its contents in memory, e.g. received over the
+    // network. The handle keeps the data; DNG opcodes, which are read from
+    // the file, are not available for it.
+    uint64_t load_raw_image_from_buffer(std::vector<uint8_t> data);
     void release_raw_image(uint64_t id);
-    ThumbnailData get_thumbnail(uint64_t id);
+    // Files whose sensor has more than `pixels` pixels (0 for no limit, the
+    // default) are rejected before their raw data is read, so untrusted files
+    // cannot make a decode allocate gigabytes. Images loaded earlier are not
+    // affected.
+    void set_max_decode_pixels(uint64_t pixels);
+    uint64_t get_max_decode_pixels();
+    size_t get_open_image_count();
+    ThumbnailData get_thumbnail(uint64_t id, bool apply_orientation = true);
+    std::vector<ThumbnailInfo> list_thumbnails(const std::string& filepath);
+    ThumbnailData extract_thumbnail_index(const std::string& filepath, int index, bool apply_orientation = true);
     Metadata get_metadata(uint64_t id);
+    float read_iso(const std::string& filepath);
+    FocalLength read_focal_length(const std::string& filepath);
+    CaptureFlags read_capture_flags(const std::string& filepath);
+    RawBayerData load_raw_bayer(const std::string& filepath);
+    std::vector<uint8_t> decode_to_tiff(const std::string& filepath, int bits = 16);
+    Linear16Image decode_linear16(const std::string& filepath, float bright = 1.0f);
+    // Decodes a raw file at 16 bits (whatever options.bits says) and returns
+    // the samples as floats: divided by 65535 into [0, 1] with `normalize`,
+    // else with their 16-bit values.
+    Float32Image load_raw_f32(const std::string& filepath, bool normalize = true,
+                            const DecodeOptions& options = DecodeOptions());
+    WhiteBalanceComparison load_raw_wb_compare(const std::string& filepath, bool half_size = true);
+    std::vector<uint8_t> make_contact_thumb(const std::string& filepath, int max_dim, int quality);
+    std::vector<uint8_t> thumbnail_or_halfsize(const std::string& filepath, int min_dim, int quality);
+    BestPreview load_best_preview(const std::string& filepath, int target_dim, int quality = 90);
+    // thumbnail_or_halfsize and load_best_preview keep their results in a
+    // least-recently-used cache, keyed by file, request, and the file's
+    // modification time. The size is in bytes of preview data; 0 disables
+    // the cache.
+    void set_preview_cache_size(size_t bytes);
+    // Drops all cached previews; the hit and miss counts are kept.
+    void clear_preview_cache();
+    PreviewCacheStats preview_cache_stats();
+    DecodedImage load_raw_with_timeout(const std::string& filepath, int timeout_ms,
+                                       const DecodeOptions& options = DecodeOptions());
+    std::vector<std::vector<uint8_t>> develop_batch(const std::vector<std::string>& filepaths,
+                                                    const DevelopSettings& settings, int quality);
+    // Unpacks a raw file once and develops a half-size decode of it as a
+    // preview. The session is an image handle that also holds the settings;
+    // finish_develop develops the same unpacked data at full resolution and
+    // releases the session. A session that is not finished is dropped with
+    // release_raw_image.
+    DevelopPreview begin_develop(const std::string& filepath, const DevelopSettings& settings);
+    ImageBuffer finish_develop(uint64_t session);
+    DecodedImage decode_image(uint64_t id, const DecodeOptions& options = DecodeOptions(),
+                              bool collect_diagnostics = false,
+                              const ProgressCallback& progress = nullptr);
+    // Decodes like decode_image, but hands the pixels to `callback` in stripes
+    // of `stripe_height` rows (the last one may be shorter), top to bottom,
+    // instead of returning them. LibRaw still processes the whole image in
+    // memory, but its output is converted one stripe at a time into a single
+    // stripe-sized buffer, so no full-size copy of it is made. The result
+    // describes the format and holds no pixel data. The image stays locked
+    // during the callbacks, so they must not use the same handle.
+    DecodedImage decode_stripes(uint64_t id, int stripe_height, const StripeCallback& callback,
+                                const DecodeOptions& options = DecodeOptions());
 
 private:
     ImageManager();
     ~ImageManager();
 
+    BestPreview cached_preview(const std::string& filepath, int min_dim, int quality, bool allow_full);
+
     class Impl;
     std::unique_ptr<Impl> pimpl;
 };
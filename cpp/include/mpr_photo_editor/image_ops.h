@@ -0,0 +1,442 @@
+#ifndef MPR_IMAGE_OPS_H
+#define MPR_IMAGE_OPS_H
+
+#include "image_types.h"
+#include <array>
+#include <cstdint>
+#include <optional>
+#include <vector>
+
+// --- Image Operations ---
+// Pure pixel operations on interleaved 8-bit buffers (row-major, channels last).
+// They never modify their input and always return a newly allocated buffer,
+// even when the result equals the input (e.g. a blur radius of 0); callers may
+// keep earlier buffers, such as undo history, without copying them first.
+// The only exceptions are the `_inplace` variants, which overwrite a buffer
+// the caller owns and allocate nothing.
+// Invalid dimensions or mismatched buffer sizes throw std::invalid_argument.
+
+// Tints shadows and highlights with separate colors. Hues are in degrees,
+// saturations in [0, 1]. `balance` in [-1, 1] favours the shadow tint
+// (negative) or the highlight tint (positive). Expects RGB input.
+std::vector<uint8_t> split_tone(const std::vector<uint8_t>& image, int width, int height,
+                                float shadow_hue, float shadow_sat,
+                                float highlight_hue, float highlight_sat,
+                                float balance);
+
+// Shifts colors separately in the shadows, midtones, and highlights, like the
+// classic color-balance dialog. Each shift is a (cyan-red, magenta-green,
+// yellow-blue) triple in [-1, 1]; positive values move towards red, green,
+// and blue. Tonal ranges are weighted smoothly by luminance. Expects RGB input.
+std::vector<uint8_t> color_balance(const std::vector<uint8_t>& image, int width, int height,
+                                   const std::array<float, 3>& shadows,
+                                   const std::array<float, 3>& midtones,
+                                   const std::array<float, 3>& highlights);
+
+// Raises (positive `amount`) or lowers (negative) saturation, like a
+// saturation control, but weighted by 1 - the pixel's HSV saturation: muted
+// colors change most, saturated ones hardly at all. Boosts are also reduced
+// around skin hues (orange, near 25 degrees), so faces do not turn orange.
+// Colors move towards or away from the pixel's luminance, which stays the
+// same. `amount` is in [-1, 1]. Expects RGB input.
+std::vector<uint8_t> adjust_vibrance(const std::vector<uint8_t>& image, int width, int height, float amount);
+
+// --- Point Operations ---
+// Tone adjustments that map every sample (of every channel) on its own. The
+// `_inplace` variants take a pointer to `width * height * channels` samples
+// and give the same result as the allocating ones.
+
+// Adds `amount` * 255 to every sample; `amount` is in [-1, 1].
+std::vector<uint8_t> adjust_brightness(const std::vector<uint8_t>& image, int width, int height,
+                                       int channels, float amount);
+void adjust_brightness_inplace(uint8_t* image, int width, int height, int channels, float amount);
+
+// Scales the distance of every sample from middle gray by 1 + `amount`;
+// `amount` is in [-1, 1], and -1 turns the image flat gray.
+std::vector<uint8_t> adjust_contrast(const std::vector<uint8_t>& image, int width, int height,
+                                     int channels, float amount);
+void adjust_contrast_inplace(uint8_t* image, int width, int height, int channels, float amount);
+
+// Raises normalized samples to the power 1 / `gamma`, so gamma values above
+// 1 brighten the midtones and values below 1 darken them. Black and white
+// stay put. `gamma` must be positive.
+std::vector<uint8_t> adjust_gamma(const std::vector<uint8_t>& image, int width, int height,
+                                  int channels, float gamma);
+void adjust_gamma_inplace(uint8_t* image, int width, int height, int channels, float gamma);
+
+// Replaces every sample v by 255 - v.
+std::vector<uint8_t> invert(const std::vector<uint8_t>& image, int width, int height, int channels);
+void invert_inplace(uint8_t* image, int width, int height, int channels);
+
+// Convert between sRGB codes and linear light, scaled to 0-255, with the
+// piecewise sRGB transfer function (a linear toe below code 10, then a 2.4
+// power), not a plain 2.2 gamma. Linear 8-bit samples have little precision
+// in the shadows: the 18 darkest codes decode to 0 or 1, so converting to
+// linear and back loses shadow detail, while the opposite direction is exact
+// to within a code.
+std::vector<uint8_t> srgb_to_linear(const std::vector<uint8_t>& image, int width, int height, int channels);
+void srgb_to_linear_inplace(uint8_t* image, int width, int height, int channels);
+std::vector<uint8_t> linear_to_srgb(const std::vector<uint8_t>& image, int width, int height, int channels);
+void linear_to_srgb_inplace(uint8_t* image, int width, int height, int channels);
+
+// The lookup tables behind the point operations: entry v is the result for
+// sample v. Each operation is one table lookup per sample, and a sequence of
+// them composes into a single table with exactly the same result, so a chain
+// of adjustments costs one pass over the image.
+using ToneLut = std::array<uint8_t, 256>;
+ToneLut brightness_lut(float amount);
+ToneLut contrast_lut(float amount);
+ToneLut gamma_lut(float gamma);
+ToneLut invert_lut();
+ToneLut srgb_to_linear_lut();
+ToneLut linear_to_srgb_lut();
+
+// The table that applies `first`, then `second`.
+ToneLut compose_luts(const ToneLut& first, const ToneLut& second);
+
+void apply_lut_inplace(const ToneLut& lut, uint8_t* image, int width, int height, int channels);
+
+// --- Filters ---
+
+// How neighbourhood filters read pixels outside the image. With the pixels
+// "abcd" in a row:
+enum class EdgeMode {
+    Clamp,    // aaa|abcd|ddd: repeats the edge pixels.
+    Reflect,  // dcb|abcd|cba: mirrors at the edge pixels, without repeating them.
+    Wrap,     // bcd|abcd|abc: tiles the image, for seamless textures.
+    Constant, // 000|abcd|000: black outside the image.
+};
+
+// Averages each pixel over a (2 * radius + 1)^2 window using a summed-area
+// table of the image, without a padded copy. With Clamp the window is clipped
+// to the image and averages only the pixels inside it; the other modes read
+// the window parts outside the image according to `edge` and average the
+// whole window. The cost per pixel does not grow with the radius, which is
+// limited to 2^24 for Reflect and Wrap.
+std::vector<uint8_t> box_blur(const std::vector<uint8_t>& image, int width, int height,
+                              int channels, int radius, EdgeMode edge = EdgeMode::Clamp);
+
+// Unsharp mask: adds `amount` times the difference between each channel and
+// its Gaussian blur with a sigma of `radius` pixels, which reads outside the
+// image according to `edge`. An amount of 0 returns an unchanged copy.
+std::vector<uint8_t> sharpen(const std::vector<uint8_t>& image, int width, int height,
+                             int channels, float amount, float radius = 1.0f,
+                             EdgeMode edge = EdgeMode::Clamp);
+
+// Unsharp mask on luminance (so no color fringes appear) that is faded out
+// where the image has no detail. The mask comes from the Sobel gradient
+// magnitude of the luminance after the Gaussian blur of `radius`: below
+// `threshold` (a luminance difference between neighbouring pixels, in [0, 1])
+// nothing is sharpened, above twice the threshold the full `amount` applies.
+// Flat areas keep their noise as it was instead of having it amplified. A
+// threshold of 0 sharpens everywhere. The blur and the gradient read outside
+// the image according to `edge`. Expects RGB input.
+std::vector<uint8_t> sharpen_masked(const std::vector<uint8_t>& image, int width, int height,
+                                    float amount, float radius, float threshold,
+                                    EdgeMode edge = EdgeMode::Clamp);
+
+// Reduces luminance noise with an undecimated Haar wavelet decomposition:
+// detail coefficients are soft-thresholded relative to the noise level
+// estimated at each scale (scaled by `strength`, 1.0 being moderate), so flat
+// areas smooth out while strong edges survive.
+// Chroma is left untouched. Expects RGB input.
+std::vector<uint8_t> denoise_wavelet(const std::vector<uint8_t>& image, int width, int height,
+                                     float strength);
+
+// Converts to YCbCr and applies a Gaussian blur with a sigma of `luma_strength`
+// pixels to Y and `chroma_strength` pixels to Cb/Cr. Sensor color noise is
+// usually coarser than luminance noise, so chroma tolerates stronger smoothing.
+// A strength of 0 leaves that component untouched. The blurs read outside the
+// image according to `edge`. Expects RGB input.
+std::vector<uint8_t> denoise_ycbcr(const std::vector<uint8_t>& image, int width, int height,
+                                   float luma_strength, float chroma_strength,
+                                   EdgeMode edge = EdgeMode::Clamp);
+
+// Repairs hot and dead sensor pixels: each channel sample that differs from
+// the median of its 8 neighbours by more than `threshold` (in 8-bit levels)
+// is replaced by that median. Unlike the denoise filters it leaves every other
+// pixel as it is, so detail and noise texture survive. Neighbours outside the
+// image are read according to `edge`.
+std::vector<uint8_t> fix_hot_pixels(const std::vector<uint8_t>& image, int width, int height,
+                                    int channels, int threshold, EdgeMode edge = EdgeMode::Clamp);
+
+// --- Geometry ---
+
+// Rotates and mirrors an image according to a LibRaw `flip` value
+// (imgdata.sizes.flip): bit 4 transposes, bit 2 mirrors vertically, and bit 1
+// mirrors horizontally, so 3 is 180 degrees, 5 is 90 degrees counter-clockwise,
+// and 6 is 90 degrees clockwise. Width and height swap when bit 4 is set.
+ImageBuffer orient_image(const std::vector<uint8_t>& image, int width, int height,
+                         int channels, int flip);
+
+// Turns an image stored with EXIF orientation `orientation` upright. The
+// codes map onto LibRaw flip values, so the result is that of orient_image:
+//   0 (unspecified) and 1: unchanged (flip 0)
+//   2: mirrored horizontally (flip 1)
+//   3: rotated 180 degrees (flip 3)
+//   4: mirrored vertically (flip 2)
+//   5: transposed, i.e. mirrored along the main diagonal (flip 4)
+//   6: rotated 90 degrees clockwise (flip 6)
+//   7: transversed, i.e. mirrored along the anti-diagonal (flip 7)
+//   8: rotated 90 degrees counter-clockwise (flip 5)
+ImageBuffer normalize_orientation(const std::vector<uint8_t>& image, int width, int height,
+                                  int channels, int orientation);
+
+// Copies the pixels inside `rect`, which must lie within the image.
+std::vector<uint8_t> crop_image(const std::vector<uint8_t>& image, int width, int height,
+                                int channels, const CropRect& rect);
+
+// Resamples an image to `new_width` x `new_height` by averaging the source
+// pixels each output pixel covers. This is meant for downscaling, where it
+// avoids aliasing; enlarging works but only interpolates between neighbours.
+// With `linear_light` the color samples are averaged as linear light instead
+// of sRGB codes; averaging codes darkens fine detail, e.g. a black and white
+// checkerboard turns to code 128 instead of the 188 of half the light. The
+// alpha channel of 2- and 4-channel images is averaged as is.
+std::vector<uint8_t> resize_image(const std::vector<uint8_t>& image, int width, int height,
+                                  int channels, int new_width, int new_height,
+                                  bool linear_light = false);
+
+// Content-aware resizing: narrows an image to `target_width` by repeatedly
+// removing the connected vertical seam (one pixel per row) that crosses the
+// least detail, so flat areas shrink while objects with strong edges keep
+// their shape. With `target_height` (0 keeps the height) horizontal seams are
+// removed as well, after the vertical ones. Only reduction is supported. Each
+// seam costs a pass over the image, so large reductions of large images are
+// slow; downscale with resize_image first. The detail measure, a Sobel
+// gradient, reads outside the image according to `edge`.
+std::vector<uint8_t> seam_carve(const std::vector<uint8_t>& image, int width, int height,
+                                int channels, int target_width, int target_height = 0,
+                                EdgeMode edge = EdgeMode::Clamp);
+
+// The rotation in degrees, counter-clockwise as displayed (y pointing down),
+// that levels the line through two points, e.g. a horizon drawn in the UI.
+// Lines within 45 degrees of horizontal are made horizontal and the others
+// vertical, so the result is in [-45, 45]; a line rising at 45 degrees gives
+// -45. The points must differ.
+float straighten_angle(float x0, float y0, float x1, float y1);
+
+// How geometric operations resample at fractional positions.
+enum class Interpolation {
+    Bilinear, // Fast; softens fine detail under large transforms.
+    Bicubic,  // Catmull-Rom; keeps detail, with slight ringing at hard edges.
+};
+
+// Returns the bilinearly interpolated value of each channel at (fx, fy),
+// unrounded. Pixel centres sit at integer coordinates, and coordinates
+// outside the image are clamped to the border, repeating the edge pixels.
+// Geometric operations that resample at fractional positions share this.
+std::vector<float> sample_bilinear(const std::vector<uint8_t>& image, int width, int height,
+                                   int channels, float fx, float fy);
+
+// As sample_bilinear, with bicubic interpolation over a 4x4 neighbourhood.
+// Values are clamped to [0, 255].
+std::vector<float> sample_bicubic(const std::vector<uint8_t>& image, int width, int height,
+                                  int channels, float fx, float fy);
+
+// --- Multi-Scale ---
+
+// Builds a Gaussian pyramid of `levels` images, starting with the input and
+// halving (rounding up) the size at each step. Every level is low-pass
+// filtered with a 5-tap binomial kernel before decimation to avoid aliasing.
+std::vector<ImageBuffer> build_pyramid(const std::vector<uint8_t>& image, int width, int height,
+                                       int channels, int levels);
+
+// Blends `a` and `b` with a Laplacian pyramid of `levels` levels. `mask` is a
+// single-channel buffer where 255 selects `a` and 0 selects `b`; it is
+// smoothed by its own Gaussian pyramid, so hard masks give seamless transitions.
+std::vector<uint8_t> blend_laplacian(const std::vector<uint8_t>& a, const std::vector<uint8_t>& b,
+                                     const std::vector<uint8_t>& mask, int width, int height,
+                                     int channels, int levels);
+
+// --- Stacking ---
+
+// Averages aligned frames of the same scene to reduce noise, e.g. for
+// astrophotography or simulated long exposures. All frames must have the
+// given dimensions. Sums are accumulated exactly and rounded once at the end;
+// large images are processed on several threads.
+std::vector<uint8_t> average_stack(const std::vector<std::vector<uint8_t>>& frames,
+                                   int width, int height, int channels);
+
+enum class StackMode {
+    Mean,   // Noise reduction, as average_stack.
+    Max,    // Lighten: keeps the brightest value, e.g. for star trails.
+    Min,    // Darken: keeps the darkest value.
+    Median, // Removes objects that appear in fewer than half of the frames.
+};
+
+// Combines aligned frames sample by sample with the given mode. The same
+// dimension checks as for average_stack apply. For an even number of frames
+// the median is the rounded mean of the two middle values.
+std::vector<uint8_t> stack(const std::vector<std::vector<uint8_t>>& frames,
+                           int width, int height, int channels, StackMode mode);
+
+// Merges frames focused at different distances into one image that is sharp
+// throughout. Every pixel takes the frame with the strongest local Laplacian
+// response around it; the selection map is smoothed before blending so the
+// borders between frames do not show halos or seams. The Laplacian and the
+// smoothing read outside the image according to `edge`.
+std::vector<uint8_t> focus_stack(const std::vector<std::vector<uint8_t>>& frames,
+                                 int width, int height, int channels,
+                                 EdgeMode edge = EdgeMode::Clamp);
+
+// Subtracts a dark frame, a capture with the lens capped at the same
+// exposure, ISO, and temperature, which removes hot pixels and fixed-pattern
+// noise, e.g. from long exposures. Results below zero are clamped to zero.
+// Both buffers must have the given dimensions.
+std::vector<uint8_t> subtract_dark_frame(const std::vector<uint8_t>& image, const std::vector<uint8_t>& dark,
+                                         int width, int height, int channels);
+
+// Divides an image by a flat-field frame, a capture of an evenly lit
+// surface, to remove vignetting and dust shadows. The flat is normalized to
+// its mean per channel, so the overall brightness and color stay the same.
+// Flat samples of 0 carry no information and leave the image as it is there;
+// a channel that is 0 throughout is rejected. Both buffers must have the
+// given dimensions.
+std::vector<uint8_t> flat_field_correct(const std::vector<uint8_t>& image, const std::vector<uint8_t>& flat,
+                                        int width, int height, int channels);
+
+// --- HDR ---
+
+// Merges bracketed RGB frames from decode_linear16 into one linear radiance
+// image. `exposures` holds the relative exposure of every frame (e.g. its
+// shutter time); a sample z contributes z / 65535 / exposure, weighted by a
+// hat function that is 1 at mid-range and falls to 0 at black and at
+// saturation. The weight of a pixel follows its brightest channel, so a clip
+// in one channel does not shift the hue. Pixels that are black or saturated in
+// every frame take the longest or the shortest exposure, respectively.
+std::vector<float> merge_hdr(const std::vector<std::vector<uint16_t>>& frames,
+                             const std::vector<float>& exposures, int width, int height);
+
+enum class ToneMapOperator {
+    Reinhard, // Global photographic operator with a burn-out white point.
+    Drago,    // Global adaptive logarithmic mapping.
+    Local,    // Compresses large-scale luminance only and keeps local detail.
+};
+
+// Parameters of the tonemapping operators. Each operator reads only its own.
+struct ToneMapParams {
+    float key = 0.18f;  // Reinhard: where the log-average luminance lands.
+    float white = 0.0f; // Reinhard: scaled luminance that maps to white; 0 uses the brightest pixel.
+    float bias = 0.85f; // Drago: in [0.5, 1]; lower values brighten the shadows more.
+    float base_stops = 5.0f; // Local: range in stops the large-scale luminance is compressed to.
+    float detail = 1.0f;     // Local: gain on local detail; above 1 exaggerates it.
+};
+
+// Maps linear RGB radiance (e.g. from merge_hdr) to a displayable 8-bit sRGB
+// image. The operators compress luminance only and scale every pixel's RGB by
+// the same factor, which keeps its hue; channels that still exceed 1 clip.
+// The local operator splits log luminance into a base layer, the coarsest
+// level of a Gaussian pyramid expanded back to full size, and the detail on
+// top of it. Only the base is compressed, so textures keep their contrast in
+// both bright and dark regions; very strong edges may show faint halos.
+std::vector<uint8_t> tonemap(const std::vector<float>& radiance, int width, int height,
+                             ToneMapOperator op, const ToneMapParams& params = {});
+
+// --- Analysis ---
+
+// Maps an ISO value to a recommended denoise strength in [0, 1). The curve is
+// zero up to ISO 100 and grows with every stop, flattening at high ISO.
+float suggest_denoise_strength(float iso);
+
+// Suggests an exposure change in stops (as used by DevelopSettings) that moves
+// the median linear luminance to middle gray (0.18), reduced if needed so the
+// brightest 1% of pixels do not clip. The result is limited to [-5, 5].
+// Expects RGB input.
+float suggest_exposure(const std::vector<uint8_t>& image, int width, int height);
+
+// Per-channel statistics of an 8-bit image. A value counts as clipped in the
+// shadows when it is at or below the low threshold and in the highlights when
+// it is at or above the high one; the percentages are of all pixels.
+struct ChannelStats {
+    float mean = 0.0f;
+    uint8_t min = 0;
+    uint8_t max = 0;
+    size_t clipped_low = 0;
+    size_t clipped_high = 0;
+    float clipped_low_percent = 0.0f;
+    float clipped_high_percent = 0.0f;
+};
+
+// Computes the statistics of every channel in one pass, split across threads
+// for large images. Requires low < high.
+std::vector<ChannelStats> image_stats(const std::vector<uint8_t>& image, int width, int height,
+                                      int channels, uint8_t low = 0, uint8_t high = 255);
+
+// Computes a 64-bit perceptual hash for finding near-duplicates. The
+// luminance is area-averaged down to 32x32 and transformed with a 2D DCT; bit
+// i (counting from the least significant) is set when the i-th of the 8x8
+// lowest-frequency coefficients, in row-major order, is above their median.
+// Rescaled, recompressed, or slightly retouched copies give hashes that
+// differ in only a few bits. Expects RGB input.
+uint64_t phash(const std::vector<uint8_t>& image, int width, int height);
+
+// The number of bits in which two hashes differ (their Hamming distance).
+// For phash, distances up to about 10 of 64 usually mean the same picture.
+int hash_distance(uint64_t a, uint64_t b);
+
+// The R, G, B multipliers that would turn the pixel at (x, y) neutral gray,
+// for a white balance picker; the image itself is not changed. The pixel is
+// averaged with its neighbours within `radius` in linear light, where white
+// balance works, and the multipliers are normalized so green is 1, like
+// LibRaw's. Areas darker than 0.5% of white in any channel hold mostly
+// noise, so they give no result. Expects RGB input.
+std::optional<std::array<float, 3>> sample_neutral_multipliers(const std::vector<uint8_t>& image,
+                                                               int width, int height, int x, int y,
+                                                               int radius = 1);
+
+// --- Scopes ---
+// Scopes render an analysis of an image as an RGB buffer, ready to be shown
+// by a frontend as is.
+
+// Draws the per-channel histograms of an image as an `out_width` x
+// `out_height` RGB image: dark gray background, bin counts growing upwards,
+// and each channel filled in its own color. Overlapping fills add up, so
+// where all three channels coincide the plot turns light gray. Single-channel
+// images are drawn in gray; a fourth (alpha) channel is ignored. Columns
+// cover equal shares of the 256 values, and every curve is scaled so the
+// largest bin of any channel reaches the top.
+std::vector<uint8_t> render_histogram(const std::vector<uint8_t>& image, int width, int height,
+                                      int channels, int out_width, int out_height);
+
+// Draws a luma waveform, like a video scope: column x of the output shows
+// the distribution of the luma values in column x of the image, with black
+// at the bottom and white at the top of `out_height` rows. Brightness follows
+// the square root of the number of pixels at each level, relative to the
+// most populated level of any column, so sparse values stay visible. The
+// output is RGB with the image's width. Expects RGB input.
+std::vector<uint8_t> render_waveform(const std::vector<uint8_t>& image, int width, int height,
+                                     int out_height);
+
+// Draws a vectorscope: a `size` x `size` RGB plot of every pixel's chroma,
+// with Cb (blue difference) growing to the right and Cr (red difference)
+// growing upwards around the neutral center, as in BT.601 video scopes. Red
+// lands in the upper left, blue on the right, and green in the lower left.
+// Brightness is normalized like render_waveform. Expects RGB input.
+std::vector<uint8_t> render_vectorscope(const std::vector<uint8_t>& image, int width, int height,
+                                        int size);
+
+// --- Develop ---
+
+// The basic adjustments of a develop panel. All values default to 0, which
+// leaves the image unchanged.
+struct DevelopSettings {
+    float exposure = 0.0f;   // In stops, applied to linear light.
+    float contrast = 0.0f;   // [-1, 1], around middle gray.
+    float saturation = 0.0f; // [-1, 1]; -1 is grayscale.
+    float temp = 0.0f;       // [-1, 1]; positive is warmer.
+    float tint = 0.0f;       // [-1, 1]; positive is more magenta.
+    float highlights = 0.0f; // [-1, 1]; negative recovers bright areas.
+    float shadows = 0.0f;    // [-1, 1]; positive lifts dark areas.
+    float sharpen = 0.0f;    // [0, 2], unsharp mask amount on luminance.
+};
+
+// Applies all settings in a fixed order on a float working copy: white
+// balance and exposure in linear light, then highlights/shadows, contrast,
+// saturation, and sharpening on the sRGB encoded values. The result is
+// clamped only once at the end, so intermediate steps never lose detail.
+// Expects RGB input.
+std::vector<uint8_t> develop(const std::vector<uint8_t>& image, int width, int height,
+                             const DevelopSettings& settings);
+
+#endif // MPR_IMAGE_OPS_H
@@ -1,6 +1,12 @@
 #ifndef MPR_IMAGE_TYPES_H
 #define MPR_IMAGE_TYPES_H
 
+#include <array>
+#include <cstddef>
+#include <cstdint>
+#include <functional>
+#include <optional>
+#include <stdexcept>
 #include <string>
 #include <vector>
 
@@ -8,12 +14,236 @@ struct ThumbnailData {
     std::vector<char> data;
 };
 
+// One of the previews embedded in a raw file, as listed in its headers.
+// Some formats only state the dimensions once the preview is unpacked; they
+// are 0 then.
+struct ThumbnailInfo {
+    int width = 0;
+    int height = 0;
+    bool jpeg = false; // Otherwise a bitmap or a format LibRaw converts.
+    size_t size = 0;   // Bytes stored in the file.
+};
+
+// Where a preview returned by load_best_preview came from, from fastest to
+// slowest.
+enum class PreviewSource {
+    Thumbnail, // The largest embedded preview, no demosaicing at all.
+    HalfSize,  // A half-size decode (one pixel per 2x2 sensor block).
+    Full,      // A full-resolution decode.
+};
+
+// An upright JPEG preview and how it was made.
+struct BestPreview {
+    std::vector<uint8_t> jpeg;
+    PreviewSource source = PreviewSource::Thumbnail;
+    int width = 0;
+    int height = 0;
+};
+
+// The state of the preview cache. Hits and misses count lookups since the
+// program started; entries and bytes describe what the cache holds now.
+struct PreviewCacheStats {
+    size_t hits = 0;
+    size_t misses = 0;
+    size_t entries = 0;
+    size_t bytes = 0; // JPEG data held.
+};
+
+// Focal lengths in millimetres; 0 if unknown. The 35mm equivalent is the
+// value the camera recorded, which accounts for the sensor's crop factor.
+struct FocalLength {
+    float actual = 0.0f;
+    float equivalent_35mm = 0.0f;
+};
+
+// How the shot was taken, as far as the file records it.
+struct CaptureFlags {
+    bool flash_fired = false;
+    // The EXIF MeteringMode code: 0 unknown, 1 average, 2 center-weighted
+    // average, 3 spot, 4 multi-spot, 5 pattern, 6 partial, 255 other.
+    int metering_mode = 0;
+};
+
 struct Metadata {
     std::string make;
     std::string model;
     float iso_speed = 0.0f;
     float shutter = 0.0f;
     float aperture = 0.0f;
+    int64_t timestamp = 0; // Capture time in seconds since the epoch; 0 if unknown.
+    FocalLength focal_length;
+    CaptureFlags capture_flags;
+    // Whether the raw data was stored with lossy compression, such as a lossy
+    // DNG; decodes of it have less latitude than the sensor could deliver.
+    bool lossy_compression = false;
+    // Size of a full decode. Decodes are rotated upright, so for portrait
+    // shots these are the sensor dimensions swapped.
+    int width = 0;
+    int height = 0;
+};
+
+// A rectangle of pixels, with (x, y) its top-left corner.
+struct CropRect {
+    int x = 0;
+    int y = 0;
+    int width = 0;
+    int height = 0;
+};
+
+struct ImageBuffer {
+    std::vector<uint8_t> data;
+    int width = 0;
+    int height = 0;
+    int channels = 0;
+};
+
+// The undemosaiced sensor data of a Bayer camera (rawdata.raw_image). Values
+// are sensor-linear: proportional to the light hitting each photosite, with
+// the black level still included and no white balance or color conversion.
+struct RawBayerData {
+    std::vector<uint16_t> data; // raw_width x raw_height, one sample per photosite.
+    int raw_width = 0;
+    int raw_height = 0;
+    // The visible image within the sensor data, as used by dcraw_process.
+    int left_margin = 0;
+    int top_margin = 0;
+    int width = 0;
+    int height = 0;
+    // Colors of the 2x2 block at the top-left of the visible area, row by row,
+    // e.g. "RGGB".
+    std::string cfa_pattern;
+    unsigned black = 0;
+    unsigned maximum = 0;
+};
+
+// A demosaiced RGB image with linear 16-bit samples, as input to an HDR
+// merge. `max_value` is the largest sample in the image: saturated sensor
+// data ends up there or close to it, so merges treat it as the clip level.
+struct Linear16Image {
+    std::vector<uint16_t> data; // width x height x 3.
+    int width = 0;
+    int height = 0;
+    uint16_t max_value = 0;
+};
+
+// A decoded image with float samples, as machine learning models take them.
+struct Float32Image {
+    std::vector<float> data; // width x height x channels, interleaved.
+    int width = 0;
+    int height = 0;
+    int channels = 0;
+};
+
+// Processing parameters passed on to LibRaw for a decode. The defaults match
+// LibRaw's own, so a default constructed value changes nothing.
+struct DecodeOptions {
+    float bright = 1.0f; // params.bright: scales the output brightness.
+    // params.gamm[0..1]: the inverse power and the slope of the linear toe of
+    // the output curve. The default is BT.709; {1.0, 1.0} gives linear output.
+    std::array<double, 2> gamma = {0.45, 4.5};
+    int bits = 8; // params.output_bps: 8 or 16 bits per sample.
+    // params.half_size: skips demosaicing and outputs one pixel per 2x2 block
+    // of the sensor, which is about four times faster.
+    bool half_size = false;
+    // params.user_qual: the demosaic algorithm. -1 lets LibRaw choose (AHD);
+    // 0 linear, 1 VNG, 2 PPG, 3 AHD, 4 DCB, 11 DHT, 12 AAHD.
+    int quality = -1;
+    // params.four_color_rgb: interpolate RGB as four colors (two separate
+    // greens), which removes maze artifacts on some sensors.
+    bool four_color_rgb = false;
+    // params.dcb_iterations and dcb_enhance_fl: refinement passes and the
+    // false-color suppression of DCB demosaicing (quality 4 only). -1 is
+    // LibRaw's default number of passes.
+    int dcb_iterations = -1;
+    bool dcb_enhance = false;
+    // params.med_passes: 3x3 median filter passes over the color differences
+    // after demosaicing, a cheap way to reduce color artifacts and mild noise.
+    int med_passes = 0;
+    // params.auto_bright_thr: the fraction of pixels auto-brightness lets clip
+    // to white when it stretches the histogram. Larger values brighten more;
+    // must be in (0, 0.5].
+    float auto_bright_thr = 0.01f;
+    // params.use_camera_wb: white balance with the camera's as-shot
+    // multipliers instead of LibRaw's daylight ones.
+    bool use_camera_wb = false;
+    // params.use_camera_matrix: convert colors with the matrix embedded in
+    // the file instead of LibRaw's own for the camera model. LibRaw uses it
+    // for DNG files, and for other files only together with use_camera_wb.
+    bool use_camera_matrix = true;
+    // params.user_flip: the orientation to rotate the output to, overriding
+    // the one the camera recorded, for bodies that record it wrongly. -1 uses
+    // the camera's; otherwise a LibRaw flip code from 0 to 7 (0 none, 3 180
+    // degrees, 5 90 degrees counter-clockwise, 6 90 degrees clockwise).
+    int user_flip = -1;
+    // Linear output for scientific use: still demosaiced, but as close to the
+    // sensor data as the pipeline allows. Overrides `bright`, `gamma`, and
+    // `auto_bright_thr` by setting
+    //   params.gamm = {1, 1}          (no gamma curve),
+    //   params.no_auto_bright = 1     (no histogram stretch),
+    //   params.user_mul = {1, 1, 1, 1} (no white balance scaling),
+    //   params.output_color = 0       (camera color space, no color matrix).
+    // Combine with bits = 16 to keep the precision.
+    bool linear = false;
+    // Not a LibRaw parameter: decode_image returns the channels one after
+    // another (RRR...GGG...BBB...) instead of interleaved (RGBRGB...).
+    bool planar = false;
+    // Not a LibRaw parameter: applies the gain maps and lens distortion
+    // corrections stored in a DNG file's opcode lists (see dng_opcodes.h).
+    // Decodes of images loaded by handle only; other files are unaffected.
+    bool dng_opcodes = false;
+};
+
+struct DecodeDiagnostics {
+    double process_ms = 0.0; // Time spent in LibRaw's dcraw_process.
+    double copy_ms = 0.0;    // Time spent copying the result out of LibRaw.
+    size_t buffer_size = 0;  // Size of the decoded pixel buffer in bytes.
+};
+
+// Receives the name of the current LibRaw processing stage and the progress
+// within it as a fraction in [0, 1].
+using ProgressCallback = std::function<void(const std::string& stage, float fraction)>;
+
+// Receives `stripe_height` consecutive rows of a decode, starting at row
+// `y_offset`: `size` bytes of interleaved samples in the decode's format. The
+// data is only valid during the call.
+using StripeCallback =
+    std::function<void(int y_offset, const uint8_t* data, size_t size, int stripe_height)>;
+
+// Thrown when a decode with a time limit runs past it.
+struct DecodeTimeout : std::runtime_error {
+    using std::runtime_error::runtime_error;
+};
+
+// A fully processed (demosaiced, color converted) image. For 16-bit output
+// `image.data` holds native-endian uint16 samples. With `planar` set, the
+// buffer holds one width x height plane per channel, in channel order;
+// otherwise the channels of each pixel are interleaved.
+struct DecodedImage {
+    ImageBuffer image;
+    int bits = 8;
+    bool planar = false;
+    // LibRaw's white level (color.maximum) and the largest value actually
+    // found in the raw data (color.data_maximum), both in sensor units.
+    unsigned maximum = 0;
+    unsigned data_maximum = 0;
+    std::optional<DecodeDiagnostics> diagnostics;
+};
+
+// The same raw file processed with the camera's as-shot white balance and
+// with LibRaw's automatic (gray world) white balance, for A/B comparison.
+// The multipliers are the R, G, B, G2 channel gains LibRaw applied.
+struct WhiteBalanceComparison {
+    DecodedImage camera;
+    DecodedImage automatic;
+    std::array<float, 4> camera_multipliers = {};
+    std::array<float, 4> auto_multipliers = {};
+};
+
+// The first phase of a two-phase develop: the developed half-size preview,
+// and the handle that finishes the develop at full resolution.
+struct DevelopPreview {
+    ImageBuffer preview;
+    uint64_t session = 0;
 };
 
 #endif // MPR_IMAGE_TYPES_H
\ No newline at end of file
@@ -0,0 +1,40 @@
+#ifndef MPR_XMP_H
+#define MPR_XMP_H
+
+#include <string>
+#include <vector>
+
+// --- XMP Sidecars ---
+// Cataloging metadata is kept in an XMP file next to the raw file, which is
+// never modified. Sidecars follow Adobe's naming: the raw file's extension is
+// replaced by ".xmp", so IMG_0001.CR2 gets IMG_0001.xmp.
+
+// The cataloging fields of a sidecar.
+struct XmpSidecar {
+    int rating = 0;    // xmp:Rating in [-1, 5]; -1 is rejected, 0 unrated.
+    std::string label; // xmp:Label, a color name such as "Red" in Lightroom.
+    std::vector<std::string> keywords; // dc:subject
+};
+
+// The sidecar path for a raw file.
+std::string xmp_sidecar_path(const std::string& raw_path);
+
+// Writes the sidecar of `raw_path` with an xmp:Rating, the keywords as a
+// dc:subject bag, and an xmp:Label unless `label` is empty, and returns the
+// sidecar's path. `rating` is in [-1, 5], where -1 marks a rejected image and
+// 0 an unrated one. An existing sidecar keeps everything but these three
+// properties, which are replaced (an empty label or keyword list removes
+// them); one without an rdf:Description throws std::runtime_error and is left
+// as it is.
+std::string write_xmp_sidecar(const std::string& raw_path, int rating,
+                              const std::vector<std::string>& keywords,
+                              const std::string& label = "");
+
+// Reads the sidecar of `raw_path`, such as one written by write_xmp_sidecar
+// or by Lightroom. Properties may be stored as attributes or as elements;
+// missing ones, or a missing sidecar, keep the defaults of XmpSidecar. This
+// is a small scanner, not a full XML parser: it only understands the
+// standard "xmp" and "dc" prefixes, which all common writers use.
+XmpSidecar read_xmp_sidecar(const std::string& raw_path);
+
+#endif // MPR_XMP_H
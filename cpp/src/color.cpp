@@ -0,0 +1,303 @@
+#include "mpr_photo_editor/color.h"
+#include "pixel_utils.h"
+#include <algorithm>
+#include <cmath>
+#include <cstddef>
+#include <stdexcept>
+
+// --- Internal Helpers ---
+namespace {
+
+// D65 reference white in XYZ.
+constexpr float white_x = 0.95047f;
+constexpr float white_y = 1.0f;
+constexpr float white_z = 1.08883f;
+
+float lab_f(float t) {
+    constexpr float epsilon = 216.0f / 24389.0f;
+    constexpr float kappa = 24389.0f / 27.0f;
+    return t > epsilon ? std::cbrt(t) : (kappa * t + 16.0f) / 116.0f;
+}
+
+float lab_f_inverse(float f) {
+    constexpr float epsilon = 216.0f / 24389.0f;
+    constexpr float kappa = 24389.0f / 27.0f;
+    float cubed = f * f * f;
+    return cubed > epsilon ? cubed : (116.0f * f - 16.0f) / kappa;
+}
+
+float wrap_hue(float hue) {
+    float h = std::fmod(hue, 360.0f);
+    return h < 0.0f ? h + 360.0f : h;
+}
+
+// Hue in degrees of an RGB triple with the given max and chroma (max - min).
+float rgb_hue(const Color& rgb, float max, float chroma) {
+    if (chroma <= 0.0f) {
+        return 0.0f;
+    }
+    float h;
+    if (max == rgb[0]) {
+        h = (rgb[1] - rgb[2]) / chroma;
+    } else if (max == rgb[1]) {
+        h = (rgb[2] - rgb[0]) / chroma + 2.0f;
+    } else {
+        h = (rgb[0] - rgb[1]) / chroma + 4.0f;
+    }
+    return wrap_hue(h * 60.0f);
+}
+
+// RGB from hue, chroma, and the amount added to every channel.
+Color hue_chroma_to_rgb(float hue, float chroma, float offset) {
+    float h = wrap_hue(hue) / 60.0f;
+    float x = chroma * (1.0f - std::fabs(std::fmod(h, 2.0f) - 1.0f));
+    Color rgb;
+    switch (static_cast<int>(h)) {
+        case 0: rgb = {chroma, x, 0.0f}; break;
+        case 1: rgb = {x, chroma, 0.0f}; break;
+        case 2: rgb = {0.0f, chroma, x}; break;
+        case 3: rgb = {0.0f, x, chroma}; break;
+        case 4: rgb = {x, 0.0f, chroma}; break;
+        default: rgb = {chroma, 0.0f, x}; break;
+    }
+    for (float& v : rgb) {
+        v += offset;
+    }
+    return rgb;
+}
+
+template <typename Convert>
+std::vector<float> convert_from_rgb(const std::vector<uint8_t>& image, int width, int height, Convert convert) {
+    check_buffer(image, width, height, 3);
+    std::vector<float> result(image.size());
+    for (size_t i = 0; i < image.size(); i += 3) {
+        Color converted = convert(Color{image[i] / 255.0f, image[i + 1] / 255.0f, image[i + 2] / 255.0f});
+        std::copy(converted.begin(), converted.end(), result.begin() + i);
+    }
+    return result;
+}
+
+template <typename Convert>
+std::vector<uint8_t> convert_to_rgb(const std::vector<float>& data, int width, int height, Convert convert) {
+    check_buffer(data, width, height, 3);
+    std::vector<uint8_t> result(data.size());
+    for (size_t i = 0; i < data.size(); i += 3) {
+        Color rgb = convert(Color{data[i], data[i + 1], data[i + 2]});
+        for (int c = 0; c < 3; ++c) {
+            result[i + c] = clamp_u8(rgb[c] * 255.0f);
+        }
+    }
+    return result;
+}
+
+// The temperature range of the Planckian locus approximation.
+constexpr double min_temperature = 1667.0;
+constexpr double max_temperature = 25000.0;
+constexpr double tint_scale = 3000.0;
+
+// CIE 1960 uv chromaticity.
+struct Uv {
+    double u;
+    double v;
+};
+
+// The point of the Planckian locus at `temperature` (Kim et al., 2002).
+Uv planckian_uv(double temperature) {
+    double t = 1000.0 / temperature;
+    double x = temperature <= 4000.0
+        ? ((-0.2661239 * t - 0.2343589) * t + 0.8776956) * t + 0.179910
+        : ((-3.0258469 * t + 2.1070379) * t + 0.2226347) * t + 0.240390;
+    double y = temperature <= 2222.0 ? ((-1.1063814 * x - 1.34811020) * x + 2.18555832) * x - 0.20219683
+             : temperature <= 4000.0 ? ((-0.9549476 * x - 1.37418593) * x + 2.09137015) * x - 0.16748867
+             : ((3.0817580 * x - 5.87338670) * x + 3.75112997) * x - 0.37001483;
+    double d = -2.0 * x + 12.0 * y + 3.0;
+    return {4.0 * x / d, 6.0 * y / d};
+}
+
+// The unit normal of the locus at `temperature`, pointing to positive Duv
+// (towards green).
+Uv planckian_normal(double temperature) {
+    Uv a = planckian_uv(temperature * 0.999);
+    Uv b = planckian_uv(temperature * 1.001);
+    double length = std::hypot(b.u - a.u, b.v - a.v);
+    Uv normal{-(b.v - a.v) / length, (b.u - a.u) / length};
+    return normal.v < 0.0 ? Uv{-normal.u, -normal.v} : normal;
+}
+
+} // namespace
+
+// --- Per-Pixel Conversions ---
+
+Color rgb_to_ycbcr(const Color& rgb) {
+    float y = 0.299f * rgb[0] + 0.587f * rgb[1] + 0.114f * rgb[2];
+    float cb = -0.168736f * rgb[0] - 0.331264f * rgb[1] + 0.5f * rgb[2];
+    float cr = 0.5f * rgb[0] - 0.418688f * rgb[1] - 0.081312f * rgb[2];
+    return {y, cb, cr};
+}
+
+Color ycbcr_to_rgb(const Color& ycbcr) {
+    float y = ycbcr[0], cb = ycbcr[1], cr = ycbcr[2];
+    return {y + 1.402f * cr, y - 0.344136f * cb - 0.714136f * cr, y + 1.772f * cb};
+}
+
+Color rgb_to_hsl(const Color& rgb) {
+    float max = std::max({rgb[0], rgb[1], rgb[2]});
+    float min = std::min({rgb[0], rgb[1], rgb[2]});
+    float chroma = max - min;
+    float lightness = 0.5f * (max + min);
+    float saturation = 0.0f;
+    if (chroma > 0.0f) {
+        saturation = chroma / (1.0f - std::fabs(2.0f * lightness - 1.0f));
+    }
+    return {rgb_hue(rgb, max, chroma), saturation, lightness};
+}
+
+Color hsl_to_rgb(const Color& hsl) {
+    float chroma = (1.0f - std::fabs(2.0f * hsl[2] - 1.0f)) * hsl[1];
+    return hue_chroma_to_rgb(hsl[0], chroma, hsl[2] - 0.5f * chroma);
+}
+
+Color rgb_to_hsv(const Color& rgb) {
+    float max = std::max({rgb[0], rgb[1], rgb[2]});
+    float min = std::min({rgb[0], rgb[1], rgb[2]});
+    float chroma = max - min;
+    float saturation = max > 0.0f ? chroma / max : 0.0f;
+    return {rgb_hue(rgb, max, chroma), saturation, max};
+}
+
+Color hsv_to_rgb(const Color& hsv) {
+    float chroma = hsv[2] * hsv[1];
+    return hue_chroma_to_rgb(hsv[0], chroma, hsv[2] - chroma);
+}
+
+Color rgb_to_lab(const Color& rgb) {
+    float r = srgb_decode(rgb[0]), g = srgb_decode(rgb[1]), b = srgb_decode(rgb[2]);
+    float x = 0.4124564f * r + 0.3575761f * g + 0.1804375f * b;
+    float y = 0.2126729f * r + 0.7151522f * g + 0.0721750f * b;
+    float z = 0.0193339f * r + 0.1191920f * g + 0.9503041f * b;
+    float fx = lab_f(x / white_x), fy = lab_f(y / white_y), fz = lab_f(z / white_z);
+    return {116.0f * fy - 16.0f, 500.0f * (fx - fy), 200.0f * (fy - fz)};
+}
+
+Color lab_to_rgb(const Color& lab) {
+    float fy = (lab[0] + 16.0f) / 116.0f;
+    float fx = fy + lab[1] / 500.0f;
+    float fz = fy - lab[2] / 200.0f;
+    float x = white_x * lab_f_inverse(fx), y = white_y * lab_f_inverse(fy), z = white_z * lab_f_inverse(fz);
+    float r = 3.2404542f * x - 1.5371385f * y - 0.4985314f * z;
+    float g = -0.9692660f * x + 1.8760108f * y + 0.0415560f * z;
+    float b = 0.0556434f * x - 0.2040259f * y + 1.0572252f * z;
+    return {srgb_encode(std::max(r, 0.0f)), srgb_encode(std::max(g, 0.0f)), srgb_encode(std::max(b, 0.0f))};
+}
+
+// --- White Balance ---
+
+Color temp_tint_to_multipliers(float temperature, float tint) {
+    if (!(temperature >= min_temperature && temperature <= max_temperature)) {
+        throw std::invalid_argument("Temperature must be between 1667 and 25000 K");
+    }
+    Uv locus = planckian_uv(temperature);
+    Uv normal = planckian_normal(temperature);
+    double duv = tint / tint_scale;
+    double u = locus.u + duv * normal.u;
+    double v = locus.v + duv * normal.v;
+
+    // uv to xy, then to XYZ with Y = 1 and on to linear sRGB.
+    double d = 2.0 * u - 8.0 * v + 4.0;
+    double x = 3.0 * u / d;
+    double y = 2.0 * v / d;
+    double cx = x / y;
+    double cz = (1.0 - x - y) / y;
+    double r = 3.2404542 * cx - 1.5371385 - 0.4985314 * cz;
+    double g = -0.9692660 * cx + 1.8760108 + 0.0415560 * cz;
+    double b = 0.0556434 * cx - 0.2040259 + 1.0572252 * cz;
+    if (!(r > 0.0 && g > 0.0 && b > 0.0)) {
+        throw std::invalid_argument("Temperature and tint describe a color outside the sRGB gamut");
+    }
+    return {static_cast<float>(g / r), 1.0f, static_cast<float>(g / b)};
+}
+
+TempTint multipliers_to_temp_tint(const Color& multipliers) {
+    if (!(multipliers[0] > 0.0f && multipliers[1] > 0.0f && multipliers[2] > 0.0f)) {
+        throw std::invalid_argument("Multipliers must be positive");
+    }
+    // The illuminant's white is the color the multipliers turn neutral.
+    double r = 1.0 / multipliers[0], g = 1.0 / multipliers[1], b = 1.0 / multipliers[2];
+    double cx = 0.4124564 * r + 0.3575761 * g + 0.1804375 * b;
+    double cy = 0.2126729 * r + 0.7151522 * g + 0.0721750 * b;
+    double cz = 0.0193339 * r + 0.1191920 * g + 0.9503041 * b;
+    double x = cx / (cx + cy + cz);
+    double y = cy / (cx + cy + cz);
+    double d = -2.0 * x + 12.0 * y + 3.0;
+    Uv point{4.0 * x / d, 6.0 * y / d};
+
+    // The nearest point of the locus, searched in mired (1e6 / K), where
+    // the locus is spaced about evenly: a coarse scan, then golden-section
+    // refinement around the best step.
+    auto distance = [&](double mired) {
+        Uv locus = planckian_uv(1e6 / mired);
+        return std::hypot(point.u - locus.u, point.v - locus.v);
+    };
+    double low_mired = 1e6 / max_temperature;
+    double high_mired = 1e6 / min_temperature;
+    constexpr int steps = 512;
+    double step = (high_mired - low_mired) / steps;
+    int best = 0;
+    for (int i = 1; i <= steps; ++i) {
+        if (distance(low_mired + i * step) < distance(low_mired + best * step)) {
+            best = i;
+        }
+    }
+    double lower = low_mired + std::max(best - 1, 0) * step;
+    double upper = low_mired + std::min(best + 1, steps) * step;
+    constexpr double ratio = 0.6180339887498949;
+    for (int i = 0; i < 60; ++i) {
+        double m1 = upper - ratio * (upper - lower);
+        double m2 = lower + ratio * (upper - lower);
+        if (distance(m1) < distance(m2)) {
+            upper = m2;
+        } else {
+            lower = m1;
+        }
+    }
+    double temperature = 1e6 / (0.5 * (lower + upper));
+
+    Uv locus = planckian_uv(temperature);
+    Uv normal = planckian_normal(temperature);
+    double duv = (point.u - locus.u) * normal.u + (point.v - locus.v) * normal.v;
+    return {static_cast<float>(temperature), static_cast<float>(duv * tint_scale)};
+}
+
+// --- Whole-Buffer Conversions ---
+
+std::vector<float> rgb_to_ycbcr(const std::vector<uint8_t>& image, int width, int height) {
+    return convert_from_rgb(image, width, height, [](const Color& c) { return rgb_to_ycbcr(c); });
+}
+
+std::vector<uint8_t> ycbcr_to_rgb(const std::vector<float>& data, int width, int height) {
+    return convert_to_rgb(data, width, height, [](const Color& c) { return ycbcr_to_rgb(c); });
+}
+
+std::vector<float> rgb_to_hsl(const std::vector<uint8_t>& image, int width, int height) {
+    return convert_from_rgb(image, width, height, [](const Color& c) { return rgb_to_hsl(c); });
+}
+
+std::vector<uint8_t> hsl_to_rgb(const std::vector<float>& data, int width, int height) {
+    return convert_to_rgb(data, width, height, [](const Color& c) { return hsl_to_rgb(c); });
+}
+
+std::vector<float> rgb_to_hsv(const std::vector<uint8_t>& image, int width, int height) {
+    return convert_from_rgb(image, width, height, [](const Color& c) { return rgb_to_hsv(c); });
+}
+
+std::vector<uint8_t> hsv_to_rgb(const std::vector<float>& data, int width, int height) {
+    return convert_to_rgb(data, width, height, [](const Color& c) { return hsv_to_rgb(c); });
+}
+
+std::vector<float> rgb_to_lab(const std::vector<uint8_t>& image, int width, int height) {
+    return convert_from_rgb(image, width, height, [](const Color& c) { return rgb_to_lab(c); });
+}
+
+std::vector<uint8_t> lab_to_rgb(const std::vector<float>& data, int width, int height) {
+    return convert_to_rgb(data, width, height, [](const Color& c) { return lab_to_rgb(c); });
+}
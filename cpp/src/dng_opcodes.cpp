@@ -0,0 +1,409 @@
+#include "mpr_photo_editor/dng_opcodes.h"
+#include "pixel_utils.h"
+#include <algorithm>
+#include <cmath>
+#include <cstring>
+#include <fstream>
+#include <set>
+#include <stdexcept>
+
+namespace {
+
+constexpr uint32_t opcode_warp_rectilinear = 1;
+constexpr uint32_t opcode_gain_map = 9;
+
+constexpr uint16_t tag_new_subfile_type = 254;
+constexpr uint16_t tag_sub_ifds = 330;
+constexpr uint16_t tag_opcode_list2 = 51009;
+constexpr uint16_t tag_opcode_list3 = 51022;
+
+// Far larger than any real opcode list; larger fields mean a corrupt file.
+constexpr size_t max_field_size = size_t(64) << 20;
+
+// Opcode lists are big-endian whatever the byte order of the file.
+class OpcodeReader {
+public:
+    OpcodeReader(const uint8_t* data, size_t size) : data(data), size(size) {}
+
+    uint32_t u32() {
+        const uint8_t* p = take(4);
+        return (uint32_t(p[0]) << 24) | (uint32_t(p[1]) << 16) | (uint32_t(p[2]) << 8) | p[3];
+    }
+
+    int count() {
+        uint32_t value = u32();
+        if (value > (1u << 24)) {
+            throw std::invalid_argument("DNG opcode parameter out of range: " + std::to_string(value));
+        }
+        return static_cast<int>(value);
+    }
+
+    double f64() {
+        uint64_t bits = (uint64_t(u32()) << 32) | u32();
+        double value;
+        std::memcpy(&value, &bits, sizeof(value));
+        return value;
+    }
+
+    float f32() {
+        uint32_t bits = u32();
+        float value;
+        std::memcpy(&value, &bits, sizeof(value));
+        return value;
+    }
+
+    void skip(size_t n) { take(n); }
+
+    size_t remaining() const { return size - position; }
+
+private:
+    const uint8_t* take(size_t n) {
+        if (remaining() < n) {
+            throw std::invalid_argument("DNG opcode list ends early");
+        }
+        const uint8_t* p = data + position;
+        position += n;
+        return p;
+    }
+
+    const uint8_t* data;
+    size_t size;
+    size_t position = 0;
+};
+
+DngGainMap parse_gain_map(OpcodeReader& reader) {
+    DngGainMap map;
+    map.top = reader.count();
+    map.left = reader.count();
+    map.bottom = reader.count();
+    map.right = reader.count();
+    map.plane = reader.count();
+    map.planes = reader.count();
+    map.row_pitch = reader.count();
+    map.col_pitch = reader.count();
+    map.points_v = reader.count();
+    map.points_h = reader.count();
+    map.spacing_v = reader.f64();
+    map.spacing_h = reader.f64();
+    map.origin_v = reader.f64();
+    map.origin_h = reader.f64();
+    map.map_planes = reader.count();
+    if (map.points_v < 1 || map.points_h < 1 || map.map_planes < 1 || map.planes < 1 ||
+        map.row_pitch < 1 || map.col_pitch < 1) {
+        throw std::invalid_argument("DNG gain map has an empty grid or a zero pitch");
+    }
+    if ((map.points_v > 1 && !(map.spacing_v > 0.0)) || (map.points_h > 1 && !(map.spacing_h > 0.0))) {
+        throw std::invalid_argument("DNG gain map spacing must be positive");
+    }
+    size_t count = static_cast<size_t>(map.points_v) * map.points_h * map.map_planes;
+    if (reader.remaining() < count * 4) {
+        throw std::invalid_argument("DNG gain map has fewer gains than its grid needs");
+    }
+    map.gains.resize(count);
+    for (float& gain : map.gains) {
+        gain = reader.f32();
+    }
+    return map;
+}
+
+DngWarpRectilinear parse_warp_rectilinear(OpcodeReader& reader) {
+    DngWarpRectilinear warp;
+    int planes = reader.count();
+    if (planes < 1 || planes > 4) {
+        throw std::invalid_argument("DNG WarpRectilinear needs 1 to 4 coefficient sets");
+    }
+    warp.coefficients.resize(planes);
+    for (std::array<double, 6>& coefficients : warp.coefficients) {
+        for (double& k : coefficients) {
+            k = reader.f64();
+        }
+    }
+    warp.center_x = reader.f64();
+    warp.center_y = reader.f64();
+    return warp;
+}
+
+// Reads the parts of a TIFF file the opcode lists live in, in the file's
+// byte order.
+class TiffReader {
+public:
+    explicit TiffReader(const std::string& filepath) : file(filepath, std::ios::binary) {
+        if (!file) {
+            throw std::runtime_error("Could not open " + filepath);
+        }
+        uint8_t header[4] = {};
+        file.read(reinterpret_cast<char*>(header), 4);
+        if (file && header[0] == 'I' && header[1] == 'I' && header[2] == 42 && header[3] == 0) {
+            little_endian = true;
+            valid = true;
+        } else if (file && header[0] == 'M' && header[1] == 'M' && header[2] == 0 && header[3] == 42) {
+            little_endian = false;
+            valid = true;
+        }
+    }
+
+    bool is_tiff() const { return valid; }
+
+    std::vector<uint8_t> bytes(uint32_t offset, size_t size) {
+        std::vector<uint8_t> result(size);
+        file.clear();
+        file.seekg(offset);
+        file.read(reinterpret_cast<char*>(result.data()), static_cast<std::streamsize>(size));
+        if (!file) {
+            throw std::runtime_error("TIFF structure points past the end of the file");
+        }
+        return result;
+    }
+
+    uint32_t value(const uint8_t* p, size_t size) const {
+        uint32_t result = 0;
+        for (size_t i = 0; i < size; ++i) {
+            result |= uint32_t(p[i]) << (8 * (little_endian ? i : size - 1 - i));
+        }
+        return result;
+    }
+
+    uint32_t u32_at(uint32_t offset) {
+        std::vector<uint8_t> data = bytes(offset, 4);
+        return value(data.data(), 4);
+    }
+
+private:
+    std::ifstream file;
+    bool little_endian = true;
+    bool valid = false;
+};
+
+// The size in bytes of one value of a TIFF field type, 0 for unknown types.
+size_t tiff_type_size(uint32_t type) {
+    switch (type) {
+        case 1: case 2: case 6: case 7: return 1;
+        case 3: case 8: return 2;
+        case 4: case 9: case 11: case 13: return 4;
+        case 5: case 10: case 12: return 8;
+        default: return 0;
+    }
+}
+
+// Bilinear interpolation of one channel of a 16-bit image at a continuous
+// position, where pixel (x, y) covers [x, x + 1) x [y, y + 1). Positions off
+// the image repeat its edge.
+float sample_u16(const uint16_t* image, int width, int height, int channels, int channel, double fx, double fy) {
+    fx = std::clamp(fx - 0.5, 0.0, static_cast<double>(width - 1));
+    fy = std::clamp(fy - 0.5, 0.0, static_cast<double>(height - 1));
+    int x0 = static_cast<int>(fx);
+    int y0 = static_cast<int>(fy);
+    int x1 = std::min(x0 + 1, width - 1);
+    int y1 = std::min(y0 + 1, height - 1);
+    double tx = fx - x0;
+    double ty = fy - y0;
+    auto at = [&](int x, int y) { return image[(static_cast<size_t>(y) * width + x) * channels + channel]; };
+    double upper = at(x0, y0) + tx * (at(x1, y0) - at(x0, y0));
+    double lower = at(x0, y1) + tx * (at(x1, y1) - at(x0, y1));
+    return static_cast<float>(upper + ty * (lower - upper));
+}
+
+} // namespace
+
+float DngGainMap::gain_at(double v, double h, int map_plane) const {
+    double gv = points_v > 1 ? std::clamp((v - origin_v) / spacing_v, 0.0, points_v - 1.0) : 0.0;
+    double gh = points_h > 1 ? std::clamp((h - origin_h) / spacing_h, 0.0, points_h - 1.0) : 0.0;
+    int v0 = static_cast<int>(gv);
+    int h0 = static_cast<int>(gh);
+    int v1 = std::min(v0 + 1, points_v - 1);
+    int h1 = std::min(h0 + 1, points_h - 1);
+    double tv = gv - v0;
+    double th = gh - h0;
+    auto at = [&](int row, int col) { return gains[(static_cast<size_t>(row) * points_h + col) * map_planes + map_plane]; };
+    double upper = at(v0, h0) + th * (at(v0, h1) - at(v0, h0));
+    double lower = at(v1, h0) + th * (at(v1, h1) - at(v1, h0));
+    return static_cast<float>(upper + tv * (lower - upper));
+}
+
+DngOpcodes parse_dng_opcode_list(const std::vector<uint8_t>& data) {
+    DngOpcodes result;
+    OpcodeReader list(data.data(), data.size());
+    uint32_t count = list.u32();
+    for (uint32_t i = 0; i < count; ++i) {
+        uint32_t id = list.u32();
+        list.u32(); // The DNG version the opcode was specified in.
+        list.u32(); // Flags: optional, and skippable for previews.
+        uint32_t size = list.u32();
+        if (list.remaining() < size) {
+            throw std::invalid_argument("DNG opcode " + std::to_string(id) + " is longer than its list");
+        }
+        size_t offset = data.size() - list.remaining();
+        OpcodeReader parameters(data.data() + offset, size);
+        switch (id) {
+            case opcode_gain_map: result.gain_maps.push_back(parse_gain_map(parameters)); break;
+            case opcode_warp_rectilinear: result.warps.push_back(parse_warp_rectilinear(parameters)); break;
+            default: result.skipped.push_back(id); break;
+        }
+        list.skip(size);
+    }
+    return result;
+}
+
+DngOpcodeLists read_dng_opcodes(const std::string& filepath) {
+    TiffReader tiff(filepath);
+    DngOpcodeLists result;
+    if (!tiff.is_tiff()) {
+        return result;
+    }
+
+    // Walks all IFDs and SubIFDs; the full-resolution image is the one whose
+    // NewSubFileType is 0. Offsets already seen are skipped, so a corrupt file
+    // with a loop in its IFD chain cannot hang the walk.
+    std::vector<uint8_t> lists[2];
+    std::vector<uint32_t> pending = {tiff.u32_at(4)};
+    std::set<uint32_t> seen;
+    while (!pending.empty() && seen.size() < 64) {
+        uint32_t offset = pending.back();
+        pending.pop_back();
+        if (offset == 0 || !seen.insert(offset).second) {
+            continue;
+        }
+        std::vector<uint8_t> count_bytes = tiff.bytes(offset, 2);
+        uint32_t count = tiff.value(count_bytes.data(), 2);
+        std::vector<uint8_t> entries = tiff.bytes(offset + 2, count * 12 + 4);
+
+        uint32_t subfile_type = 0;
+        std::vector<uint8_t> found[2];
+        for (uint32_t i = 0; i < count; ++i) {
+            const uint8_t* entry = entries.data() + i * 12;
+            uint32_t tag = tiff.value(entry, 2);
+            uint32_t type = tiff.value(entry + 2, 2);
+            uint32_t values = tiff.value(entry + 4, 4);
+            size_t size = tiff_type_size(type) * values;
+            if (size > max_field_size) {
+                throw std::runtime_error("TIFF field of " + std::to_string(size) + " bytes in " + filepath);
+            }
+            // Values of up to four bytes are stored in the entry itself.
+            auto field = [&] {
+                return size <= 4 ? std::vector<uint8_t>(entry + 8, entry + 8 + size)
+                                 : tiff.bytes(tiff.value(entry + 8, 4), size);
+            };
+            if (tag == tag_new_subfile_type) {
+                subfile_type = tiff.value(entry + 8, tiff_type_size(type) == 2 ? 2 : 4);
+            } else if (tag == tag_sub_ifds && (type == 4 || type == 13)) {
+                std::vector<uint8_t> offsets = field();
+                for (uint32_t j = 0; j < values; ++j) {
+                    pending.push_back(tiff.value(offsets.data() + j * 4, 4));
+                }
+            } else if (tag == tag_opcode_list2) {
+                found[0] = field();
+            } else if (tag == tag_opcode_list3) {
+                found[1] = field();
+            }
+        }
+        if (subfile_type == 0) {
+            for (int l = 0; l < 2; ++l) {
+                if (!found[l].empty()) {
+                    lists[l] = std::move(found[l]);
+                }
+            }
+        }
+        pending.push_back(tiff.value(entries.data() + count * 12, 4));
+    }
+
+    if (!lists[0].empty()) {
+        result.list2 = parse_dng_opcode_list(lists[0]);
+    }
+    if (!lists[1].empty()) {
+        result.list3 = parse_dng_opcode_list(lists[1]);
+    }
+    return result;
+}
+
+void apply_gain_map(const DngGainMap& map, int channel, uint16_t* image, int width, int height,
+                    int channels, int full_width, int full_height) {
+    check_dimensions(width, height, channels);
+    check_dimensions(full_width, full_height, 1);
+    bool pitched = map.row_pitch > 1 || map.col_pitch > 1;
+    if (channel < 0 && pitched && (width != full_width || height != full_height)) {
+        throw std::invalid_argument("A DNG gain map with a pitch needs a channel to apply to a scaled image");
+    }
+    double scale_x = static_cast<double>(full_width) / width;
+    double scale_y = static_cast<double>(full_height) / height;
+
+    parallel_chunks(static_cast<size_t>(height), 64, [&](size_t begin, size_t end) {
+        for (size_t y = begin; y < end; ++y) {
+            // Positions are those of pixel centres in the full-size image.
+            double full_y = (y + 0.5) * scale_y;
+            if (full_y < map.top || full_y >= map.bottom) {
+                continue;
+            }
+            if (channel < 0 && (static_cast<int>(y) - map.top) % map.row_pitch != 0) {
+                continue;
+            }
+            double v = full_y / full_height;
+            for (int x = 0; x < width; ++x) {
+                double full_x = (x + 0.5) * scale_x;
+                if (full_x < map.left || full_x >= map.right) {
+                    continue;
+                }
+                if (channel < 0 && (x - map.left) % map.col_pitch != 0) {
+                    continue;
+                }
+                double h = full_x / full_width;
+                uint16_t* pixel = image + (y * width + x) * channels;
+                if (channel >= 0) {
+                    if (channel < channels) {
+                        pixel[channel] = static_cast<uint16_t>(
+                            std::clamp(pixel[channel] * map.gain_at(v, h, 0), 0.0f, 65535.0f) + 0.5f);
+                    }
+                    continue;
+                }
+                for (int p = 0; p < map.planes && map.plane + p < channels; ++p) {
+                    float gain = map.gain_at(v, h, std::min(p, map.map_planes - 1));
+                    uint16_t& sample = pixel[map.plane + p];
+                    sample = static_cast<uint16_t>(std::clamp(sample * gain, 0.0f, 65535.0f) + 0.5f);
+                }
+            }
+        }
+    });
+}
+
+void apply_warp_rectilinear(const DngWarpRectilinear& warp, uint16_t* image, int width, int height,
+                            int channels) {
+    check_dimensions(width, height, channels);
+    if (warp.coefficients.empty()) {
+        return;
+    }
+    std::vector<uint16_t> source(image, image + static_cast<size_t>(width) * height * channels);
+
+    // Offsets are normalised by the distance from the optical center to the
+    // farthest corner, as the DNG specification defines.
+    double cx = warp.center_x * width;
+    double cy = warp.center_y * height;
+    double reach = 0.0;
+    for (double corner_x : {0.0, static_cast<double>(width)}) {
+        for (double corner_y : {0.0, static_cast<double>(height)}) {
+            reach = std::max(reach, std::hypot(corner_x - cx, corner_y - cy));
+        }
+    }
+    if (!(reach > 0.0)) {
+        return;
+    }
+
+    parallel_chunks(static_cast<size_t>(height), 64, [&](size_t begin, size_t end) {
+        for (size_t y = begin; y < end; ++y) {
+            for (int x = 0; x < width; ++x) {
+                double dx = (x + 0.5 - cx) / reach;
+                double dy = (y + 0.5 - cy) / reach;
+                double r2 = dx * dx + dy * dy;
+                uint16_t* pixel = image + (y * width + x) * channels;
+                for (int c = 0; c < channels; ++c) {
+                    const std::array<double, 6>& k =
+                        warp.coefficients[std::min<size_t>(c, warp.coefficients.size() - 1)];
+                    double radial = k[0] + r2 * (k[1] + r2 * (k[2] + r2 * k[3]));
+                    double sx = dx * radial + k[4] * 2.0 * dx * dy + k[5] * (r2 + 2.0 * dx * dx);
+                    double sy = dy * radial + k[5] * 2.0 * dx * dy + k[4] * (r2 + 2.0 * dy * dy);
+                    float value = sample_u16(source.data(), width, height, channels, c,
+                                             cx + sx * reach, cy + sy * reach);
+                    pixel[c] = static_cast<uint16_t>(std::clamp(value, 0.0f, 65535.0f) + 0.5f);
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,179 @@
+#include "mpr_photo_editor/image.h"
+#include "mpr_photo_editor/image_io.h"
+#include "mpr_photo_editor/image_ops.h"
+#include "pixel_utils.h"
+#include <algorithm>
+#include <cctype>
+#include <fstream>
+#include <stdexcept>
+#include <utility>
+
+Image::Image(std::vector<uint8_t> data, int width, int height, int channels) {
+    check_buffer(data, width, height, channels);
+    buffer = ImageBuffer{std::move(data), width, height, channels};
+    out_width = width;
+    out_height = height;
+}
+
+Image::Image(const Image& other) {
+    std::lock_guard<std::mutex> lock(other.mutex);
+    buffer = other.buffer;
+    pending = other.pending;
+    out_width = other.out_width;
+    out_height = other.out_height;
+    passes = other.passes;
+}
+
+Image::Image(Image&& other) noexcept {
+    std::lock_guard<std::mutex> lock(other.mutex);
+    buffer = std::move(other.buffer);
+    pending = std::move(other.pending);
+    out_width = other.out_width;
+    out_height = other.out_height;
+    passes = other.passes;
+}
+
+Image& Image::operator=(Image other) noexcept {
+    std::lock_guard<std::mutex> lock(mutex);
+    buffer = std::move(other.buffer);
+    pending = std::move(other.pending);
+    out_width = other.out_width;
+    out_height = other.out_height;
+    passes = other.passes;
+    return *this;
+}
+
+int Image::width() const {
+    std::lock_guard<std::mutex> lock(mutex);
+    return out_width;
+}
+
+int Image::height() const {
+    std::lock_guard<std::mutex> lock(mutex);
+    return out_height;
+}
+
+int Image::channels() const {
+    std::lock_guard<std::mutex> lock(mutex);
+    return buffer.channels;
+}
+
+size_t Image::buffer_passes() const {
+    std::lock_guard<std::mutex> lock(mutex);
+    return passes;
+}
+
+std::vector<uint8_t> Image::data() {
+    std::lock_guard<std::mutex> lock(mutex);
+    render_locked();
+    return buffer.data;
+}
+
+Image& Image::queue_lut(const ToneLut& lut) {
+    std::lock_guard<std::mutex> lock(mutex);
+    // Consecutive point operations merge right away, so the queue never
+    // holds two tables in a row.
+    if (!pending.empty() && pending.back().kind == Edit::Kind::Lut) {
+        pending.back().lut = compose_luts(pending.back().lut, lut);
+    } else {
+        pending.push_back(Edit{Edit::Kind::Lut, lut, CropRect()});
+    }
+    return *this;
+}
+
+Image& Image::brightness(float amount) {
+    return queue_lut(brightness_lut(amount));
+}
+
+Image& Image::contrast(float amount) {
+    return queue_lut(contrast_lut(amount));
+}
+
+Image& Image::gamma(float gamma) {
+    return queue_lut(gamma_lut(gamma));
+}
+
+Image& Image::invert() {
+    return queue_lut(invert_lut());
+}
+
+Image& Image::crop(const CropRect& rect) {
+    std::lock_guard<std::mutex> lock(mutex);
+    if (rect.width <= 0 || rect.height <= 0 || rect.x < 0 || rect.y < 0 ||
+        rect.x > out_width - rect.width || rect.y > out_height - rect.height) {
+        throw std::invalid_argument("Crop rectangle must be non-empty and lie within the image");
+    }
+    pending.push_back(Edit{Edit::Kind::Crop, ToneLut{}, rect});
+    out_width = rect.width;
+    out_height = rect.height;
+    return *this;
+}
+
+Image& Image::resize(int new_width, int new_height) {
+    if (new_width <= 0 || new_height <= 0) {
+        throw std::invalid_argument("Target dimensions must be positive");
+    }
+    std::lock_guard<std::mutex> lock(mutex);
+    pending.push_back(Edit{Edit::Kind::Resize, ToneLut{}, CropRect{0, 0, new_width, new_height}});
+    out_width = new_width;
+    out_height = new_height;
+    return *this;
+}
+
+Image& Image::render() {
+    std::lock_guard<std::mutex> lock(mutex);
+    render_locked();
+    return *this;
+}
+
+void Image::render_locked() {
+    for (const Edit& edit : pending) {
+        switch (edit.kind) {
+            case Edit::Kind::Lut:
+                apply_lut_inplace(edit.lut, buffer.data.data(), buffer.width, buffer.height, buffer.channels);
+                break;
+            case Edit::Kind::Crop:
+                buffer.data = crop_image(buffer.data, buffer.width, buffer.height, buffer.channels, edit.rect);
+                break;
+            case Edit::Kind::Resize:
+                buffer.data = resize_image(buffer.data, buffer.width, buffer.height, buffer.channels,
+                                           edit.rect.width, edit.rect.height);
+                break;
+        }
+        if (edit.kind != Edit::Kind::Lut) {
+            buffer.width = edit.rect.width;
+            buffer.height = edit.rect.height;
+        }
+        ++passes;
+    }
+    pending.clear();
+}
+
+Image& Image::save(const std::string& filepath, int quality) {
+    std::lock_guard<std::mutex> lock(mutex);
+    render_locked();
+    size_t dot = filepath.find_last_of('.');
+    std::string extension = dot == std::string::npos ? "" : filepath.substr(dot + 1);
+    std::transform(extension.begin(), extension.end(), extension.begin(),
+                   [](unsigned char c) { return static_cast<char>(std::tolower(c)); });
+
+    if (extension == "tif" || extension == "tiff") {
+        write_tiff(filepath, buffer.data, buffer.width, buffer.height, buffer.channels, 8);
+        return *this;
+    }
+    if (extension != "jpg" && extension != "jpeg") {
+        throw std::invalid_argument("Cannot tell the format of " + filepath + "; use .jpg, .jpeg, .tif, or .tiff");
+    }
+
+    std::vector<uint8_t> jpeg = encode_jpeg(buffer.data, buffer.width, buffer.height, buffer.channels, quality);
+    std::ofstream file(filepath, std::ios::binary | std::ios::trunc);
+    if (!file) {
+        throw std::runtime_error("Could not open " + filepath + " for writing");
+    }
+    file.write(reinterpret_cast<const char*>(jpeg.data()), static_cast<std::streamsize>(jpeg.size()));
+    file.close();
+    if (!file) {
+        throw std::runtime_error("Failed to write " + filepath);
+    }
+    return *this;
+}
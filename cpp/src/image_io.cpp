@@ -0,0 +1,632 @@
+#include "mpr_photo_editor/image_io.h"
+#include "mpr_photo_editor/image_ops.h"
+#include "pixel_utils.h"
+#include <csetjmp>
+#include <cstdio>
+#include <cstdlib>
+#include <cstring>
+#include <ctime>
+#include <fstream>
+#include <stdexcept>
+#include <jpeglib.h>
+
+// --- Internal Helpers ---
+namespace {
+
+// libjpeg reports fatal errors through error_exit, which by default calls
+// exit(). We jump back to the caller instead and turn the error into an
+// exception once we are out of libjpeg's frames.
+struct JpegErrorManager {
+    jpeg_error_mgr base;
+    std::jmp_buf jump;
+    char message[JMSG_LENGTH_MAX];
+};
+
+void jpeg_error_exit(j_common_ptr cinfo) {
+    auto* manager = reinterpret_cast<JpegErrorManager*>(cinfo->err);
+    (*cinfo->err->format_message)(cinfo, manager->message);
+    std::longjmp(manager->jump, 1);
+}
+
+J_COLOR_SPACE jpeg_input_space(int channels) {
+    switch (channels) {
+        case 1: return JCS_GRAYSCALE;
+        case 3: return JCS_RGB;
+        case 4: return JCS_EXT_RGBA;
+        default: throw std::invalid_argument("JPEG encoding supports 1, 3, or 4 channels");
+    }
+}
+
+// Builds a TIFF file in the host byte order. Values are copied with memcpy, so
+// no byte swapping is needed for either header fields or 16-bit samples.
+class TiffWriter {
+public:
+    template <typename T>
+    void put(size_t offset, T value) {
+        if (data.size() < offset + sizeof(T)) {
+            data.resize(offset + sizeof(T));
+        }
+        std::memcpy(data.data() + offset, &value, sizeof(T));
+    }
+
+    // Writes the TIFF header: the byte order mark matching the host ("II" on
+    // little-endian hosts, "MM" on big-endian ones), 42, and the offset of
+    // the first IFD.
+    void header(uint32_t ifd_offset) {
+        const uint16_t probe = 1;
+        uint8_t low_byte_first = 0;
+        std::memcpy(&low_byte_first, &probe, 1);
+        put<uint16_t>(0, low_byte_first ? 0x4949 : 0x4D4D);
+        put<uint16_t>(2, 42);
+        put<uint32_t>(4, ifd_offset);
+    }
+
+    // Appends an IFD entry; values of up to four bytes are stored inline.
+    void entry(uint16_t tag, uint16_t type, uint32_t count, uint32_t value) {
+        size_t offset = next_entry;
+        put<uint16_t>(offset, tag);
+        put<uint16_t>(offset + 2, type);
+        put<uint32_t>(offset + 4, count);
+        if (type == short_type && count == 1) {
+            put<uint16_t>(offset + 8, static_cast<uint16_t>(value));
+            put<uint16_t>(offset + 10, 0);
+        } else {
+            put<uint32_t>(offset + 8, value);
+        }
+        next_entry += 12;
+    }
+
+    static constexpr uint16_t ascii_type = 2;
+    static constexpr uint16_t short_type = 3;
+    static constexpr uint16_t long_type = 4;
+    static constexpr uint16_t rational_type = 5;
+    static constexpr uint16_t undefined_type = 7;
+
+    std::vector<uint8_t> data;
+    size_t next_entry = 0;
+};
+
+// JFIF stores the resolution as a 16-bit value, so TIFF gets the same range.
+void check_dpi(int dpi) {
+    if (dpi < 1 || dpi > 65535) {
+        throw std::invalid_argument("Resolution must be between 1 and 65535 dpi");
+    }
+}
+
+// An IFD entry whose value is given as bytes in the host byte order.
+struct TiffField {
+    uint16_t tag;
+    uint16_t type;
+    uint32_t count;
+    std::vector<uint8_t> value;
+};
+
+TiffField ascii_field(uint16_t tag, const std::string& text) {
+    std::vector<uint8_t> value(text.begin(), text.end());
+    value.push_back(0);
+    return {tag, TiffWriter::ascii_type, static_cast<uint32_t>(value.size()), value};
+}
+
+TiffField short_field(uint16_t tag, uint16_t number) {
+    std::vector<uint8_t> value(sizeof(number));
+    std::memcpy(value.data(), &number, sizeof(number));
+    return {tag, TiffWriter::short_type, 1, value};
+}
+
+TiffField long_field(uint16_t tag, uint32_t number) {
+    std::vector<uint8_t> value(sizeof(number));
+    std::memcpy(value.data(), &number, sizeof(number));
+    return {tag, TiffWriter::long_type, 1, value};
+}
+
+TiffField rational_field(uint16_t tag, uint32_t numerator, uint32_t denominator) {
+    std::vector<uint8_t> value(8);
+    std::memcpy(value.data(), &numerator, 4);
+    std::memcpy(value.data() + 4, &denominator, 4);
+    return {tag, TiffWriter::rational_type, 1, value};
+}
+
+// Writes an IFD at `offset` followed by the values that do not fit into
+// their entries, and returns the offset just past them (word aligned).
+// `fields` must be sorted by tag.
+size_t write_ifd(TiffWriter& tiff, size_t offset, const std::vector<TiffField>& fields) {
+    tiff.put<uint16_t>(offset, static_cast<uint16_t>(fields.size()));
+    size_t entry = offset + 2;
+    size_t extra = entry + fields.size() * 12 + 4;
+    tiff.put<uint32_t>(extra - 4, 0); // No further IFDs.
+    for (const TiffField& field : fields) {
+        tiff.put<uint16_t>(entry, field.tag);
+        tiff.put<uint16_t>(entry + 2, field.type);
+        tiff.put<uint32_t>(entry + 4, field.count);
+        tiff.put<uint32_t>(entry + 8, 0);
+        size_t target = entry + 8;
+        if (field.value.size() > 4) {
+            tiff.put<uint32_t>(entry + 8, static_cast<uint32_t>(extra));
+            target = extra;
+            extra += field.value.size() + field.value.size() % 2;
+            tiff.put<uint8_t>(extra - 1, 0);
+        }
+        std::memcpy(tiff.data.data() + target, field.value.data(), field.value.size());
+        entry += 12;
+    }
+    return extra;
+}
+
+// EXIF "YYYY:MM:DD HH:MM:SS" in local time, the way cameras record it and
+// LibRaw reads it back.
+std::string exif_date(int64_t timestamp) {
+    std::time_t time = static_cast<std::time_t>(timestamp);
+    std::tm local{};
+#ifdef _WIN32
+    localtime_s(&local, &time);
+#else
+    localtime_r(&time, &local);
+#endif
+    char text[20];
+    std::strftime(text, sizeof(text), "%Y:%m:%d %H:%M:%S", &local);
+    return text;
+}
+
+// The payload of an EXIF APP1 segment: the "Exif" identifier and a TIFF
+// structure with IFD0 (make, model, date) pointing to the EXIF sub-IFD
+// (exposure settings).
+std::vector<uint8_t> exif_segment(const Metadata& exif) {
+    std::vector<TiffField> ifd0;
+    std::vector<TiffField> exif_ifd;
+    if (!exif.make.empty()) {
+        ifd0.push_back(ascii_field(0x010F, exif.make)); // Make
+    }
+    if (!exif.model.empty()) {
+        ifd0.push_back(ascii_field(0x0110, exif.model)); // Model
+    }
+    if (exif.timestamp > 0) {
+        ifd0.push_back(ascii_field(0x0132, exif_date(exif.timestamp))); // DateTime
+    }
+    ifd0.push_back(long_field(0x8769, 0)); // ExifIFDPointer, set below.
+
+    if (exif.shutter > 0.0f) {
+        // Short exposures as 1/n like cameras write them, others (0.7 s,
+        // 2 s) in tenths.
+        float reciprocal = 1.0f / exif.shutter;
+        if (exif.shutter < 1.0f && std::abs(reciprocal - std::round(reciprocal)) <= 0.01f * reciprocal) {
+            exif_ifd.push_back(rational_field(0x829A, 1, static_cast<uint32_t>(std::lround(reciprocal))));
+        } else {
+            exif_ifd.push_back(rational_field(0x829A, static_cast<uint32_t>(std::lround(exif.shutter * 10.0f)), 10));
+        }
+    }
+    if (exif.aperture > 0.0f) {
+        exif_ifd.push_back(rational_field(0x829D, static_cast<uint32_t>(std::lround(exif.aperture * 10.0f)), 10)); // FNumber
+    }
+    if (exif.iso_speed > 0.0f) {
+        float iso = std::min(exif.iso_speed, 65535.0f);
+        exif_ifd.push_back(short_field(0x8827, static_cast<uint16_t>(std::lround(iso)))); // ISOSpeedRatings
+    }
+    if (exif.timestamp > 0) {
+        exif_ifd.push_back(ascii_field(0x9003, exif_date(exif.timestamp))); // DateTimeOriginal
+    }
+
+    TiffWriter tiff;
+    tiff.header(8);
+    size_t exif_offset = write_ifd(tiff, 8, ifd0);
+    ifd0.back() = long_field(0x8769, static_cast<uint32_t>(exif_offset));
+    write_ifd(tiff, 8, ifd0);
+    write_ifd(tiff, exif_offset, exif_ifd);
+
+    const char identifier[] = "Exif\0"; // Followed by a second NUL from the literal.
+    std::vector<uint8_t> segment(identifier, identifier + sizeof(identifier));
+    segment.insert(segment.end(), tiff.data.begin(), tiff.data.end());
+    if (segment.size() > 65533) {
+        throw std::invalid_argument("EXIF metadata does not fit into a JPEG segment");
+    }
+    return segment;
+}
+
+// APP2 segments carrying an ICC profile: the "ICC_PROFILE" identifier, the
+// 1-based sequence number and total count of segments, then a piece of the
+// profile. Large profiles are split since a segment holds at most 64 KiB.
+std::vector<std::vector<uint8_t>> icc_segments(const std::vector<uint8_t>& profile) {
+    const char identifier[] = "ICC_PROFILE";
+    constexpr size_t chunk = 65533 - 14; // Segment payload limit minus the prefix.
+    size_t count = (profile.size() + chunk - 1) / chunk;
+    if (count > 255) {
+        throw std::invalid_argument("ICC profile is too large for a JPEG file");
+    }
+    std::vector<std::vector<uint8_t>> segments;
+    for (size_t i = 0; i < count; ++i) {
+        std::vector<uint8_t> segment(identifier, identifier + sizeof(identifier));
+        segment.push_back(static_cast<uint8_t>(i + 1));
+        segment.push_back(static_cast<uint8_t>(count));
+        auto begin = profile.begin() + i * chunk;
+        segment.insert(segment.end(), begin, begin + std::min(chunk, profile.size() - i * chunk));
+        segments.push_back(std::move(segment));
+    }
+    return segments;
+}
+
+} // namespace
+
+// --- JPEG ---
+
+std::vector<uint8_t> encode_jpeg(const std::vector<uint8_t>& image, int width, int height,
+                                 int channels, int quality, ChromaSubsampling subsampling,
+                                 const std::optional<Metadata>& exif, const std::vector<uint8_t>& icc_profile,
+                                 int dpi) {
+    J_COLOR_SPACE input_space = jpeg_input_space(channels);
+    check_buffer(image, width, height, channels);
+    if (quality < 1 || quality > 100) {
+        throw std::invalid_argument("JPEG quality must be between 1 and 100");
+    }
+    check_dpi(dpi);
+    std::vector<uint8_t> exif_data;
+    if (exif) {
+        exif_data = exif_segment(*exif);
+    }
+    std::vector<std::vector<uint8_t>> icc_data = icc_segments(icc_profile);
+
+    // Nothing with a destructor may live between setjmp and the end of
+    // compression, because longjmp would skip it.
+    jpeg_compress_struct cinfo;
+    JpegErrorManager error_manager;
+    unsigned char* output = nullptr;
+    unsigned long output_size = 0;
+
+    cinfo.err = jpeg_std_error(&error_manager.base);
+    error_manager.base.error_exit = jpeg_error_exit;
+    if (setjmp(error_manager.jump)) {
+        jpeg_destroy_compress(&cinfo);
+        std::free(output);
+        throw std::runtime_error(std::string("JPEG encoding failed: ") + error_manager.message);
+    }
+
+    jpeg_create_compress(&cinfo);
+    jpeg_mem_dest(&cinfo, &output, &output_size);
+    cinfo.image_width = static_cast<JDIMENSION>(width);
+    cinfo.image_height = static_cast<JDIMENSION>(height);
+    cinfo.input_components = channels;
+    cinfo.in_color_space = input_space;
+    jpeg_set_defaults(&cinfo);
+    jpeg_set_quality(&cinfo, quality, TRUE);
+    cinfo.density_unit = 1; // Dots per inch, in the JFIF header.
+    cinfo.X_density = static_cast<UINT16>(dpi);
+    cinfo.Y_density = static_cast<UINT16>(dpi);
+    if (cinfo.num_components == 3) {
+        // The chroma components keep a factor of 1, so luma's factors set
+        // how many luma samples share one chroma sample.
+        cinfo.comp_info[0].h_samp_factor = subsampling == ChromaSubsampling::S444 ? 1 : 2;
+        cinfo.comp_info[0].v_samp_factor = subsampling == ChromaSubsampling::S420 ? 2 : 1;
+    }
+
+    jpeg_start_compress(&cinfo, TRUE);
+    if (!exif_data.empty()) {
+        jpeg_write_marker(&cinfo, JPEG_APP0 + 1, exif_data.data(), static_cast<unsigned int>(exif_data.size()));
+    }
+    for (const std::vector<uint8_t>& segment : icc_data) {
+        jpeg_write_marker(&cinfo, JPEG_APP0 + 2, segment.data(), static_cast<unsigned int>(segment.size()));
+    }
+    size_t stride = static_cast<size_t>(width) * channels;
+    while (cinfo.next_scanline < cinfo.image_height) {
+        // libjpeg only reads the rows, the cast just matches its C interface.
+        JSAMPROW row = const_cast<JSAMPROW>(image.data() + cinfo.next_scanline * stride);
+        jpeg_write_scanlines(&cinfo, &row, 1);
+    }
+    jpeg_finish_compress(&cinfo);
+    jpeg_destroy_compress(&cinfo);
+
+    std::vector<uint8_t> result(output, output + output_size);
+    std::free(output);
+    return result;
+}
+
+ImageBuffer decode_jpeg(const std::vector<uint8_t>& data) {
+    if (data.empty()) {
+        throw std::invalid_argument("JPEG data is empty");
+    }
+
+    // Same setjmp restrictions as in encode_jpeg; the result buffer is only
+    // resized once the header has been read, and is not touched by longjmp.
+    jpeg_decompress_struct cinfo;
+    JpegErrorManager error_manager;
+    ImageBuffer result;
+
+    cinfo.err = jpeg_std_error(&error_manager.base);
+    error_manager.base.error_exit = jpeg_error_exit;
+    if (setjmp(error_manager.jump)) {
+        jpeg_destroy_decompress(&cinfo);
+        throw std::runtime_error(std::string("JPEG decoding failed: ") + error_manager.message);
+    }
+
+    jpeg_create_decompress(&cinfo);
+    jpeg_mem_src(&cinfo, data.data(), static_cast<unsigned long>(data.size()));
+    jpeg_read_header(&cinfo, TRUE);
+    cinfo.out_color_space = JCS_RGB;
+    jpeg_start_decompress(&cinfo);
+
+    result.width = static_cast<int>(cinfo.output_width);
+    result.height = static_cast<int>(cinfo.output_height);
+    result.channels = 3;
+    size_t stride = static_cast<size_t>(result.width) * result.channels;
+    result.data.resize(stride * result.height);
+    while (cinfo.output_scanline < cinfo.output_height) {
+        JSAMPROW row = result.data.data() + cinfo.output_scanline * stride;
+        jpeg_read_scanlines(&cinfo, &row, 1);
+    }
+    jpeg_finish_decompress(&cinfo);
+    jpeg_destroy_decompress(&cinfo);
+    return result;
+}
+
+std::string preview_jpeg_base64(const std::vector<uint8_t>& image, int width, int height, int quality) {
+    return base64_encode(encode_jpeg(image, width, height, 3, quality));
+}
+
+// --- TIFF ---
+
+namespace {
+
+// Everything in front of the pixel data of a single-strip baseline TIFF. The
+// pixels follow directly, so the file is this header plus the image bytes.
+std::vector<uint8_t> tiff_header(const std::vector<uint8_t>& image, int width, int height,
+                                 int channels, int bits, const std::vector<uint8_t>& icc_profile, int dpi) {
+    check_dpi(dpi);
+    if (channels != 1 && channels != 3 && channels != 4) {
+        throw std::invalid_argument("TIFF encoding supports 1, 3, or 4 channels");
+    }
+    if (bits != 8 && bits != 16) {
+        throw std::invalid_argument("TIFF encoding supports 8 or 16 bits per sample");
+    }
+    check_buffer(image, width, height, channels * (bits / 8));
+    if (image.size() + icc_profile.size() > 0xFFFFFFFFu - 4096) {
+        throw std::invalid_argument("Image is too large for a baseline TIFF");
+    }
+
+    const char software[] = "MPR Photo Editor";
+    uint16_t entry_count = 14 + (channels == 4) + !icc_profile.empty();
+    size_t ifd = 8;
+    size_t bits_offset = ifd + 2 + entry_count * 12 + 4;
+    size_t resolution_offset = bits_offset + channels * 2;
+    size_t software_offset = resolution_offset + 16;
+    size_t icc_offset = software_offset + sizeof(software);
+    icc_offset += icc_offset % 2;
+    size_t pixel_offset = icc_offset + icc_profile.size();
+    pixel_offset += pixel_offset % 2; // Word alignment for 16-bit readers.
+
+    TiffWriter tiff;
+    tiff.data.reserve(pixel_offset + image.size());
+    tiff.header(static_cast<uint32_t>(ifd));
+
+    tiff.put<uint16_t>(ifd, entry_count);
+    tiff.next_entry = ifd + 2;
+    tiff.entry(256, TiffWriter::long_type, 1, width);  // ImageWidth
+    tiff.entry(257, TiffWriter::long_type, 1, height); // ImageLength
+    if (channels == 1) {
+        tiff.entry(258, TiffWriter::short_type, 1, bits); // BitsPerSample
+    } else {
+        tiff.entry(258, TiffWriter::short_type, channels, static_cast<uint32_t>(bits_offset));
+    }
+    tiff.entry(259, TiffWriter::short_type, 1, 1);                 // Compression: none
+    tiff.entry(262, TiffWriter::short_type, 1, channels == 1 ? 1 : 2); // Photometric: gray or RGB
+    tiff.entry(273, TiffWriter::long_type, 1, static_cast<uint32_t>(pixel_offset)); // StripOffsets
+    tiff.entry(277, TiffWriter::short_type, 1, channels);           // SamplesPerPixel
+    tiff.entry(278, TiffWriter::long_type, 1, height);              // RowsPerStrip
+    tiff.entry(279, TiffWriter::long_type, 1, static_cast<uint32_t>(image.size())); // StripByteCounts
+    tiff.entry(282, TiffWriter::rational_type, 1, static_cast<uint32_t>(resolution_offset)); // XResolution
+    tiff.entry(283, TiffWriter::rational_type, 1, static_cast<uint32_t>(resolution_offset + 8)); // YResolution
+    tiff.entry(284, TiffWriter::short_type, 1, 1);                  // PlanarConfiguration: interleaved
+    tiff.entry(296, TiffWriter::short_type, 1, 2);                  // ResolutionUnit: inch
+    tiff.entry(305, TiffWriter::ascii_type, sizeof(software), static_cast<uint32_t>(software_offset)); // Software
+    if (channels == 4) {
+        tiff.entry(338, TiffWriter::short_type, 1, 2);              // ExtraSamples: unassociated alpha
+    }
+    if (!icc_profile.empty()) {
+        tiff.entry(34675, TiffWriter::undefined_type, static_cast<uint32_t>(icc_profile.size()),
+                   static_cast<uint32_t>(icc_offset)); // ICC Profile
+    }
+    tiff.put<uint32_t>(tiff.next_entry, 0); // No further IFDs.
+
+    for (int c = 0; c < channels; ++c) {
+        tiff.put<uint16_t>(bits_offset + c * 2, static_cast<uint16_t>(bits));
+    }
+    for (size_t r = 0; r < 2; ++r) {
+        tiff.put<uint32_t>(resolution_offset + r * 8, static_cast<uint32_t>(dpi));
+        tiff.put<uint32_t>(resolution_offset + r * 8 + 4, 1);
+    }
+    tiff.data.resize(pixel_offset);
+    std::memcpy(tiff.data.data() + software_offset, software, sizeof(software));
+    std::copy(icc_profile.begin(), icc_profile.end(), tiff.data.begin() + icc_offset);
+    return tiff.data;
+}
+
+} // namespace
+
+std::vector<uint8_t> encode_tiff(const std::vector<uint8_t>& image, int width, int height,
+                                 int channels, int bits, const std::vector<uint8_t>& icc_profile, int dpi) {
+    std::vector<uint8_t> tiff = tiff_header(image, width, height, channels, bits, icc_profile, dpi);
+    tiff.reserve(tiff.size() + image.size());
+    tiff.insert(tiff.end(), image.begin(), image.end());
+    return tiff;
+}
+
+void write_tiff(const std::string& filepath, const std::vector<uint8_t>& image, int width, int height,
+                int channels, int bits, const std::vector<uint8_t>& icc_profile, int dpi) {
+    std::vector<uint8_t> header = tiff_header(image, width, height, channels, bits, icc_profile, dpi);
+    std::ofstream file(filepath, std::ios::binary | std::ios::trunc);
+    if (!file) {
+        throw std::runtime_error("Could not open " + filepath + " for writing");
+    }
+    file.write(reinterpret_cast<const char*>(header.data()), static_cast<std::streamsize>(header.size()));
+
+    // The pixels go out in strips of whole rows, straight from the source
+    // buffer, so no encoded copy of the image is ever built.
+    size_t row_bytes = image.size() / height;
+    size_t rows_per_strip = std::max<size_t>(1, (size_t(4) << 20) / row_bytes);
+    for (size_t row = 0; row < static_cast<size_t>(height) && file; row += rows_per_strip) {
+        size_t rows = std::min(rows_per_strip, static_cast<size_t>(height) - row);
+        file.write(reinterpret_cast<const char*>(image.data() + row * row_bytes),
+                   static_cast<std::streamsize>(rows * row_bytes));
+    }
+    file.close();
+    if (!file) {
+        throw std::runtime_error("Failed to write " + filepath);
+    }
+}
+
+// --- Color Profiles ---
+
+namespace {
+
+// Big-endian writer for ICC profiles, which unlike TIFF have a fixed byte order.
+class IccWriter {
+public:
+    void u16(uint16_t v) {
+        data.push_back(static_cast<uint8_t>(v >> 8));
+        data.push_back(static_cast<uint8_t>(v));
+    }
+    void u32(uint32_t v) {
+        u16(static_cast<uint16_t>(v >> 16));
+        u16(static_cast<uint16_t>(v));
+    }
+    void s15f16(double v) { u32(static_cast<uint32_t>(static_cast<int32_t>(std::lround(v * 65536.0)))); }
+    void text(const char* signature) { data.insert(data.end(), signature, signature + 4); }
+    void align() { data.resize((data.size() + 3) / 4 * 4, 0); }
+
+    std::vector<uint8_t> data;
+};
+
+} // namespace
+
+std::vector<uint8_t> srgb_icc_profile() {
+    static const std::vector<uint8_t> profile = [] {
+        // Tag data first, so the table can point into it; the header and tag
+        // table are put in front at the end.
+        IccWriter tags;
+        struct Tag {
+            const char* signature;
+            size_t offset;
+            size_t size;
+        };
+        std::vector<Tag> table;
+        auto add = [&](std::initializer_list<const char*> signatures, auto write) {
+            size_t offset = tags.data.size();
+            write(tags);
+            for (const char* signature : signatures) {
+                table.push_back({signature, offset, tags.data.size() - offset});
+            }
+            tags.align();
+        };
+
+        add({"desc"}, [](IccWriter& w) {
+            const char description[] = "sRGB";
+            w.text("desc");
+            w.u32(0);
+            w.u32(sizeof(description));
+            w.data.insert(w.data.end(), description, description + sizeof(description));
+            w.data.resize(w.data.size() + 12 + 67, 0); // Empty Unicode and ScriptCode descriptions.
+        });
+        add({"cprt"}, [](IccWriter& w) {
+            const char copyright[] = "No copyright, use freely";
+            w.text("text");
+            w.u32(0);
+            w.data.insert(w.data.end(), copyright, copyright + sizeof(copyright));
+        });
+        auto xyz = [&](const char* signature, double x, double y, double z) {
+            add({signature}, [=](IccWriter& w) {
+                w.text("XYZ ");
+                w.u32(0);
+                w.s15f16(x);
+                w.s15f16(y);
+                w.s15f16(z);
+            });
+        };
+        xyz("wtpt", 0.9642, 1.0, 0.8249);
+        xyz("rXYZ", 0.4361, 0.2225, 0.0139);
+        xyz("gXYZ", 0.3851, 0.7169, 0.0971);
+        xyz("bXYZ", 0.1431, 0.0606, 0.7141);
+        add({"rTRC", "gTRC", "bTRC"}, [](IccWriter& w) {
+            constexpr int entries = 1024;
+            w.text("curv");
+            w.u32(0);
+            w.u32(entries);
+            for (int i = 0; i < entries; ++i) {
+                float linear = srgb_decode(static_cast<float>(i) / (entries - 1));
+                w.u16(static_cast<uint16_t>(std::lround(linear * 65535.0f)));
+            }
+        });
+
+        size_t tags_offset = 128 + 4 + table.size() * 12;
+        IccWriter icc;
+        icc.u32(static_cast<uint32_t>(tags_offset + tags.data.size())); // Profile size
+        icc.u32(0);                       // Preferred CMM
+        icc.u32(0x02100000);              // Version 2.1
+        icc.text("mntr");                 // Display device class
+        icc.text("RGB ");
+        icc.text("XYZ ");                 // Profile connection space
+        for (uint16_t date : {2024, 1, 1, 0, 0, 0}) {
+            icc.u16(date);
+        }
+        icc.text("acsp");
+        icc.data.resize(icc.data.size() + 24, 0); // Platform, flags, device, attributes
+        icc.u32(0);                       // Perceptual rendering intent
+        icc.s15f16(0.9642);               // D50 illuminant
+        icc.s15f16(1.0);
+        icc.s15f16(0.8249);
+        icc.data.resize(128, 0);          // Creator, profile ID, reserved
+        icc.u32(static_cast<uint32_t>(table.size()));
+        for (const Tag& tag : table) {
+            icc.text(tag.signature);
+            icc.u32(static_cast<uint32_t>(tags_offset + tag.offset));
+            icc.u32(static_cast<uint32_t>(tag.size));
+        }
+        icc.data.insert(icc.data.end(), tags.data.begin(), tags.data.end());
+        return icc.data;
+    }();
+    return profile;
+}
+
+// --- Export ---
+
+std::vector<uint8_t> export_region(const std::vector<uint8_t>& image, int width, int height,
+                                   int channels, const CropRect& crop, int out_width, int out_height,
+                                   float sharpen_amount, ExportFormat format, int quality, int dpi) {
+    std::vector<uint8_t> region = crop_image(image, width, height, channels, crop);
+    if (out_width != crop.width || out_height != crop.height) {
+        region = resize_image(region, crop.width, crop.height, channels, out_width, out_height);
+    }
+    if (sharpen_amount != 0.0f) {
+        region = sharpen(region, out_width, out_height, channels, sharpen_amount);
+    }
+
+    switch (format) {
+        case ExportFormat::Jpeg:
+            return encode_jpeg(region, out_width, out_height, channels, quality, ChromaSubsampling::S420,
+                               std::nullopt, {}, dpi);
+        case ExportFormat::Tiff: return encode_tiff(region, out_width, out_height, channels, 8, {}, dpi);
+    }
+    throw std::invalid_argument("Unknown export format");
+}
+
+// --- Base64 ---
+
+std::string base64_encode(const std::vector<uint8_t>& data) {
+    static const char alphabet[] = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    std::string result;
+    result.reserve((data.size() + 2) / 3 * 4);
+    size_t i = 0;
+    for (; i + 2 < data.size(); i += 3) {
+        uint32_t triple = (data[i] << 16) | (data[i + 1] << 8) | data[i + 2];
+        result += alphabet[(triple >> 18) & 0x3F];
+        result += alphabet[(triple >> 12) & 0x3F];
+        result += alphabet[(triple >> 6) & 0x3F];
+        result += alphabet[triple & 0x3F];
+    }
+    if (i < data.size()) {
+        uint32_t triple = data[i] << 16;
+        if (i + 1 < data.size()) {
+            triple |= data[i + 1] << 8;
+        }
+        result += alphabet[(triple >> 18) & 0x3F];
+        result += alphabet[(triple >> 12) & 0x3F];
+        result += i + 1 < data.size() ? alphabet[(triple >> 6) & 0x3F] : '=';
+        result += '=';
+    }
+    return result;
+}
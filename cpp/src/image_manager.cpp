@@ -1,21 +1,859 @@
 #include "mpr_photo_editor/image_manager.h"
+#include "mpr_photo_editor/dng_opcodes.h"
+#include "mpr_photo_editor/image_io.h"
+#include "mpr_photo_editor/image_ops.h"
+#include "pixel_utils.h"
 #include <libraw/libraw.h>
 #include <stdexcept>
+#include <chrono>
 #include <mutex>
 #include <atomic>
+#include <algorithm>
+#include <cmath>
+#include <cstring>
+#include <exception>
+#include <filesystem>
+#include <list>
+#include <optional>
+#include <string_view>
 #include <unordered_map>
 #include <memory>
+#include <vector>
+
+namespace {
+
+// Previews kept by default, in bytes of JPEG data.
+constexpr size_t default_preview_cache_bytes = size_t(256) << 20;
+
+// The previews made from files, least recently used first out once they use
+// more than the capacity, so returning to a recently viewed image does not
+// decode it again. Each entry remembers the modification time of its file; a
+// file that changed since is a miss. The lock is never held while decoding,
+// so two threads missing on the same key both decode it.
+class PreviewCache {
+public:
+    using FileTime = std::filesystem::file_time_type;
+
+    std::optional<BestPreview> find(const std::string& key, FileTime mtime) {
+        std::lock_guard<std::mutex> lock(mutex);
+        auto it = index.find(key);
+        if (it == index.end() || it->second->mtime != mtime) {
+            if (it != index.end()) {
+                remove(it->second);
+            }
+            ++misses;
+            return std::nullopt;
+        }
+        entries.splice(entries.begin(), entries, it->second);
+        ++hits;
+        return entries.front().preview;
+    }
+
+    void insert(const std::string& key, FileTime mtime, const BestPreview& preview) {
+        std::lock_guard<std::mutex> lock(mutex);
+        auto it = index.find(key);
+        if (it != index.end()) {
+            remove(it->second);
+        }
+        entries.push_front(Entry{key, mtime, preview});
+        index[key] = entries.begin();
+        used += preview.jpeg.size();
+        evict();
+    }
+
+    void set_capacity(size_t bytes) {
+        std::lock_guard<std::mutex> lock(mutex);
+        capacity = bytes;
+        evict();
+    }
+
+    void clear() {
+        std::lock_guard<std::mutex> lock(mutex);
+        entries.clear();
+        index.clear();
+        used = 0;
+    }
+
+    PreviewCacheStats stats() {
+        std::lock_guard<std::mutex> lock(mutex);
+        return PreviewCacheStats{hits, misses, entries.size(), used};
+    }
+
+private:
+    struct Entry {
+        std::string key;
+        FileTime mtime;
+        BestPreview preview;
+    };
+
+    void remove(std::list<Entry>::iterator entry) {
+        used -= entry->preview.jpeg.size();
+        index.erase(entry->key);
+        entries.erase(entry);
+    }
+
+    void evict() {
+        while (used > capacity && !entries.empty()) {
+            remove(std::prev(entries.end()));
+        }
+    }
+
+    std::mutex mutex;
+    std::list<Entry> entries; // Most recently used first.
+    std::unordered_map<std::string, std::list<Entry>::iterator> index;
+    size_t capacity = default_preview_cache_bytes;
+    size_t used = 0;
+    size_t hits = 0;
+    size_t misses = 0;
+};
+
+} // namespace
 
 // --- PIMPL (Pointer to Implementation) ---
 // This hides the private members of ImageManager from the public header,
 // reducing compile times and separating interface from implementation.
 class ImageManager::Impl {
 public:
+    // A loaded image and the lock serialising work on it. LibRaw processors
+    // are not thread-safe, but different images can be processed in parallel.
+    struct Entry {
+        std::mutex mutex;
+        // The file contents of images loaded from memory. LibRaw reads from
+        // them for as long as the processor lives, so they are declared first
+        // and destroyed last.
+        std::vector<uint8_t> file_data;
+        std::unique_ptr<LibRaw> processor;
+        // Set after a fatal LibRaw error; the image data is gone then, and the
+        // entry can only be released.
+        bool failed = false;
+        std::string filepath;
+        // Read on first use, so images never decoded with them skip the parse.
+        std::optional<DngOpcodeLists> dng_opcodes;
+        // Set for the sessions of begin_develop.
+        std::optional<DevelopSettings> develop_settings;
+
+        const DngOpcodeLists& opcodes() {
+            if (filepath.empty()) {
+                throw std::runtime_error("DNG opcodes can only be read for images loaded from a file");
+            }
+            if (!dng_opcodes) {
+                dng_opcodes = read_dng_opcodes(filepath);
+            }
+            return *dng_opcodes;
+        }
+    };
+
+    // Guards the map only; it is never held while LibRaw works on an image.
     std::mutex image_manager_mutex;
-    std::unordered_map<uint64_t, std::unique_ptr<LibRaw>> image_manager;
+    std::unordered_map<uint64_t, std::shared_ptr<Entry>> image_manager;
     std::atomic<uint64_t> next_image_id{1};
+
+    // The shared_ptr keeps the entry alive if the handle is released while
+    // another thread is still working on it.
+    std::shared_ptr<Entry> find_entry(uint64_t id) {
+        std::lock_guard<std::mutex> lock(image_manager_mutex);
+        auto it = image_manager.find(id);
+        if (it == image_manager.end()) {
+            throw std::runtime_error("Invalid image ID");
+        }
+        return it->second;
+    }
+
+    uint64_t add_entry(std::shared_ptr<Entry> entry) {
+        uint64_t id = next_image_id.fetch_add(1);
+        std::lock_guard<std::mutex> lock(image_manager_mutex);
+        image_manager[id] = std::move(entry);
+        return id;
+    }
+
+    PreviewCache preview_cache;
+};
+
+namespace {
+
+// JPEG quality used when a thumbnail has to be re-encoded after rotation.
+constexpr int thumbnail_quality = 90;
+
+// The most sensor pixels a file may have to be unpacked; 0 for no limit. It
+// applies to every ImageManager call, so it lives outside the instance.
+std::atomic<uint64_t> max_decode_pixels{0};
+
+// Memory images and thumbnails returned by LibRaw must be freed with
+// libraw_dcraw_clear_mem; owning them this way frees them on every exit path.
+using ProcessedImagePtr = std::unique_ptr<libraw_processed_image_t, decltype(&libraw_dcraw_clear_mem)>;
+
+ProcessedImagePtr own_processed_image(libraw_processed_image_t* image) {
+    return ProcessedImagePtr(image, &libraw_dcraw_clear_mem);
+}
+
+// LibRaw's own messages are terse and do not say when a failure comes from
+// how the installed LibRaw was built, so those cases get an explanation.
+std::string describe_libraw_error(int code) {
+    switch (code) {
+        case LIBRAW_FILE_UNSUPPORTED:
+            return "unsupported file format or camera (a newer LibRaw may support it)";
+        case LIBRAW_NOT_IMPLEMENTED: {
+            std::string message = "not supported by this LibRaw build";
+#if LIBRAW_COMPILE_CHECK_VERSION_NOTLESS(0, 21)
+            // Older LibRaw releases do not report these optional dependencies.
+            unsigned caps = libraw_capabilities();
+            std::string missing;
+            if (!(caps & LIBRAW_CAPS_ZLIB)) missing += ", zlib (deflate compressed DNG)";
+            if (!(caps & LIBRAW_CAPS_JPEG)) missing += ", libjpeg (lossy DNG)";
+            if (!missing.empty()) {
+                message += "; it was built without " + missing.substr(2);
+            }
+#endif
+            return message;
+        }
+        case LIBRAW_NO_THUMBNAIL:
+            return "the file has no embedded thumbnail";
+        case LIBRAW_UNSUPPORTED_THUMBNAIL:
+            return "the embedded thumbnail format is not supported";
+        case LIBRAW_UNSUFFICIENT_MEMORY:
+            return "out of memory";
+        case LIBRAW_IO_ERROR:
+            return "the file does not exist or could not be read";
+        case LIBRAW_DATA_ERROR:
+            return "the file is truncated or corrupt";
+        case LIBRAW_CANCELLED_BY_CALLBACK:
+            return "cancelled";
+        default:
+            return libraw_strerror(code);
+    }
+}
+
+[[noreturn]] void throw_libraw_error(const std::string& what, int code) {
+    throw std::runtime_error(what + " (" + describe_libraw_error(code) + ")");
+}
+
+void check_libraw(int code, const std::string& what) {
+    if (code != LIBRAW_SUCCESS) {
+        throw_libraw_error(what, code);
+    }
+}
+
+// As check_libraw, for a processor that stays registered. After a fatal error
+// (such as running into the end of a truncated file) LibRaw's state is
+// undefined until the processor is recycled, so it is recycled and `failed`
+// is set, which keeps later calls from touching the broken data.
+void check_libraw_keeping(LibRaw* processor, bool& failed, int code, const std::string& what) {
+    if (LIBRAW_FATAL_ERROR(code)) {
+        processor->recycle();
+        failed = true;
+    }
+    check_libraw(code, what);
+}
+
+void check_not_failed(bool failed, uint64_t id) {
+    if (failed) {
+        throw std::runtime_error("Image " + std::to_string(id) +
+                                 " failed with a fatal LibRaw error earlier; release it and load the file again");
+    }
+}
+
+int forward_progress(void* data, enum LibRaw_progress stage, int iteration, int expected) {
+    const auto* callback = static_cast<const ProgressCallback*>(data);
+    float fraction = expected > 0 ? static_cast<float>(iteration) / expected : 0.0f;
+    (*callback)(libraw_strprogress(stage), fraction);
+    return 0; // Non-zero would cancel processing.
+}
+
+struct Deadline {
+    std::chrono::steady_clock::time_point time;
+    bool expired = false;
+};
+
+// Cancels processing once the steady clock passes the Deadline `data` points
+// to. LibRaw reports progress between and within its stages, so this is
+// checked often enough to stop a runaway demosaic.
+int cancel_after_deadline(void* data, enum LibRaw_progress, int, int) {
+    auto* deadline = static_cast<Deadline*>(data);
+    deadline->expired = deadline->expired || std::chrono::steady_clock::now() > deadline->time;
+    return deadline->expired ? 1 : 0;
+}
+
+// Installs a progress handler for the lifetime of this object. LibRaw only
+// keeps a raw pointer to the callback, so it must be removed again before the
+// callback goes out of scope, including when processing throws.
+class ProgressHandlerGuard {
+public:
+    ProgressHandlerGuard(LibRaw* processor, const ProgressCallback& callback) : processor(processor) {
+        if (callback) {
+            processor->set_progress_handler(forward_progress, const_cast<ProgressCallback*>(&callback));
+        }
+    }
+    ProgressHandlerGuard(LibRaw* processor, progress_callback handler, void* data) : processor(processor) {
+        processor->set_progress_handler(handler, data);
+    }
+    ~ProgressHandlerGuard() { processor->set_progress_handler(nullptr, nullptr); }
+
+    ProgressHandlerGuard(const ProgressHandlerGuard&) = delete;
+    ProgressHandlerGuard& operator=(const ProgressHandlerGuard&) = delete;
+
+private:
+    LibRaw* processor;
+};
+
+// A copy of `map` reduced to the gains of one of its planes.
+DngGainMap gain_map_plane(const DngGainMap& map, int map_plane) {
+    DngGainMap result = map;
+    result.map_planes = 1;
+    result.gains.clear();
+    for (size_t i = map_plane; i < map.gains.size(); i += map.map_planes) {
+        result.gains.push_back(map.gains[i]);
+    }
+    return result;
+}
+
+// Applies the corrections of a DNG file at the stages of dcraw_process the
+// DNG specification intends them for: OpcodeList2 on the linear CFA data
+// before white balance, OpcodeList3 on the demosaiced camera colors before the
+// color matrix. imgdata.image is in sensor orientation at both stages;
+// dcraw_make_mem_image rotates it only afterwards. LibRaw passes its step
+// callbacks nothing but the processor, so the opcodes of the decode in
+// progress are found through a thread-local pointer while this object lives.
+// Callbacks must not throw into LibRaw; errors are kept for rethrow().
+class DngOpcodeStages {
+public:
+    // Does nothing for null `opcodes`, so decodes without them need no branch.
+    DngOpcodeStages(LibRaw* processor, const DngOpcodeLists* opcodes) : processor(processor), opcodes(opcodes) {
+        if (!opcodes) {
+            return;
+        }
+#if LIBRAW_COMPILE_CHECK_VERSION_NOTLESS(0, 20)
+        active = this;
+        processor->imgdata.callbacks.pre_scalecolors_cb = apply_list2;
+        processor->imgdata.callbacks.pre_converttorgb_cb = apply_list3;
+#else
+        throw std::runtime_error("Applying DNG opcodes needs LibRaw 0.20 or newer");
+#endif
+    }
+    ~DngOpcodeStages() {
+#if LIBRAW_COMPILE_CHECK_VERSION_NOTLESS(0, 20)
+        if (opcodes) {
+            processor->imgdata.callbacks.pre_scalecolors_cb = nullptr;
+            processor->imgdata.callbacks.pre_converttorgb_cb = nullptr;
+            active = nullptr;
+        }
+#endif
+    }
+
+    DngOpcodeStages(const DngOpcodeStages&) = delete;
+    DngOpcodeStages& operator=(const DngOpcodeStages&) = delete;
+
+    void rethrow() const {
+        if (error) {
+            std::rethrow_exception(error);
+        }
+    }
+
+private:
+    static void apply_list2(void*) {
+        active->run([](DngOpcodeStages& stages) { stages.apply_raw(); });
+    }
+    static void apply_list3(void*) {
+        active->run([](DngOpcodeStages& stages) { stages.apply_processed(); });
+    }
+
+    template <typename Step>
+    void run(Step step) {
+        try {
+            step(*this);
+        } catch (...) {
+            error = std::current_exception();
+        }
+    }
+
+    void apply_raw() {
+        const libraw_image_sizes_t& sizes = processor->imgdata.sizes;
+        uint16_t* image = &processor->imgdata.image[0][0];
+        if (!processor->imgdata.idata.filters) {
+            // Linear DNGs have no CFA; their planes are the channels.
+            for (const DngGainMap& map : opcodes->list2.gain_maps) {
+                apply_gain_map(map, -1, image, sizes.iwidth, sizes.iheight, 4, sizes.width, sizes.height);
+            }
+            for (const DngWarpRectilinear& warp : opcodes->list2.warps) {
+                apply_warp_rectilinear(warp, image, sizes.iwidth, sizes.iheight, 4);
+            }
+            return;
+        }
+        // Each CFA sample sits in the channel of its color, with the second
+        // green of a 3-color camera in channel 3, and the others are 0 (or,
+        // in half-size decodes, hold the other samples of a 2x2 block). A
+        // map with a pitch covers the color at its top-left; one without
+        // covers every sample. Warps cannot resample CFA data and are left
+        // out.
+        for (const DngGainMap& map : opcodes->list2.gain_maps) {
+            if (map.row_pitch > 1 || map.col_pitch > 1) {
+                apply_gain_map(map, processor->COLOR(map.top, map.left), image, sizes.iwidth, sizes.iheight,
+                               4, sizes.width, sizes.height);
+            } else {
+                for (int c = 0; c < 4; ++c) {
+                    apply_gain_map(map, c, image, sizes.iwidth, sizes.iheight, 4, sizes.width, sizes.height);
+                }
+            }
+        }
+    }
+
+    void apply_processed() {
+        const libraw_image_sizes_t& sizes = processor->imgdata.sizes;
+        const libraw_iparams_t& idata = processor->imgdata.idata;
+        uint16_t* image = &processor->imgdata.image[0][0];
+        // Half-size decodes of 3-color cameras keep the second green in
+        // channel 3 up to the color matrix; it takes the corrections of the
+        // green plane.
+        bool second_green = idata.colors == 4 && idata.cdesc[1] == idata.cdesc[3];
+        for (const DngGainMap& map : opcodes->list3.gain_maps) {
+            apply_gain_map(map, -1, image, sizes.iwidth, sizes.iheight, 4, sizes.width, sizes.height);
+            if (second_green && map.plane <= 1 && map.plane + map.planes > 1) {
+                DngGainMap green = gain_map_plane(map, std::min(1 - map.plane, map.map_planes - 1));
+                apply_gain_map(green, 3, image, sizes.iwidth, sizes.iheight, 4, sizes.width, sizes.height);
+            }
+        }
+        for (DngWarpRectilinear warp : opcodes->list3.warps) {
+            if (second_green && warp.coefficients.size() == 3) {
+                warp.coefficients.push_back(warp.coefficients[1]);
+            }
+            apply_warp_rectilinear(warp, image, sizes.iwidth, sizes.iheight, 4);
+        }
+    }
+
+    static thread_local DngOpcodeStages* active;
+
+    LibRaw* processor;
+    const DngOpcodeLists* opcodes;
+    std::exception_ptr error;
 };
 
+thread_local DngOpcodeStages* DngOpcodeStages::active = nullptr;
+
+// Every option is written on every decode, so settings from an earlier decode
+// of the same handle never leak into the next one.
+void apply_decode_options(LibRaw* processor, const DecodeOptions& options) {
+    if (options.bright <= 0.0f) {
+        throw std::invalid_argument("Decode brightness must be positive");
+    }
+    if (options.gamma[0] <= 0.0 || options.gamma[1] < 0.0) {
+        throw std::invalid_argument("Gamma power must be positive and the toe slope non-negative");
+    }
+    if (options.bits != 8 && options.bits != 16) {
+        throw std::invalid_argument("Decode bit depth must be 8 or 16");
+    }
+    if (options.quality >= 5 && options.quality <= 10) {
+        throw std::invalid_argument("Demosaic quality " + std::to_string(options.quality) +
+                                    " needs the GPL demosaic packs, which LibRaw no longer includes");
+    }
+    if (options.quality < -1 || (options.quality > 4 && options.quality != 11 && options.quality != 12)) {
+        throw std::invalid_argument("Demosaic quality must be -1, 0-4, 11, or 12");
+    }
+    if (options.dcb_iterations < -1) {
+        throw std::invalid_argument("DCB iterations must be -1 (default) or more");
+    }
+    if (options.med_passes < 0) {
+        throw std::invalid_argument("Median filter passes must not be negative");
+    }
+    if (!(options.auto_bright_thr > 0.0f && options.auto_bright_thr <= 0.5f)) {
+        throw std::invalid_argument("Auto-brightness threshold must be in (0, 0.5]");
+    }
+    if (options.user_flip < -1 || options.user_flip > 7) {
+        throw std::invalid_argument("User flip must be -1 (camera orientation) or a flip code from 0 to 7");
+    }
+    libraw_output_params_t& params = processor->imgdata.params;
+    params.bright = options.bright;
+    params.gamm[0] = options.gamma[0];
+    params.gamm[1] = options.gamma[1];
+    params.output_bps = options.bits;
+    params.half_size = options.half_size ? 1 : 0;
+    params.user_qual = options.quality;
+    params.four_color_rgb = options.four_color_rgb ? 1 : 0;
+    params.dcb_iterations = options.dcb_iterations;
+    params.dcb_enhance_fl = options.dcb_enhance ? 1 : 0;
+    params.med_passes = options.med_passes;
+    params.auto_bright_thr = options.auto_bright_thr;
+    params.use_camera_wb = options.use_camera_wb ? 1 : 0;
+    params.use_camera_matrix = options.use_camera_matrix ? 1 : 0;
+    params.user_flip = options.user_flip;
+
+    // Processors are reused across decodes, so the linear settings are reset
+    // to LibRaw's defaults when they are not wanted.
+    params.no_auto_bright = options.linear ? 1 : 0;
+    params.output_color = options.linear ? 0 : 1;
+    std::fill(std::begin(params.user_mul), std::end(params.user_mul), options.linear ? 1.0f : 0.0f);
+    if (options.linear) {
+        params.gamm[0] = 1.0;
+        params.gamm[1] = 1.0;
+    }
+}
+
+// Full-resolution decodes produce buffers of a hundred megabytes and more.
+// Copying those in a few parallel chunks uses more of the memory bandwidth
+// than a single memcpy; smaller buffers are copied in one go.
+std::vector<uint8_t> copy_buffer(const uint8_t* data, size_t size) {
+    constexpr size_t parallel_threshold = size_t(64) << 20;
+    if (size < parallel_threshold) {
+        return std::vector<uint8_t>(data, data + size);
+    }
+
+    std::vector<uint8_t> result(size);
+    parallel_chunks(size, parallel_threshold, [&result, data](size_t begin, size_t end) {
+        std::memcpy(result.data() + begin, data + begin, end - begin);
+    });
+    return result;
+}
+
+// Copies interleaved pixels into one plane per channel. Samples are moved as
+// whole units of `sample_size` bytes, so 16-bit data keeps its byte order.
+std::vector<uint8_t> copy_planar(const uint8_t* data, size_t pixels, int channels, size_t sample_size) {
+    std::vector<uint8_t> result(pixels * channels * sample_size);
+    size_t pixel_size = channels * sample_size;
+    size_t plane_size = pixels * sample_size;
+    parallel_chunks(pixels, size_t(1) << 20, [&](size_t begin, size_t end) {
+        for (size_t p = begin; p < end; ++p) {
+            for (int c = 0; c < channels; ++c) {
+                std::memcpy(result.data() + c * plane_size + p * sample_size,
+                            data + p * pixel_size + c * sample_size, sample_size);
+            }
+        }
+    });
+    return result;
+}
+
+// Checks that a processed image is a bitmap whose header matches its buffer.
+// The channel count is taken from the image, not assumed: depending on the
+// parameters and the sensor, LibRaw can return four channels (RGBG or CMYG)
+// instead of three.
+void check_processed_image(const libraw_processed_image_t* image) {
+    if (image->type != LIBRAW_IMAGE_BITMAP) {
+        throw std::runtime_error("LibRaw returned an encoded image where a bitmap was expected");
+    }
+    if (image->colors < 1 || image->colors > 4 || (image->bits != 8 && image->bits != 16)) {
+        throw std::runtime_error("LibRaw returned an unsupported image format: " +
+                                 std::to_string(image->colors) + " channels, " +
+                                 std::to_string(image->bits) + " bits per sample");
+    }
+    size_t expected = static_cast<size_t>(image->width) * image->height * image->colors * (image->bits / 8);
+    if (image->data_size != expected) {
+        throw std::runtime_error("LibRaw returned " + std::to_string(image->data_size) + " bytes for a " +
+                                 std::to_string(image->width) + "x" + std::to_string(image->height) + ", " +
+                                 std::to_string(image->colors) + "-channel, " +
+                                 std::to_string(image->bits) + "-bit image; expected " +
+                                 std::to_string(expected));
+    }
+}
+
+// The tone curve dcraw_make_mem_image applies to the processed image: LibRaw's
+// gamma curve for the decode's gamma settings, scaled to the white point that
+// auto-brightness picks from the histogram. LibRaw keeps its histogram to
+// itself, so it is counted again here from the processed image, which it
+// matches unless the image was rotated or stretched for a Fuji SuperCCD or
+// non-square-pixel sensor afterwards. apply_decode_options ensures a
+// positive gamma power.
+std::vector<uint16_t> output_curve(const LibRaw* processor) {
+    constexpr int histogram_size = 0x2000;
+    const libraw_data_t& data = processor->imgdata;
+    const libraw_output_params_t& params = data.params;
+    const libraw_image_sizes_t& sizes = data.sizes;
+    int white = histogram_size;
+    if (!((params.highlight & ~2) || params.no_auto_bright)) {
+        int colors = data.idata.colors;
+        size_t pixels = static_cast<size_t>(sizes.width) * sizes.height;
+        std::vector<int> histogram(static_cast<size_t>(colors) * histogram_size, 0);
+        for (size_t p = 0; p < pixels; ++p) {
+            for (int c = 0; c < colors; ++c) {
+                ++histogram[static_cast<size_t>(c) * histogram_size + (data.image[p][c] >> 3)];
+            }
+        }
+        // The white point leaves the brightest auto_bright_thr of the pixels
+        // clipped in every channel.
+        int clipped = static_cast<int>(sizes.width * sizes.height * params.auto_bright_thr);
+        white = 0;
+        for (int c = 0; c < colors; ++c) {
+            int value = histogram_size;
+            int total = 0;
+            while (--value > 32) {
+                total += histogram[static_cast<size_t>(c) * histogram_size + value];
+                if (total > clipped) {
+                    break;
+                }
+            }
+            white = std::max(white, value);
+        }
+    }
+    int imax = static_cast<int>((white << 3) / params.bright);
+
+    // A power curve with a linear toe of slope gamm[1], joined where both
+    // have the same value and slope; the join is found by bisection.
+    double power = params.gamm[0];
+    double slope = params.gamm[1];
+    double join = 0.0, toe_end = 0.0, offset = 0.0;
+    double bounds[2] = {0.0, 0.0};
+    bounds[slope >= 1] = 1.0;
+    if (slope != 0.0 && (slope - 1) * (power - 1) <= 0) {
+        for (int i = 0; i < 48; ++i) {
+            join = (bounds[0] + bounds[1]) / 2;
+            bounds[(std::pow(join / slope, -power) - 1) / power - 1 / join > -1] = join;
+        }
+        toe_end = join / slope;
+        offset = join * (1 / power - 1);
+    }
+
+    std::vector<uint16_t> curve(0x10000, 0xffff);
+    for (int i = 0; i < 0x10000; ++i) {
+        double r = static_cast<double>(i) / imax;
+        if (r < 1) {
+            double value = r < toe_end ? r * slope : std::pow(r, power) * (1 + offset) - offset;
+            curve[i] = static_cast<uint16_t>(std::min(0x10000 * value, 65535.0));
+        }
+    }
+    return curve;
+}
+
+// Encoders treat four channels as RGBA, which would silently misread a
+// four-color decode, so callers that encode require grayscale or RGB.
+void require_gray_or_rgb(const ImageBuffer& image, const std::string& what) {
+    if (image.channels != 1 && image.channels != 3) {
+        throw std::runtime_error(what + " needs a 1- or 3-channel decode, but LibRaw returned " +
+                                 std::to_string(image.channels) + " channels");
+    }
+}
+
+// Held around every LibRaw call that decodes or parses. The reentrant library
+// keeps all state in the processor, so the lock is empty. The plain library is
+// not thread-safe, so with it there is one process-wide lock and decodes run
+// one at a time. LibRaw itself needs no global initialisation.
+class LibRawLock {
+public:
+#ifndef MPO_LIBRAW_NOT_REENTRANT
+    // User-provided, so that unused-variable warnings do not fire.
+    LibRawLock() {}
+#else
+    LibRawLock() : lock(mutex()) {}
+
+private:
+    static std::mutex& mutex() {
+        static std::mutex instance;
+        return instance;
+    }
+    std::lock_guard<std::mutex> lock;
+#endif
+};
+
+// Unpacks an opened processor; `source` names the file in error messages.
+// The sensor size is known from the header, so files over the pixel limit are
+// rejected before their data is allocated.
+void unpack_opened(LibRaw* processor, const std::string& source) {
+    const libraw_image_sizes_t& sizes = processor->imgdata.sizes;
+    uint64_t pixels = static_cast<uint64_t>(sizes.raw_width) * sizes.raw_height;
+    uint64_t limit = max_decode_pixels.load();
+    if (limit > 0 && pixels > limit) {
+        throw std::runtime_error("Refusing to unpack " + source + ": its " + std::to_string(sizes.raw_width) +
+                                 "x" + std::to_string(sizes.raw_height) + " sensor exceeds the limit of " +
+                                 std::to_string(limit) + " pixels");
+    }
+    int code = processor->unpack();
+    if (code == LIBRAW_IO_ERROR) {
+        // The header was read, so a read error now means the data ends early.
+        throw std::runtime_error("Failed to unpack " + source +
+                                 " (the image data ends early; the file is truncated, e.g. not fully downloaded)");
+    }
+    check_libraw(code, "Failed to unpack " + source);
+}
+
+// A private processor for one-off work on a file: nothing is registered, and
+// the image manager lock is never held while it decodes.
+std::unique_ptr<LibRaw> open_and_unpack(const std::string& filepath) {
+    LibRawLock libraw_lock;
+    auto processor = std::make_unique<LibRaw>();
+    check_libraw(processor->open_file(filepath.c_str()), "Failed to open file: " + filepath);
+    unpack_opened(processor.get(), "file: " + filepath);
+    return processor;
+}
+
+// Runs dcraw_process with the current parameters; it can be repeated on the
+// same unpacked data with different options. The bit depth and channel count
+// are those of the image LibRaw actually produced.
+DecodedImage process_image(LibRaw* processor) {
+    LibRawLock libraw_lock;
+    check_libraw(processor->dcraw_process(), "Failed to process raw image");
+    int error = LIBRAW_SUCCESS;
+    libraw_processed_image_t* image = processor->dcraw_make_mem_image(&error);
+    if (!image) {
+        throw_libraw_error("Failed to create memory image", error);
+    }
+    ProcessedImagePtr image_ptr = own_processed_image(image);
+    check_processed_image(image);
+    DecodedImage result;
+    result.image = ImageBuffer{copy_buffer(image->data, image->data_size), image->width, image->height, image->colors};
+    result.bits = image->bits;
+    result.maximum = processor->imgdata.color.maximum;
+    result.data_maximum = processor->imgdata.color.data_maximum;
+    return result;
+}
+
+// Returns the first of embedded preview, half-size decode, and (if
+// `allow_full`) full decode whose longer side reaches `min_dim`, as an upright
+// JPEG. Decoding is orders of magnitude slower than reading the preview, and a
+// half-size decode about four times faster than a full one. Without
+// `allow_full` the half-size decode is returned even when it is too small.
+BestPreview fastest_preview(const std::string& filepath, int min_dim, int quality, bool allow_full) {
+    if (min_dim <= 0) {
+        throw std::invalid_argument("Minimum preview size must be positive");
+    }
+
+    // Only the metadata and the preview are read at first; the sensor data is
+    // unpacked only if the preview turns out to be unusable.
+    auto processor = std::make_unique<LibRaw>();
+    ProcessedImagePtr thumb = own_processed_image(nullptr);
+    {
+        LibRawLock libraw_lock;
+        check_libraw(processor->open_file(filepath.c_str()), "Failed to open file: " + filepath);
+        // A missing or unsupported preview is not an error: the decode stands in.
+        if (processor->unpack_thumb() == LIBRAW_SUCCESS) {
+            thumb = own_processed_image(processor->dcraw_make_mem_thumb());
+        }
+    }
+
+    ImageBuffer preview;
+    try {
+        if (thumb && thumb->type == LIBRAW_IMAGE_JPEG) {
+            preview = decode_jpeg(std::vector<uint8_t>(thumb->data, thumb->data + thumb->data_size));
+        } else if (thumb && thumb->type == LIBRAW_IMAGE_BITMAP && thumb->bits == 8) {
+            preview = ImageBuffer{std::vector<uint8_t>(thumb->data, thumb->data + thumb->data_size),
+                                  thumb->width, thumb->height, thumb->colors};
+        }
+    } catch (const std::runtime_error&) {
+        preview = ImageBuffer(); // A corrupt preview falls back to the decode as well.
+    }
+
+    int flip = processor->imgdata.sizes.flip & 7;
+    if (!preview.data.empty() && std::max(preview.width, preview.height) >= min_dim) {
+        BestPreview result;
+        result.source = PreviewSource::Thumbnail;
+        // An upright JPEG is returned as is rather than losing quality to a re-encode.
+        if (flip == 0 && thumb->type == LIBRAW_IMAGE_JPEG) {
+            result.jpeg.assign(thumb->data, thumb->data + thumb->data_size);
+        } else {
+            if (flip != 0) {
+                preview = orient_image(preview.data, preview.width, preview.height, preview.channels, flip);
+            }
+            result.jpeg = encode_jpeg(preview.data, preview.width, preview.height, preview.channels, quality);
+        }
+        result.width = preview.width;
+        result.height = preview.height;
+        return result;
+    }
+
+    // Half-size decodes shrink Bayer data only; other sensors come out at
+    // full size either way.
+    const libraw_image_sizes_t& sizes = processor->imgdata.sizes;
+    int full_dim = std::max(sizes.width, sizes.height);
+    bool shrinks = processor->imgdata.idata.filters != 0;
+    bool half_size = !allow_full || !shrinks || (full_dim + 1) / 2 >= min_dim;
+
+    {
+        LibRawLock libraw_lock;
+        unpack_opened(processor.get(), "file: " + filepath);
+    }
+    DecodeOptions options;
+    options.half_size = half_size;
+    apply_decode_options(processor.get(), options);
+    ImageBuffer image = process_image(processor.get()).image;
+    require_gray_or_rgb(image, "A preview");
+
+    BestPreview result;
+    result.source = half_size && shrinks ? PreviewSource::HalfSize : PreviewSource::Full;
+    result.jpeg = encode_jpeg(image.data, image.width, image.height, image.channels, quality);
+    result.width = image.width;
+    result.height = image.height;
+    return result;
+}
+
+// LibRaw leaves both values at 0 when the file does not record them.
+FocalLength focal_length_of(const LibRaw* processor) {
+    FocalLength focal_length;
+    focal_length.actual = std::max(processor->imgdata.other.focal_len, 0.0f);
+    focal_length.equivalent_35mm = processor->imgdata.lens.FocalLengthIn35mmFormat;
+    return focal_length;
+}
+
+// color.flash_used holds the EXIF Flash tag, whose lowest bit says whether
+// the flash fired.
+CaptureFlags capture_flags_of(const LibRaw* processor) {
+    CaptureFlags flags;
+    flags.flash_fired = (static_cast<int>(processor->imgdata.color.flash_used) & 1) != 0;
+    flags.metering_mode = std::max<int>(processor->imgdata.shootinginfo.MeteringMode, 0);
+    return flags;
+}
+
+// LibRaw does not say whether the raw data was compressed lossily, so this is
+// judged from the decoder it picked (lossy DNG, and Sony's compressed ARW,
+// which stores 11-bit data as 8-bit deltas) and from Nikon's maker notes,
+// whose NEF compression codes 1 and 4 are lossy and 13 and 14 the lossy
+// high-efficiency formats.
+bool lossy_compression_of(LibRaw* processor) {
+    libraw_decoder_info_t info{};
+    if (processor->get_decoder_info(&info) == LIBRAW_SUCCESS && info.decoder_name) {
+        std::string_view decoder = info.decoder_name;
+        for (std::string_view lossy : {"lossy_dng_load_raw", "sony_arw_load_raw", "sony_arw2_load_raw"}) {
+            if (decoder.substr(0, lossy.size()) == lossy) {
+                return true;
+            }
+        }
+    }
+#if LIBRAW_COMPILE_CHECK_VERSION_NOTLESS(0, 20)
+    switch (processor->imgdata.makernotes.nikon.NEFCompression) {
+        case 1:
+        case 4:
+        case 13:
+        case 14:
+            return true;
+        default:
+            break;
+    }
+#endif
+    return false;
+}
+
+// Copies the unpacked thumbnail out of LibRaw. Embedded previews are stored
+// in sensor orientation, so portrait shots come out sideways unless the
+// camera's orientation is applied; JPEGs are re-encoded for that.
+ThumbnailData make_thumbnail(LibRaw* processor, bool apply_orientation) {
+    int error = LIBRAW_SUCCESS;
+    libraw_processed_image_t* thumb = processor->dcraw_make_mem_thumb(&error);
+    if (!thumb) {
+        throw_libraw_error("Failed to create memory thumbnail", error);
+    }
+
+    ProcessedImagePtr thumb_ptr = own_processed_image(thumb);
+
+    ThumbnailData result;
+    result.data.assign(reinterpret_cast<char*>(thumb->data), reinterpret_cast<char*>(thumb->data) + thumb->data_size);
+
+    int flip = processor->imgdata.sizes.flip;
+    if (!apply_orientation || (flip & 7) == 0) {
+        return result;
+    }
+    if (thumb->type == LIBRAW_IMAGE_JPEG) {
+        ImageBuffer decoded = decode_jpeg(std::vector<uint8_t>(thumb->data, thumb->data + thumb->data_size));
+        ImageBuffer oriented = orient_image(decoded.data, decoded.width, decoded.height, decoded.channels, flip & 7);
+        std::vector<uint8_t> encoded = encode_jpeg(oriented.data, oriented.width, oriented.height,
+                                                   oriented.channels, thumbnail_quality);
+        result.data.assign(encoded.begin(), encoded.end());
+    } else if (thumb->type == LIBRAW_IMAGE_BITMAP && thumb->bits == 8) {
+        std::vector<uint8_t> pixels(thumb->data, thumb->data + thumb->data_size);
+        ImageBuffer oriented = orient_image(pixels, thumb->width, thumb->height, thumb->colors, flip & 7);
+        result.data.assign(oriented.data.begin(), oriented.data.end());
+    }
+    return result;
+}
+
+double elapsed_ms(std::chrono::steady_clock::time_point start) {
+    return std::chrono::duration<double, std::milli>(std::chrono::steady_clock::now() - start).count();
+}
+
+} // namespace
+
 // --- ImageManager Methods ---
 
 ImageManager::ImageManager() : pimpl(std::make_unique<Impl>()) {}
@@ -30,22 +868,66 @@ std::string ImageManager::get_libraw_version() {
     return libraw_version();
 }
 
-uint64_t ImageManager::load_raw_image(const std::string& filepath) {
-    auto processor = std::make_unique<LibRaw>();
+std::vector<std::string> ImageManager::get_supported_extensions() {
+    // LibRaw identifies files by their content, not their name, and has no
+    // list of extensions; these are the common RAW formats it can open.
+    return {
+        "3fr", "arw", "cr2", "cr3", "crw", "dcr", "dng", "erf", "iiq", "k25",
+        "kdc", "mef", "mos", "mrw", "nef", "nrw", "orf", "pef", "raf", "raw",
+        "rw2", "rwl", "sr2", "srf", "srw", "x3f",
+    };
+}
 
-    if (processor->open_file(filepath.c_str()) != LIBRAW_SUCCESS) {
-        throw std::runtime_error("Failed to open file: " + filepath);
+int ImageManager::get_supported_camera_count() {
+    return libraw_cameraCount();
+}
+
+std::vector<std::string> ImageManager::get_supported_cameras() {
+    // LibRaw's list is static and terminated by a null pointer.
+    std::vector<std::string> cameras;
+    for (const char** camera = LibRaw::cameraList(); camera && *camera; ++camera) {
+        cameras.emplace_back(*camera);
     }
-    if (processor->unpack() != LIBRAW_SUCCESS) {
-        throw std::runtime_error("Failed to unpack file: " + filepath);
+    return cameras;
+}
+
+uint64_t ImageManager::load_raw_image(const std::string& filepath) {
+    // The processor only enters the map once it is fully loaded; until then the
+    // unique_ptr releases it (and LibRaw closes the file) on any failure.
+    std::unique_ptr<LibRaw> processor = open_and_unpack(filepath);
+
+    auto entry = std::make_shared<Impl::Entry>();
+    entry->processor = std::move(processor);
+    entry->filepath = filepath;
+    return pimpl->add_entry(std::move(entry));
+}
+
+uint64_t ImageManager::load_raw_image_from_buffer(std::vector<uint8_t> data) {
+    if (data.empty()) {
+        throw std::invalid_argument("Raw file data is empty");
     }
 
-    uint64_t id = pimpl->next_image_id.fetch_add(1);
+    // The entry owns the data before LibRaw sees it, so it outlives the
+    // processor on every path.
+    auto entry = std::make_shared<Impl::Entry>();
+    entry->file_data = std::move(data);
+    {
+        LibRawLock libraw_lock;
+        auto processor = std::make_unique<LibRaw>();
+        check_libraw(processor->open_buffer(entry->file_data.data(), entry->file_data.size()),
+                     "Failed to open raw file data in memory");
+        unpack_opened(processor.get(), "raw file data in memory");
+        entry->processor = std::move(processor);
+    }
+    return pimpl->add_entry(std::move(entry));
+}
 
-    std::lock_guard<std::mutex> lock(pimpl->image_manager_mutex);
-    pimpl->image_manager[id] = std::move(processor);
+void ImageManager::set_max_decode_pixels(uint64_t pixels) {
+    max_decode_pixels.store(pixels);
+}
 
-    return id;
+uint64_t ImageManager::get_max_decode_pixels() {
+    return max_decode_pixels.load();
 }
 
 void ImageManager::release_raw_image(uint64_t id) {
@@ -53,37 +935,75 @@ void ImageManager::release_raw_image(uint64_t id) {
     pimpl->image_manager.erase(id);
 }
 
-ThumbnailData ImageManager::get_thumbnail(uint64_t id) {
+size_t ImageManager::get_open_image_count() {
     std::lock_guard<std::mutex> lock(pimpl->image_manager_mutex);
-    auto it = pimpl->image_manager.find(id);
-    if (it == pimpl->image_manager.end()) {
-        throw std::runtime_error("Invalid image ID");
-    }
-    LibRaw* processor = it->second.get();
+    return pimpl->image_manager.size();
+}
 
-    if (processor->unpack_thumb() != LIBRAW_SUCCESS) {
-        throw std::runtime_error("Failed to unpack thumbnail");
-    }
+ThumbnailData ImageManager::get_thumbnail(uint64_t id, bool apply_orientation) {
+    std::shared_ptr<Impl::Entry> entry = pimpl->find_entry(id);
+    std::lock_guard<std::mutex> lock(entry->mutex);
+    check_not_failed(entry->failed, id);
+    LibRaw* processor = entry->processor.get();
+    LibRawLock libraw_lock;
 
-    libraw_processed_image_t* thumb = processor->dcraw_make_mem_thumb();
-    if (!thumb) {
-        throw std::runtime_error("Failed to create memory thumbnail");
-    }
+    check_libraw_keeping(processor, entry->failed, processor->unpack_thumb(), "Failed to unpack thumbnail");
 
-    std::unique_ptr<libraw_processed_image_t, decltype(&libraw_dcraw_clear_mem)> thumb_ptr(thumb, &libraw_dcraw_clear_mem);
+    return make_thumbnail(processor, apply_orientation);
+}
 
-    ThumbnailData result;
-    result.data.assign(reinterpret_cast<char*>(thumb->data), reinterpret_cast<char*>(thumb->data) + thumb->data_size);
+std::vector<ThumbnailInfo> ImageManager::list_thumbnails(const std::string& filepath) {
+    // The list comes from the headers; no preview is unpacked.
+    LibRawLock libraw_lock;
+    auto processor = std::make_unique<LibRaw>();
+    check_libraw(processor->open_file(filepath.c_str()), "Failed to open file: " + filepath);
+
+    std::vector<ThumbnailInfo> result;
+#if LIBRAW_COMPILE_CHECK_VERSION_NOTLESS(0, 21)
+    const libraw_thumbnail_list_t& list = processor->imgdata.thumbs_list;
+    for (int i = 0; i < list.thumbcount; ++i) {
+        const libraw_thumbnail_item_t& item = list.thumblist[i];
+        result.push_back({item.twidth, item.theight, item.tformat == LIBRAW_INTERNAL_THUMBNAIL_JPEG, item.tlength});
+    }
+#else
+    // Older LibRaw releases only know the preview they would unpack.
+    const libraw_thumbnail_t& thumbnail = processor->imgdata.thumbnail;
+    if (thumbnail.tlength > 0) {
+        result.push_back({thumbnail.twidth, thumbnail.theight, thumbnail.tformat == LIBRAW_THUMBNAIL_JPEG,
+                          thumbnail.tlength});
+    }
+#endif
     return result;
 }
 
-Metadata ImageManager::get_metadata(uint64_t id) {
-    std::lock_guard<std::mutex> lock(pimpl->image_manager_mutex);
-    auto it = pimpl->image_manager.find(id);
-    if (it == pimpl->image_manager.end()) {
-        throw std::runtime_error("Invalid image ID");
+ThumbnailData ImageManager::extract_thumbnail_index(const std::string& filepath, int index, bool apply_orientation) {
+    LibRawLock libraw_lock;
+    auto processor = std::make_unique<LibRaw>();
+    check_libraw(processor->open_file(filepath.c_str()), "Failed to open file: " + filepath);
+
+#if LIBRAW_COMPILE_CHECK_VERSION_NOTLESS(0, 21)
+    int count = processor->imgdata.thumbs_list.thumbcount;
+#else
+    int count = processor->imgdata.thumbnail.tlength > 0 ? 1 : 0;
+#endif
+    if (index < 0 || index >= count) {
+        throw std::out_of_range("Thumbnail index " + std::to_string(index) + " is out of range; " + filepath +
+                                " has " + std::to_string(count) + " embedded preview(s)");
     }
-    LibRaw* processor = it->second.get();
+#if LIBRAW_COMPILE_CHECK_VERSION_NOTLESS(0, 21)
+    check_libraw(processor->unpack_thumb_ex(index), "Failed to unpack thumbnail");
+#else
+    check_libraw(processor->unpack_thumb(), "Failed to unpack thumbnail");
+#endif
+    return make_thumbnail(processor.get(), apply_orientation);
+}
+
+Metadata ImageManager::get_metadata(uint64_t id) {
+    std::shared_ptr<Impl::Entry> entry = pimpl->find_entry(id);
+    std::lock_guard<std::mutex> lock(entry->mutex);
+    // A fatal error recycled the processor, which zeroed its metadata.
+    check_not_failed(entry->failed, id);
+    LibRaw* processor = entry->processor.get();
 
     Metadata meta;
     meta.make = std::string(processor->imgdata.idata.make);
@@ -91,5 +1011,456 @@ Metadata ImageManager::get_metadata(uint64_t id) {
     meta.iso_speed = processor->imgdata.other.iso_speed;
     meta.shutter = processor->imgdata.other.shutter;
     meta.aperture = processor->imgdata.other.aperture;
+    meta.timestamp = static_cast<int64_t>(processor->imgdata.other.timestamp);
+    meta.focal_length = focal_length_of(processor);
+    meta.capture_flags = capture_flags_of(processor);
+    meta.lossy_compression = lossy_compression_of(processor);
+    const libraw_image_sizes_t& sizes = processor->imgdata.sizes;
+    bool transposed = (sizes.flip & 4) != 0;
+    meta.width = transposed ? sizes.height : sizes.width;
+    meta.height = transposed ? sizes.width : sizes.height;
     return meta;
-}
\ No newline at end of file
+}
+
+float ImageManager::read_iso(const std::string& filepath) {
+    // Opening reads all metadata; unpacking the sensor data is not needed.
+    LibRawLock libraw_lock;
+    auto processor = std::make_unique<LibRaw>();
+    check_libraw(processor->open_file(filepath.c_str()), "Failed to open file: " + filepath);
+    return processor->imgdata.other.iso_speed;
+}
+
+FocalLength ImageManager::read_focal_length(const std::string& filepath) {
+    LibRawLock libraw_lock;
+    auto processor = std::make_unique<LibRaw>();
+    check_libraw(processor->open_file(filepath.c_str()), "Failed to open file: " + filepath);
+    return focal_length_of(processor.get());
+}
+
+CaptureFlags ImageManager::read_capture_flags(const std::string& filepath) {
+    LibRawLock libraw_lock;
+    auto processor = std::make_unique<LibRaw>();
+    check_libraw(processor->open_file(filepath.c_str()), "Failed to open file: " + filepath);
+    return capture_flags_of(processor.get());
+}
+
+RawBayerData ImageManager::load_raw_bayer(const std::string& filepath) {
+    std::unique_ptr<LibRaw> processor = open_and_unpack(filepath);
+    // Foveon, X-Trans, and linear DNGs have no single-channel Bayer mosaic.
+    const libraw_iparams_t& idata = processor->imgdata.idata;
+    if (!processor->imgdata.rawdata.raw_image || idata.filters < 1000) {
+        throw std::runtime_error("Not a Bayer sensor image: " + filepath);
+    }
+
+    const libraw_image_sizes_t& sizes = processor->imgdata.sizes;
+    RawBayerData result;
+    result.raw_width = sizes.raw_width;
+    result.raw_height = sizes.raw_height;
+    result.left_margin = sizes.left_margin;
+    result.top_margin = sizes.top_margin;
+    result.width = sizes.width;
+    result.height = sizes.height;
+    result.black = processor->imgdata.color.black;
+    result.maximum = processor->imgdata.color.maximum;
+
+    // Rows may be padded, so copy them one at a time using the pitch in bytes.
+    result.data.resize(static_cast<size_t>(sizes.raw_width) * sizes.raw_height);
+    size_t pitch = sizes.raw_pitch / sizeof(uint16_t);
+    for (int row = 0; row < sizes.raw_height; ++row) {
+        const uint16_t* src = processor->imgdata.rawdata.raw_image + row * pitch;
+        std::copy(src, src + sizes.raw_width, result.data.begin() + static_cast<size_t>(row) * sizes.raw_width);
+    }
+
+    // COLOR takes coordinates relative to the visible area.
+    for (int row = 0; row < 2; ++row) {
+        for (int col = 0; col < 2; ++col) {
+            result.cfa_pattern += idata.cdesc[processor->COLOR(row, col)];
+        }
+    }
+    return result;
+}
+
+std::vector<uint8_t> ImageManager::decode_to_tiff(const std::string& filepath, int bits) {
+    // LibRaw's own TIFF writer (dcraw_ppm_tiff_writer) only writes to files,
+    // so the processed image is encoded in memory instead.
+    // The output is sRGB; its tone curve replaces LibRaw's default BT.709
+    // one, so the pixels match the embedded profile.
+    std::unique_ptr<LibRaw> processor = open_and_unpack(filepath);
+    DecodeOptions options;
+    options.bits = bits;
+    options.gamma = {1.0 / 2.4, 12.92};
+    apply_decode_options(processor.get(), options);
+    DecodedImage decoded = process_image(processor.get());
+    const ImageBuffer& image = decoded.image;
+    require_gray_or_rgb(image, "TIFF output");
+    return encode_tiff(image.data, image.width, image.height, image.channels, decoded.bits, srgb_icc_profile());
+}
+
+Linear16Image ImageManager::decode_linear16(const std::string& filepath, float bright) {
+    // Frames of a bracket must be scaled alike, so this sets
+    //   params.output_bps = 16, params.gamm = {1, 1} (linear),
+    //   params.no_auto_bright = 1 (no per-frame histogram stretch),
+    //   params.use_camera_wb = 1 (the same as-shot white balance in every frame),
+    // and keeps LibRaw's sRGB output primaries. `bright` scales linearly.
+    std::unique_ptr<LibRaw> processor = open_and_unpack(filepath);
+    DecodeOptions options;
+    options.bits = 16;
+    options.gamma = {1.0, 1.0};
+    options.bright = bright;
+    apply_decode_options(processor.get(), options);
+    processor->imgdata.params.no_auto_bright = 1;
+    processor->imgdata.params.use_camera_wb = 1;
+
+    DecodedImage decoded = process_image(processor.get());
+    if (decoded.image.channels != 3) {
+        throw std::runtime_error("A linear decode needs RGB output, but LibRaw returned " +
+                                 std::to_string(decoded.image.channels) + " channels");
+    }
+    Linear16Image result;
+    result.width = decoded.image.width;
+    result.height = decoded.image.height;
+    result.data.resize(decoded.image.data.size() / sizeof(uint16_t));
+    std::memcpy(result.data.data(), decoded.image.data.data(), decoded.image.data.size());
+    result.max_value = result.data.empty() ? 0 : *std::max_element(result.data.begin(), result.data.end());
+    return result;
+}
+
+Float32Image ImageManager::load_raw_f32(const std::string& filepath, bool normalize, const DecodeOptions& options) {
+    std::unique_ptr<LibRaw> processor = open_and_unpack(filepath);
+    DecodeOptions decode_options = options;
+    decode_options.bits = 16;
+    apply_decode_options(processor.get(), decode_options);
+    DecodedImage decoded = process_image(processor.get());
+
+    Float32Image result;
+    result.width = decoded.image.width;
+    result.height = decoded.image.height;
+    result.channels = decoded.image.channels;
+    result.data.resize(decoded.image.data.size() / sizeof(uint16_t));
+    const uint8_t* samples = decoded.image.data.data();
+    float scale = normalize ? 1.0f / 65535.0f : 1.0f;
+    parallel_chunks(result.data.size(), size_t(1) << 20, [&](size_t begin, size_t end) {
+        for (size_t i = begin; i < end; ++i) {
+            uint16_t sample;
+            std::memcpy(&sample, samples + i * sizeof(uint16_t), sizeof(uint16_t));
+            result.data[i] = sample * scale;
+        }
+    });
+    return result;
+}
+
+WhiteBalanceComparison ImageManager::load_raw_wb_compare(const std::string& filepath, bool half_size) {
+    // The raw data is unpacked once; only processing runs twice.
+    std::unique_ptr<LibRaw> processor = open_and_unpack(filepath);
+    DecodeOptions options;
+    options.half_size = half_size;
+    apply_decode_options(processor.get(), options);
+    libraw_output_params_t& params = processor->imgdata.params;
+    const float* applied = processor->imgdata.color.pre_mul;
+
+    WhiteBalanceComparison result;
+    params.use_camera_wb = 1;
+    params.use_auto_wb = 0;
+    result.camera = process_image(processor.get());
+    std::copy(applied, applied + 4, result.camera_multipliers.begin());
+
+    params.use_camera_wb = 0;
+    params.use_auto_wb = 1;
+    result.automatic = process_image(processor.get());
+    std::copy(applied, applied + 4, result.auto_multipliers.begin());
+    return result;
+}
+
+std::vector<uint8_t> ImageManager::make_contact_thumb(const std::string& filepath, int max_dim, int quality) {
+    if (max_dim <= 0) {
+        throw std::invalid_argument("Contact thumbnail size must be positive");
+    }
+
+    std::unique_ptr<LibRaw> processor = open_and_unpack(filepath);
+    DecodeOptions options;
+    options.half_size = true;
+    apply_decode_options(processor.get(), options);
+    ImageBuffer image = process_image(processor.get()).image;
+    require_gray_or_rgb(image, "A contact thumbnail");
+
+    // Scale the longer side down to max_dim; never enlarge.
+    double scale = std::min(1.0, static_cast<double>(max_dim) / std::max(image.width, image.height));
+    int thumb_width = std::max(1, static_cast<int>(std::lround(image.width * scale)));
+    int thumb_height = std::max(1, static_cast<int>(std::lround(image.height * scale)));
+    if (thumb_width != image.width || thumb_height != image.height) {
+        image.data = resize_image(image.data, image.width, image.height, image.channels, thumb_width, thumb_height);
+    }
+    return encode_jpeg(image.data, thumb_width, thumb_height, image.channels, quality);
+}
+
+std::vector<uint8_t> ImageManager::thumbnail_or_halfsize(const std::string& filepath, int min_dim, int quality) {
+    return cached_preview(filepath, min_dim, quality, false).jpeg;
+}
+
+BestPreview ImageManager::load_best_preview(const std::string& filepath, int target_dim, int quality) {
+    return cached_preview(filepath, target_dim, quality, true);
+}
+
+BestPreview ImageManager::cached_preview(const std::string& filepath, int min_dim, int quality, bool allow_full) {
+    // Files whose modification time cannot be read are not cached; opening
+    // them reports the actual problem.
+    std::error_code error;
+    PreviewCache::FileTime mtime = std::filesystem::last_write_time(filepath, error);
+    if (error) {
+        return fastest_preview(filepath, min_dim, quality, allow_full);
+    }
+
+    std::string key = filepath + '\0' + std::to_string(min_dim) + ',' + std::to_string(quality) +
+                      (allow_full ? ",full" : "");
+    if (std::optional<BestPreview> cached = pimpl->preview_cache.find(key, mtime)) {
+        return *cached;
+    }
+    BestPreview preview = fastest_preview(filepath, min_dim, quality, allow_full);
+    pimpl->preview_cache.insert(key, mtime, preview);
+    return preview;
+}
+
+void ImageManager::set_preview_cache_size(size_t bytes) {
+    pimpl->preview_cache.set_capacity(bytes);
+}
+
+void ImageManager::clear_preview_cache() {
+    pimpl->preview_cache.clear();
+}
+
+PreviewCacheStats ImageManager::preview_cache_stats() {
+    return pimpl->preview_cache.stats();
+}
+
+DecodedImage ImageManager::load_raw_with_timeout(const std::string& filepath, int timeout_ms,
+                                                 const DecodeOptions& options) {
+    if (timeout_ms <= 0) {
+        throw std::invalid_argument("Decode timeout must be positive");
+    }
+
+    // The processor is private to this call, so a cancelled decode leaves
+    // nothing behind: it is destroyed, and the file closed, on the way out.
+    Deadline deadline{std::chrono::steady_clock::now() + std::chrono::milliseconds(timeout_ms)};
+    auto processor = std::make_unique<LibRaw>();
+    ProgressHandlerGuard deadline_guard(processor.get(), cancel_after_deadline, &deadline);
+    try {
+        {
+            LibRawLock libraw_lock;
+            check_libraw(processor->open_file(filepath.c_str()), "Failed to open file: " + filepath);
+            unpack_opened(processor.get(), "file: " + filepath);
+        }
+        apply_decode_options(processor.get(), options);
+        return process_image(processor.get());
+    } catch (const std::runtime_error&) {
+        if (deadline.expired) {
+            throw DecodeTimeout("Decoding " + filepath + " took longer than " + std::to_string(timeout_ms) + " ms");
+        }
+        throw;
+    }
+}
+
+std::vector<std::vector<uint8_t>> ImageManager::develop_batch(const std::vector<std::string>& filepaths,
+                                                             const DevelopSettings& settings, int quality) {
+    // Each file is decoded on a private processor, so files are developed in
+    // parallel. Worker threads must not throw; the first failure, in input
+    // order, is rethrown once all of them are done.
+    std::vector<std::vector<uint8_t>> results(filepaths.size());
+    std::vector<std::exception_ptr> errors(filepaths.size());
+    parallel_chunks(filepaths.size(), 2, [&](size_t begin, size_t end) {
+        for (size_t i = begin; i < end; ++i) {
+            try {
+                std::unique_ptr<LibRaw> processor = open_and_unpack(filepaths[i]);
+                apply_decode_options(processor.get(), DecodeOptions());
+                ImageBuffer image = process_image(processor.get()).image;
+                std::vector<uint8_t> developed = develop(image.data, image.width, image.height, settings);
+                results[i] = encode_jpeg(developed, image.width, image.height, 3, quality);
+            } catch (...) {
+                errors[i] = std::current_exception();
+            }
+        }
+    });
+    for (const std::exception_ptr& error : errors) {
+        if (error) {
+            std::rethrow_exception(error);
+        }
+    }
+    return results;
+}
+
+DevelopPreview ImageManager::begin_develop(const std::string& filepath, const DevelopSettings& settings) {
+    uint64_t id = load_raw_image(filepath);
+    try {
+        {
+            std::shared_ptr<Impl::Entry> entry = pimpl->find_entry(id);
+            std::lock_guard<std::mutex> lock(entry->mutex);
+            entry->develop_settings = settings;
+        }
+        DecodeOptions options;
+        options.half_size = true;
+        ImageBuffer image = decode_image(id, options).image;
+        DevelopPreview result;
+        result.preview = ImageBuffer{develop(image.data, image.width, image.height, settings),
+                                     image.width, image.height, 3};
+        result.session = id;
+        return result;
+    } catch (...) {
+        release_raw_image(id);
+        throw;
+    }
+}
+
+ImageBuffer ImageManager::finish_develop(uint64_t session) {
+    std::optional<DevelopSettings> settings;
+    {
+        std::shared_ptr<Impl::Entry> entry = pimpl->find_entry(session);
+        std::lock_guard<std::mutex> lock(entry->mutex);
+        settings = entry->develop_settings;
+    }
+    if (!settings) {
+        throw std::runtime_error("Image " + std::to_string(session) + " is not a develop session");
+    }
+
+    // The session ends here whether or not the decode succeeds; its processor
+    // goes with the entry once no other call is using it.
+    ImageBuffer image;
+    try {
+        image = decode_image(session).image;
+    } catch (...) {
+        release_raw_image(session);
+        throw;
+    }
+    release_raw_image(session);
+    return ImageBuffer{develop(image.data, image.width, image.height, *settings), image.width, image.height, 3};
+}
+
+DecodedImage ImageManager::decode_image(uint64_t id, const DecodeOptions& options,
+                                        bool collect_diagnostics, const ProgressCallback& progress) {
+    std::shared_ptr<Impl::Entry> entry = pimpl->find_entry(id);
+    std::lock_guard<std::mutex> lock(entry->mutex);
+    check_not_failed(entry->failed, id);
+    LibRaw* processor = entry->processor.get();
+    LibRawLock libraw_lock;
+    apply_decode_options(processor, options);
+    ProgressHandlerGuard progress_guard(processor, progress);
+
+    auto process_start = std::chrono::steady_clock::now();
+    {
+        DngOpcodeStages opcode_stages(processor, options.dng_opcodes ? &entry->opcodes() : nullptr);
+        check_libraw_keeping(processor, entry->failed, processor->dcraw_process(), "Failed to process raw image");
+        opcode_stages.rethrow();
+    }
+    double process_ms = elapsed_ms(process_start);
+
+    int error = LIBRAW_SUCCESS;
+    libraw_processed_image_t* image = processor->dcraw_make_mem_image(&error);
+    if (!image) {
+        throw_libraw_error("Failed to create memory image", error);
+    }
+
+    ProcessedImagePtr image_ptr = own_processed_image(image);
+    check_processed_image(image);
+
+    auto copy_start = std::chrono::steady_clock::now();
+    DecodedImage result;
+    if (options.planar) {
+        size_t pixels = static_cast<size_t>(image->width) * image->height;
+        result.image.data = copy_planar(image->data, pixels, image->colors, image->bits / 8);
+        result.planar = true;
+    } else {
+        result.image.data = copy_buffer(image->data, image->data_size);
+    }
+    result.image.width = image->width;
+    result.image.height = image->height;
+    result.image.channels = image->colors;
+    result.bits = image->bits;
+    result.maximum = processor->imgdata.color.maximum;
+    result.data_maximum = processor->imgdata.color.data_maximum;
+
+    if (collect_diagnostics) {
+        DecodeDiagnostics diagnostics;
+        diagnostics.process_ms = process_ms;
+        diagnostics.copy_ms = elapsed_ms(copy_start);
+        diagnostics.buffer_size = result.image.data.size();
+        result.diagnostics = diagnostics;
+    }
+    return result;
+}
+
+DecodedImage ImageManager::decode_stripes(uint64_t id, int stripe_height, const StripeCallback& callback,
+                                          const DecodeOptions& options) {
+    if (stripe_height <= 0) {
+        throw std::invalid_argument("Stripe height must be positive");
+    }
+    if (options.planar) {
+        throw std::invalid_argument("Striped decodes are always interleaved");
+    }
+
+    // The stripes are read from the processor, so the image stays locked
+    // until the last one has been delivered.
+    std::shared_ptr<Impl::Entry> entry = pimpl->find_entry(id);
+    std::lock_guard<std::mutex> lock(entry->mutex);
+    check_not_failed(entry->failed, id);
+    LibRaw* processor = entry->processor.get();
+    {
+        LibRawLock libraw_lock;
+        apply_decode_options(processor, options);
+        DngOpcodeStages opcode_stages(processor, options.dng_opcodes ? &entry->opcodes() : nullptr);
+        check_libraw_keeping(processor, entry->failed, processor->dcraw_process(), "Failed to process raw image");
+        opcode_stages.rethrow();
+    }
+
+    const libraw_data_t& data = processor->imgdata;
+    const libraw_image_sizes_t& sizes = data.sizes;
+    int colors = data.idata.colors;
+    int bits = data.params.output_bps;
+    if (!data.image || colors < 1 || colors > 4) {
+        throw std::runtime_error("LibRaw returned an unsupported image format: " + std::to_string(colors) +
+                                 " channels");
+    }
+    // Rows are converted as dcraw_make_mem_image converts them, including
+    // the flip, but one stripe at a time into the same buffer.
+    std::vector<uint16_t> curve = output_curve(processor);
+    int flip = sizes.flip;
+    bool transposed = (flip & 4) != 0;
+    int width = transposed ? sizes.height : sizes.width;
+    int height = transposed ? sizes.width : sizes.height;
+
+    DecodedImage result;
+    result.image.width = width;
+    result.image.height = height;
+    result.image.channels = colors;
+    result.bits = bits;
+    result.maximum = data.color.maximum;
+    result.data_maximum = data.color.data_maximum;
+
+    size_t row_size = static_cast<size_t>(width) * colors * (bits / 8);
+    std::vector<uint8_t> stripe(row_size * std::min(stripe_height, height));
+    for (int y = 0; y < height; y += stripe_height) {
+        int rows = std::min(stripe_height, height - y);
+        uint8_t* dst = stripe.data();
+        for (int row = y; row < y + rows; ++row) {
+            for (int col = 0; col < width; ++col) {
+                int src_row = transposed ? col : row;
+                int src_col = transposed ? row : col;
+                if (flip & 2) {
+                    src_row = sizes.height - 1 - src_row;
+                }
+                if (flip & 1) {
+                    src_col = sizes.width - 1 - src_col;
+                }
+                const unsigned short* src = data.image[static_cast<size_t>(src_row) * sizes.width + src_col];
+                for (int c = 0; c < colors; ++c) {
+                    uint16_t value = curve[src[c]];
+                    if (bits == 8) {
+                        *dst++ = static_cast<uint8_t>(value >> 8);
+                    } else {
+                        std::memcpy(dst, &value, sizeof(value));
+                        dst += sizeof(value);
+                    }
+                }
+            }
+        }
+        callback(y, stripe.data(), rows * row_size, rows);
+    }
+    return result;
+}
@@ -0,0 +1,1829 @@
+#include "mpr_photo_editor/image_ops.h"
+#include "mpr_photo_editor/color.h"
+#include "pixel_utils.h"
+#include <algorithm>
+#include <array>
+#include <bitset>
+#include <cmath>
+#include <cstddef>
+#include <mutex>
+#include <stdexcept>
+
+// --- Internal Helpers ---
+namespace {
+
+// Rec. 709 luma of a normalized RGB triple.
+float luminance(float r, float g, float b) {
+    return 0.2126f * r + 0.7152f * g + 0.0722f * b;
+}
+
+float smoothstep(float edge0, float edge1, float x) {
+    float t = std::clamp((x - edge0) / (edge1 - edge0), 0.0f, 1.0f);
+    return t * t * (3.0f - 2.0f * t);
+}
+
+// Maps a coordinate that may lie outside [0, size) to the pixel it reads
+// under `edge`, or -1 where a constant border is read instead.
+int edge_index(int i, int size, EdgeMode edge) {
+    if (i >= 0 && i < size) {
+        return i;
+    }
+    switch (edge) {
+        case EdgeMode::Reflect: {
+            if (size == 1) {
+                return 0;
+            }
+            int period = 2 * (size - 1);
+            int m = ((i % period) + period) % period;
+            return m < size ? m : period - m;
+        }
+        case EdgeMode::Wrap:
+            return ((i % size) + size) % size;
+        case EdgeMode::Constant:
+            return -1;
+        default:
+            return std::clamp(i, 0, size - 1);
+    }
+}
+
+// Extends an image by `border` pixels on every side according to `edge`.
+std::vector<uint8_t> pad_image(const std::vector<uint8_t>& image, int width, int height, int channels,
+                               int border, EdgeMode edge) {
+    int padded_width = width + 2 * border;
+    int padded_height = height + 2 * border;
+    std::vector<uint8_t> result(static_cast<size_t>(padded_width) * padded_height * channels, 0);
+    for (int y = 0; y < padded_height; ++y) {
+        int sy = edge_index(y - border, height, edge);
+        if (sy < 0) {
+            continue;
+        }
+        for (int x = 0; x < padded_width; ++x) {
+            int sx = edge_index(x - border, width, edge);
+            if (sx < 0) {
+                continue;
+            }
+            const uint8_t* src = image.data() + (static_cast<size_t>(sy) * width + sx) * channels;
+            std::copy(src, src + channels, result.data() + (static_cast<size_t>(y) * padded_width + x) * channels);
+        }
+    }
+    return result;
+}
+
+// Summed-area table with a zero row and column in front, so the sum over
+// [x0, x1) x [y0, y1) is I(x1, y1) - I(x0, y1) - I(x1, y0) + I(x0, y0).
+// 64-bit accumulation cannot overflow for any realistic image size.
+std::vector<uint64_t> integral_image(const std::vector<uint8_t>& image, int width, int height,
+                                     int channels) {
+    size_t stride = static_cast<size_t>(width + 1) * channels;
+    std::vector<uint64_t> table(stride * (height + 1), 0);
+    for (int y = 0; y < height; ++y) {
+        std::vector<uint64_t> row_sum(channels, 0);
+        const uint8_t* src = image.data() + static_cast<size_t>(y) * width * channels;
+        uint64_t* above = table.data() + static_cast<size_t>(y) * stride;
+        uint64_t* dst = above + stride;
+        for (int x = 0; x < width; ++x) {
+            for (int c = 0; c < channels; ++c) {
+                row_sum[c] += src[static_cast<size_t>(x) * channels + c];
+                size_t idx = static_cast<size_t>(x + 1) * channels + c;
+                dst[idx] = above[idx] + row_sum[c];
+            }
+        }
+    }
+    return table;
+}
+
+// One term of a box window sum along an axis: `weight` times the sum of the
+// first `index` samples of the line.
+struct WindowTerm {
+    int index;
+    int64_t weight;
+};
+
+// Adds `sign` times the sum over [0, i) of a line of `size` samples, extended
+// by `edge`, to `terms`; i may lie anywhere. Clamp and Constant windows are
+// clipped to the line. Reflect and Wrap repeat with a period of 2 * size - 2
+// and size samples, so whole periods add multiples of one period's sum.
+void add_extended_prefix(std::vector<WindowTerm>& terms, int64_t i, int size, EdgeMode edge,
+                         int64_t sign) {
+    auto add = [&](int64_t index, int64_t weight) {
+        for (WindowTerm& term : terms) {
+            if (term.index == index) {
+                term.weight += weight;
+                return;
+            }
+        }
+        terms.push_back({static_cast<int>(index), weight});
+    };
+    if (edge == EdgeMode::Clamp || edge == EdgeMode::Constant) {
+        add(std::clamp<int64_t>(i, 0, size), sign);
+        return;
+    }
+    bool wrap = edge == EdgeMode::Wrap || size == 1;
+    int64_t period = wrap ? size : 2 * static_cast<int64_t>(size) - 2;
+    int64_t periods = i >= 0 ? i / period : -((period - 1 - i) / period);
+    int64_t m = i - periods * period;
+    if (wrap) {
+        add(size, sign * periods);
+        add(m, sign);
+        return;
+    }
+    // A reflected period is the line followed by its inner samples reversed.
+    add(size, sign * periods);
+    add(size - 1, sign * periods);
+    add(1, -sign * periods);
+    if (m <= size) {
+        add(m, sign);
+    } else {
+        add(size, sign);
+        add(size - 1, sign);
+        add(2 * static_cast<int64_t>(size) - 1 - m, -sign);
+    }
+}
+
+// Floating point working image used by the multi-scale operations, which
+// need intermediate values outside the 8-bit range.
+struct FloatImage {
+    std::vector<float> data;
+    int width = 0;
+    int height = 0;
+    int channels = 0;
+};
+
+FloatImage to_float_image(const std::vector<uint8_t>& image, int width, int height, int channels) {
+    return FloatImage{std::vector<float>(image.begin(), image.end()), width, height, channels};
+}
+
+std::vector<uint8_t> to_u8(const std::vector<float>& data) {
+    std::vector<uint8_t> result(data.size());
+    std::transform(data.begin(), data.end(), result.begin(), clamp_u8);
+    return result;
+}
+
+// Separable 5-tap binomial [1 4 6 4 1] / 16 low-pass filter with clamped
+// borders. Rows are filtered on several threads for large images.
+FloatImage binomial_blur(const FloatImage& src) {
+    static const float kernel[5] = {1.0f / 16, 4.0f / 16, 6.0f / 16, 4.0f / 16, 1.0f / 16};
+    int w = src.width, h = src.height, ch = src.channels;
+    FloatImage tmp{std::vector<float>(src.data.size()), w, h, ch};
+    FloatImage dst{std::vector<float>(src.data.size()), w, h, ch};
+    size_t parallel_rows = std::max<size_t>(1, (size_t(1) << 16) / (static_cast<size_t>(w) * ch));
+
+    parallel_chunks(h, parallel_rows, [&](size_t begin, size_t end) {
+        for (int y = static_cast<int>(begin); y < static_cast<int>(end); ++y) {
+            for (int x = 0; x < w; ++x) {
+                for (int c = 0; c < ch; ++c) {
+                    float sum = 0.0f;
+                    for (int k = -2; k <= 2; ++k) {
+                        int sx = std::clamp(x + k, 0, w - 1);
+                        sum += kernel[k + 2] * src.data[(static_cast<size_t>(y) * w + sx) * ch + c];
+                    }
+                    tmp.data[(static_cast<size_t>(y) * w + x) * ch + c] = sum;
+                }
+            }
+        }
+    });
+    parallel_chunks(h, parallel_rows, [&](size_t begin, size_t end) {
+        for (int y = static_cast<int>(begin); y < static_cast<int>(end); ++y) {
+            for (int x = 0; x < w; ++x) {
+                for (int c = 0; c < ch; ++c) {
+                    float sum = 0.0f;
+                    for (int k = -2; k <= 2; ++k) {
+                        int sy = std::clamp(y + k, 0, h - 1);
+                        sum += kernel[k + 2] * tmp.data[(static_cast<size_t>(sy) * w + x) * ch + c];
+                    }
+                    dst.data[(static_cast<size_t>(y) * w + x) * ch + c] = sum;
+                }
+            }
+        }
+    });
+    return dst;
+}
+
+// Low-pass filters and keeps every second pixel; odd sizes round up.
+FloatImage downsample(const FloatImage& src) {
+    FloatImage blurred = binomial_blur(src);
+    int w = (src.width + 1) / 2, h = (src.height + 1) / 2, ch = src.channels;
+    FloatImage dst{std::vector<float>(static_cast<size_t>(w) * h * ch), w, h, ch};
+    for (int y = 0; y < h; ++y) {
+        for (int x = 0; x < w; ++x) {
+            for (int c = 0; c < ch; ++c) {
+                dst.data[(static_cast<size_t>(y) * w + x) * ch + c] =
+                    blurred.data[(static_cast<size_t>(2 * y) * src.width + 2 * x) * ch + c];
+            }
+        }
+    }
+    return dst;
+}
+
+std::vector<FloatImage> gaussian_pyramid(FloatImage base, int levels) {
+    std::vector<FloatImage> pyramid;
+    pyramid.push_back(std::move(base));
+    for (int i = 1; i < levels; ++i) {
+        pyramid.push_back(downsample(pyramid.back()));
+    }
+    return pyramid;
+}
+
+// Bilinearly resamples `src` to the given (roughly doubled) size, aligning
+// pixel centers so it is the counterpart of `downsample`.
+FloatImage upsample(const FloatImage& src, int width, int height) {
+    int ch = src.channels;
+    FloatImage dst{std::vector<float>(static_cast<size_t>(width) * height * ch), width, height, ch};
+    for (int y = 0; y < height; ++y) {
+        float fy = std::clamp((y + 0.5f) * src.height / height - 0.5f, 0.0f, src.height - 1.0f);
+        int y0 = static_cast<int>(fy);
+        int y1 = std::min(y0 + 1, src.height - 1);
+        float ty = fy - y0;
+        for (int x = 0; x < width; ++x) {
+            float fx = std::clamp((x + 0.5f) * src.width / width - 0.5f, 0.0f, src.width - 1.0f);
+            int x0 = static_cast<int>(fx);
+            int x1 = std::min(x0 + 1, src.width - 1);
+            float tx = fx - x0;
+            for (int c = 0; c < ch; ++c) {
+                auto at = [&](int sx, int sy) {
+                    return src.data[(static_cast<size_t>(sy) * src.width + sx) * ch + c];
+                };
+                float top = at(x0, y0) + (at(x1, y0) - at(x0, y0)) * tx;
+                float bottom = at(x0, y1) + (at(x1, y1) - at(x0, y1)) * tx;
+                dst.data[(static_cast<size_t>(y) * width + x) * ch + c] = top + (bottom - top) * ty;
+            }
+        }
+    }
+    return dst;
+}
+
+// Each level holds the detail lost between a Gaussian level and the upsampled
+// next one; the last level is the coarsest Gaussian level itself.
+std::vector<FloatImage> laplacian_pyramid(FloatImage base, int levels) {
+    std::vector<FloatImage> pyramid = gaussian_pyramid(std::move(base), levels);
+    for (int i = 0; i + 1 < levels; ++i) {
+        FloatImage expanded = upsample(pyramid[i + 1], pyramid[i].width, pyramid[i].height);
+        for (size_t j = 0; j < expanded.data.size(); ++j) {
+            pyramid[i].data[j] -= expanded.data[j];
+        }
+    }
+    return pyramid;
+}
+
+FloatImage collapse_laplacian(std::vector<FloatImage> pyramid) {
+    for (int i = static_cast<int>(pyramid.size()) - 2; i >= 0; --i) {
+        FloatImage expanded = upsample(pyramid[i + 1], pyramid[i].width, pyramid[i].height);
+        for (size_t j = 0; j < expanded.data.size(); ++j) {
+            pyramid[i].data[j] += expanded.data[j];
+        }
+    }
+    return std::move(pyramid.front());
+}
+
+// Median absolute value, the robust noise estimate for wavelet details.
+float median_abs(const std::vector<float>& values) {
+    std::vector<float> magnitudes(values.size());
+    std::transform(values.begin(), values.end(), magnitudes.begin(), [](float v) { return std::fabs(v); });
+    auto middle = magnitudes.begin() + magnitudes.size() / 2;
+    std::nth_element(magnitudes.begin(), middle, magnitudes.end());
+    return *middle;
+}
+
+// Separable Gaussian blur of a single plane, reading outside it according to
+// `edge`.
+void gaussian_blur_plane(std::vector<float>& plane, int width, int height, float sigma,
+                         EdgeMode edge = EdgeMode::Clamp) {
+    if (sigma <= 0.0f) {
+        return;
+    }
+    int radius = static_cast<int>(std::ceil(3.0f * sigma));
+    std::vector<float> kernel(2 * radius + 1);
+    float total = 0.0f;
+    for (int k = -radius; k <= radius; ++k) {
+        kernel[k + radius] = std::exp(-0.5f * k * k / (sigma * sigma));
+        total += kernel[k + radius];
+    }
+    for (float& weight : kernel) {
+        weight /= total;
+    }
+
+    std::vector<float> tmp(plane.size());
+    for (int y = 0; y < height; ++y) {
+        const float* row = plane.data() + static_cast<size_t>(y) * width;
+        for (int x = 0; x < width; ++x) {
+            float sum = 0.0f;
+            for (int k = -radius; k <= radius; ++k) {
+                int sx = edge_index(x + k, width, edge);
+                if (sx >= 0) {
+                    sum += kernel[k + radius] * row[sx];
+                }
+            }
+            tmp[static_cast<size_t>(y) * width + x] = sum;
+        }
+    }
+    for (int y = 0; y < height; ++y) {
+        for (int x = 0; x < width; ++x) {
+            float sum = 0.0f;
+            for (int k = -radius; k <= radius; ++k) {
+                int sy = edge_index(y + k, height, edge);
+                if (sy >= 0) {
+                    sum += kernel[k + radius] * tmp[static_cast<size_t>(sy) * width + x];
+                }
+            }
+            plane[static_cast<size_t>(y) * width + x] = sum;
+        }
+    }
+}
+
+// Sobel gradient magnitude of a plane, reading outside it according to
+// `edge`, divided by 4 so a step between two values reads as the height of
+// the step.
+std::vector<float> sobel_magnitude(const std::vector<float>& plane, int width, int height,
+                                   EdgeMode edge = EdgeMode::Clamp) {
+    auto at = [&](int x, int y) {
+        int sx = edge_index(x, width, edge);
+        int sy = edge_index(y, height, edge);
+        return sx < 0 || sy < 0 ? 0.0f : plane[static_cast<size_t>(sy) * width + sx];
+    };
+    std::vector<float> magnitude(plane.size());
+    for (int y = 0; y < height; ++y) {
+        for (int x = 0; x < width; ++x) {
+            float gx = (at(x + 1, y - 1) + 2.0f * at(x + 1, y) + at(x + 1, y + 1)) -
+                       (at(x - 1, y - 1) + 2.0f * at(x - 1, y) + at(x - 1, y + 1));
+            float gy = (at(x - 1, y + 1) + 2.0f * at(x, y + 1) + at(x + 1, y + 1)) -
+                       (at(x - 1, y - 1) + 2.0f * at(x, y - 1) + at(x + 1, y - 1));
+            magnitude[static_cast<size_t>(y) * width + x] = 0.25f * std::sqrt(gx * gx + gy * gy);
+        }
+    }
+    return magnitude;
+}
+
+// Removes the lowest-energy vertical seam, a path of one pixel per row that
+// moves at most one column between rows, until `target_width` columns remain.
+// The energy is the gradient magnitude of the channel mean, recomputed after
+// every seam so later seams see the edges the earlier ones created; it reads
+// outside the image according to `edge`.
+std::vector<uint8_t> remove_vertical_seams(std::vector<uint8_t> image, int width, int height,
+                                           int channels, int target_width, EdgeMode edge) {
+    std::vector<float> plane(static_cast<size_t>(width) * height);
+    std::vector<float> cost(plane.size());
+    std::vector<int> seam(height);
+    for (; width > target_width; --width) {
+        plane.resize(static_cast<size_t>(width) * height);
+        for (size_t i = 0; i < plane.size(); ++i) {
+            float sum = 0.0f;
+            for (int c = 0; c < channels; ++c) {
+                sum += image[i * channels + c];
+            }
+            plane[i] = sum / channels;
+        }
+        std::vector<float> energy = sobel_magnitude(plane, width, height, edge);
+
+        // Cumulative minimum cost of a seam ending at each pixel.
+        std::copy(energy.begin(), energy.begin() + width, cost.begin());
+        for (int y = 1; y < height; ++y) {
+            const float* above = cost.data() + static_cast<size_t>(y - 1) * width;
+            float* row = cost.data() + static_cast<size_t>(y) * width;
+            for (int x = 0; x < width; ++x) {
+                float best = above[x];
+                if (x > 0) {
+                    best = std::min(best, above[x - 1]);
+                }
+                if (x < width - 1) {
+                    best = std::min(best, above[x + 1]);
+                }
+                row[x] = energy[static_cast<size_t>(y) * width + x] + best;
+            }
+        }
+
+        // Trace the cheapest seam back from the bottom row.
+        const float* last = cost.data() + static_cast<size_t>(height - 1) * width;
+        seam[height - 1] = static_cast<int>(std::min_element(last, last + width) - last);
+        for (int y = height - 2; y >= 0; --y) {
+            const float* row = cost.data() + static_cast<size_t>(y) * width;
+            int x = seam[y + 1];
+            int best = x;
+            if (x > 0 && row[x - 1] < row[best]) {
+                best = x - 1;
+            }
+            if (x < width - 1 && row[x + 1] < row[best]) {
+                best = x + 1;
+            }
+            seam[y] = best;
+        }
+
+        // Copy every row without the seam's pixel.
+        std::vector<uint8_t> narrowed(static_cast<size_t>(width - 1) * height * channels);
+        uint8_t* dst = narrowed.data();
+        for (int y = 0; y < height; ++y) {
+            const uint8_t* src = image.data() + static_cast<size_t>(y) * width * channels;
+            const uint8_t* cut = src + static_cast<size_t>(seam[y]) * channels;
+            dst = std::copy(src, cut, dst);
+            dst = std::copy(cut + channels, src + static_cast<size_t>(width) * channels, dst);
+        }
+        image = std::move(narrowed);
+    }
+    return image;
+}
+
+struct ResampleTap {
+    int index;
+    float weight;
+};
+
+// For every destination pixel along one axis, the source pixels it covers and
+// the fraction of its footprint each one makes up (area averaging).
+std::vector<std::vector<ResampleTap>> area_weights(int src_size, int dst_size) {
+    std::vector<std::vector<ResampleTap>> taps(dst_size);
+    double scale = static_cast<double>(src_size) / dst_size;
+    for (int i = 0; i < dst_size; ++i) {
+        double start = i * scale;
+        double end = (i + 1) * scale;
+        int last = std::min(static_cast<int>(std::ceil(end)), src_size);
+        for (int j = static_cast<int>(start); j < last; ++j) {
+            double coverage = std::min(end, j + 1.0) - std::max(start, static_cast<double>(j));
+            if (coverage > 0.0) {
+                taps[i].push_back({j, static_cast<float>(coverage / scale)});
+            }
+        }
+    }
+    return taps;
+}
+
+void check_frames(const std::vector<std::vector<uint8_t>>& frames, int width, int height, int channels) {
+    if (frames.empty()) {
+        throw std::invalid_argument("Stacking needs at least one frame");
+    }
+    for (const std::vector<uint8_t>& frame : frames) {
+        check_buffer(frame, width, height, channels);
+    }
+}
+
+// Interpolates all channels at (fx, fy) with the given method.
+void sample_at(const uint8_t* image, int width, int height, int channels,
+               float fx, float fy, Interpolation interpolation, float* out) {
+    if (interpolation == Interpolation::Bicubic) {
+        bicubic_at(image, width, height, channels, fx, fy, out);
+    } else {
+        bilinear_at(image, width, height, channels, fx, fy, out);
+    }
+}
+
+std::vector<float> sample_pixel(const std::vector<uint8_t>& image, int width, int height,
+                                int channels, float fx, float fy, Interpolation interpolation) {
+    check_buffer(image, width, height, channels);
+    if (!std::isfinite(fx) || !std::isfinite(fy)) {
+        throw std::invalid_argument("Sample coordinates must be finite");
+    }
+    std::vector<float> result(channels);
+    sample_at(image.data(), width, height, channels, fx, fy, interpolation, result.data());
+    return result;
+}
+
+// The luminance the local tonemapping operator maps each pixel to. Works on
+// log2 luminance, so compressing the base scales ratios, not differences.
+std::vector<float> local_tonemap_luma(const std::vector<float>& luma, int width, int height,
+                                      float max_luma, const ToneMapParams& params) {
+    // Black pixels would have a log of minus infinity; 20 stops below the
+    // brightest pixel is darker than anything an 8-bit result can show.
+    float darkest = max_luma * std::exp2(-20.0f);
+    FloatImage log_luma{std::vector<float>(luma.size()), width, height, 1};
+    parallel_chunks(luma.size(), size_t(1) << 16, [&](size_t begin, size_t end) {
+        for (size_t p = begin; p < end; ++p) {
+            log_luma.data[p] = std::log2(std::max(luma[p], darkest));
+        }
+    });
+
+    // The base layer is the coarsest pyramid level that still has about
+    // eight pixels along the shorter side, expanded back to full size.
+    int levels = 1;
+    while (levels < 8 && (std::min(width, height) >> levels) >= 8) {
+        ++levels;
+    }
+    std::vector<FloatImage> pyramid = gaussian_pyramid(log_luma, levels);
+    FloatImage base = pyramid.back();
+    for (int i = levels - 2; i >= 0; --i) {
+        base = upsample(base, pyramid[i].width, pyramid[i].height);
+    }
+
+    auto [base_min, base_max] = std::minmax_element(base.data.begin(), base.data.end());
+    float base_range = *base_max - *base_min;
+    float compression = base_range > params.base_stops ? params.base_stops / base_range : 1.0f;
+    float top = *base_max;
+
+    // The brightest part of the base lands on white; detail rides on top.
+    std::vector<float> mapped(luma.size());
+    parallel_chunks(luma.size(), size_t(1) << 16, [&](size_t begin, size_t end) {
+        for (size_t p = begin; p < end; ++p) {
+            float detail = log_luma.data[p] - base.data[p];
+            mapped[p] = std::exp2((base.data[p] - top) * compression + detail * params.detail);
+        }
+    });
+    return mapped;
+}
+
+// Builds the lookup table of a point operation from its curve on [0, 255].
+template <typename Curve>
+ToneLut make_lut(Curve curve) {
+    ToneLut lut;
+    for (int v = 0; v < 256; ++v) {
+        lut[v] = clamp_u8(curve(static_cast<float>(v)));
+    }
+    return lut;
+}
+
+void apply_lut(const ToneLut& lut, uint8_t* image, size_t size) {
+    parallel_chunks(size, size_t(1) << 20, [&](size_t begin, size_t end) {
+        for (size_t i = begin; i < end; ++i) {
+            image[i] = lut[image[i]];
+        }
+    });
+}
+
+std::vector<uint8_t> apply_lut(const ToneLut& lut, const std::vector<uint8_t>& image, int width, int height,
+                               int channels) {
+    check_buffer(image, width, height, channels);
+    std::vector<uint8_t> result = image;
+    apply_lut(lut, result.data(), result.size());
+    return result;
+}
+
+// Linear light values of the 8-bit sRGB codes, scaled to [0, 255].
+const std::array<float, 256>& srgb_decode_table() {
+    static const std::array<float, 256> table = [] {
+        std::array<float, 256> values{};
+        for (int v = 0; v < 256; ++v) {
+            values[v] = 255.0f * srgb_decode(v / 255.0f);
+        }
+        return values;
+    }();
+    return table;
+}
+
+// Turns the counts a scope accumulated into a gray RGB image. The square
+// root lifts rarely hit cells, so the trace of a few pixels stays visible
+// next to one of millions.
+std::vector<uint8_t> render_counts(const std::vector<uint32_t>& counts) {
+    uint32_t peak = std::max(1u, *std::max_element(counts.begin(), counts.end()));
+    std::vector<uint8_t> result(counts.size() * 3);
+    for (size_t i = 0; i < counts.size(); ++i) {
+        uint8_t v = clamp_u8(255.0f * std::sqrt(static_cast<float>(counts[i]) / peak));
+        result[i * 3] = result[i * 3 + 1] = result[i * 3 + 2] = v;
+    }
+    return result;
+}
+
+} // namespace
+
+// --- Color Grading ---
+
+std::vector<uint8_t> split_tone(const std::vector<uint8_t>& image, int width, int height,
+                                float shadow_hue, float shadow_sat,
+                                float highlight_hue, float highlight_sat,
+                                float balance) {
+    check_buffer(image, width, height, 3);
+
+    // The tints are applied as luminance-neutral offsets, so they shift the
+    // color of a pixel without changing its brightness.
+    Color shadow_tint = hsl_to_rgb({shadow_hue, 1.0f, 0.5f});
+    Color highlight_tint = hsl_to_rgb({highlight_hue, 1.0f, 0.5f});
+    float shadow_luma = luminance(shadow_tint[0], shadow_tint[1], shadow_tint[2]);
+    float highlight_luma = luminance(highlight_tint[0], highlight_tint[1], highlight_tint[2]);
+    for (int c = 0; c < 3; ++c) {
+        shadow_tint[c] = (shadow_tint[c] - shadow_luma) * std::clamp(shadow_sat, 0.0f, 1.0f);
+        highlight_tint[c] = (highlight_tint[c] - highlight_luma) * std::clamp(highlight_sat, 0.0f, 1.0f);
+    }
+
+    // A positive balance lowers the pivot, so more of the tonal range counts as highlights.
+    float pivot = 0.5f - 0.5f * std::clamp(balance, -1.0f, 1.0f);
+
+    std::vector<uint8_t> result(image.size());
+    for (size_t i = 0; i < image.size(); i += 3) {
+        float r = image[i] / 255.0f;
+        float g = image[i + 1] / 255.0f;
+        float b = image[i + 2] / 255.0f;
+        float highlight_weight = smoothstep(pivot - 0.5f, pivot + 0.5f, luminance(r, g, b));
+        float shadow_weight = 1.0f - highlight_weight;
+        float rgb[3] = {r, g, b};
+        for (int c = 0; c < 3; ++c) {
+            float v = rgb[c] + shadow_weight * shadow_tint[c] + highlight_weight * highlight_tint[c];
+            result[i + c] = clamp_u8(v * 255.0f);
+        }
+    }
+    return result;
+}
+
+std::vector<uint8_t> color_balance(const std::vector<uint8_t>& image, int width, int height,
+                                   const std::array<float, 3>& shadows,
+                                   const std::array<float, 3>& midtones,
+                                   const std::array<float, 3>& highlights) {
+    check_buffer(image, width, height, 3);
+
+    // A full shift of 1.0 moves a channel by a quarter of the value range.
+    constexpr float max_shift = 0.25f;
+
+    std::vector<uint8_t> result(image.size());
+    for (size_t i = 0; i < image.size(); i += 3) {
+        float rgb[3] = {image[i] / 255.0f, image[i + 1] / 255.0f, image[i + 2] / 255.0f};
+        float luma = luminance(rgb[0], rgb[1], rgb[2]);
+        float shadow_weight = 1.0f - smoothstep(0.0f, 0.5f, luma);
+        float highlight_weight = smoothstep(0.5f, 1.0f, luma);
+        float midtone_weight = 1.0f - shadow_weight - highlight_weight;
+        for (int c = 0; c < 3; ++c) {
+            float shift = shadow_weight * shadows[c] + midtone_weight * midtones[c] +
+                          highlight_weight * highlights[c];
+            result[i + c] = clamp_u8((rgb[c] + shift * max_shift) * 255.0f);
+        }
+    }
+    return result;
+}
+
+std::vector<uint8_t> adjust_vibrance(const std::vector<uint8_t>& image, int width, int height, float amount) {
+    check_buffer(image, width, height, 3);
+    amount = std::clamp(amount, -1.0f, 1.0f);
+
+    // Skin tones sit in a narrow band of orange hues; within `skin_width`
+    // degrees of its centre, boosts fade to `1 - skin_protection` of their
+    // strength.
+    constexpr float skin_hue = 25.0f;
+    constexpr float skin_width = 25.0f;
+    constexpr float skin_protection = 0.7f;
+
+    std::vector<uint8_t> result(image.size());
+    for (size_t i = 0; i < image.size(); i += 3) {
+        Color rgb = {image[i] / 255.0f, image[i + 1] / 255.0f, image[i + 2] / 255.0f};
+        Color hsv = rgb_to_hsv(rgb);
+        float strength = amount * (1.0f - hsv[1]);
+        if (amount > 0.0f) {
+            float distance = std::fabs(std::remainder(hsv[0] - skin_hue, 360.0f));
+            float skin = std::max(0.0f, 1.0f - distance / skin_width);
+            strength *= 1.0f - skin_protection * skin;
+        }
+        float luma = luminance(rgb[0], rgb[1], rgb[2]);
+        for (int c = 0; c < 3; ++c) {
+            result[i + c] = clamp_u8((luma + (rgb[c] - luma) * (1.0f + strength)) * 255.0f);
+        }
+    }
+    return result;
+}
+
+// --- Point Operations ---
+
+ToneLut brightness_lut(float amount) {
+    float offset = std::clamp(amount, -1.0f, 1.0f) * 255.0f;
+    return make_lut([offset](float v) { return v + offset; });
+}
+
+ToneLut contrast_lut(float amount) {
+    float factor = 1.0f + std::clamp(amount, -1.0f, 1.0f);
+    return make_lut([factor](float v) { return (v - 127.5f) * factor + 127.5f; });
+}
+
+ToneLut gamma_lut(float gamma) {
+    if (!(gamma > 0.0f) || !std::isfinite(gamma)) {
+        throw std::invalid_argument("Gamma must be positive");
+    }
+    return make_lut([gamma](float v) { return 255.0f * std::pow(v / 255.0f, 1.0f / gamma); });
+}
+
+ToneLut invert_lut() {
+    return make_lut([](float v) { return 255.0f - v; });
+}
+
+ToneLut srgb_to_linear_lut() {
+    return make_lut([](float v) { return 255.0f * srgb_decode(v / 255.0f); });
+}
+
+ToneLut linear_to_srgb_lut() {
+    return make_lut([](float v) { return 255.0f * srgb_encode(v / 255.0f); });
+}
+
+ToneLut compose_luts(const ToneLut& first, const ToneLut& second) {
+    ToneLut composed;
+    for (int v = 0; v < 256; ++v) {
+        composed[v] = second[first[v]];
+    }
+    return composed;
+}
+
+void apply_lut_inplace(const ToneLut& lut, uint8_t* image, int width, int height, int channels) {
+    check_dimensions(width, height, channels);
+    if (!image) {
+        throw std::invalid_argument("Image buffer must not be null");
+    }
+    apply_lut(lut, image, static_cast<size_t>(width) * height * channels);
+}
+
+std::vector<uint8_t> adjust_brightness(const std::vector<uint8_t>& image, int width, int height,
+                                       int channels, float amount) {
+    return apply_lut(brightness_lut(amount), image, width, height, channels);
+}
+
+void adjust_brightness_inplace(uint8_t* image, int width, int height, int channels, float amount) {
+    apply_lut_inplace(brightness_lut(amount), image, width, height, channels);
+}
+
+std::vector<uint8_t> adjust_contrast(const std::vector<uint8_t>& image, int width, int height,
+                                     int channels, float amount) {
+    return apply_lut(contrast_lut(amount), image, width, height, channels);
+}
+
+void adjust_contrast_inplace(uint8_t* image, int width, int height, int channels, float amount) {
+    apply_lut_inplace(contrast_lut(amount), image, width, height, channels);
+}
+
+std::vector<uint8_t> adjust_gamma(const std::vector<uint8_t>& image, int width, int height,
+                                  int channels, float gamma) {
+    return apply_lut(gamma_lut(gamma), image, width, height, channels);
+}
+
+void adjust_gamma_inplace(uint8_t* image, int width, int height, int channels, float gamma) {
+    apply_lut_inplace(gamma_lut(gamma), image, width, height, channels);
+}
+
+std::vector<uint8_t> invert(const std::vector<uint8_t>& image, int width, int height, int channels) {
+    return apply_lut(invert_lut(), image, width, height, channels);
+}
+
+void invert_inplace(uint8_t* image, int width, int height, int channels) {
+    apply_lut_inplace(invert_lut(), image, width, height, channels);
+}
+
+std::vector<uint8_t> srgb_to_linear(const std::vector<uint8_t>& image, int width, int height, int channels) {
+    return apply_lut(srgb_to_linear_lut(), image, width, height, channels);
+}
+
+void srgb_to_linear_inplace(uint8_t* image, int width, int height, int channels) {
+    apply_lut_inplace(srgb_to_linear_lut(), image, width, height, channels);
+}
+
+std::vector<uint8_t> linear_to_srgb(const std::vector<uint8_t>& image, int width, int height, int channels) {
+    return apply_lut(linear_to_srgb_lut(), image, width, height, channels);
+}
+
+void linear_to_srgb_inplace(uint8_t* image, int width, int height, int channels) {
+    apply_lut_inplace(linear_to_srgb_lut(), image, width, height, channels);
+}
+
+// --- Filters ---
+
+std::vector<uint8_t> box_blur(const std::vector<uint8_t>& image, int width, int height,
+                              int channels, int radius, EdgeMode edge) {
+    check_buffer(image, width, height, channels);
+    if (radius < 0) {
+        throw std::invalid_argument("Blur radius must not be negative");
+    }
+    // Reflected and wrapped window sums grow with the square of the radius;
+    // this limit keeps them within 64 bits.
+    if ((edge == EdgeMode::Reflect || edge == EdgeMode::Wrap) && radius > (1 << 24)) {
+        throw std::invalid_argument("Blur radius is too large for a reflected or wrapped border");
+    }
+
+    // Each window sum is a weighted sum of table entries: a few for the
+    // window parts outside the image, whatever the radius.
+    std::vector<uint64_t> table = integral_image(image, width, height, channels);
+    size_t stride = static_cast<size_t>(width) + 1;
+    int64_t size = 2 * static_cast<int64_t>(radius) + 1;
+    auto axis_window = [&](int i, int length, std::vector<WindowTerm>& terms) {
+        terms.clear();
+        add_extended_prefix(terms, i + static_cast<int64_t>(radius) + 1, length, edge, 1);
+        add_extended_prefix(terms, i - static_cast<int64_t>(radius), length, edge, -1);
+        if (edge != EdgeMode::Clamp) {
+            return static_cast<uint64_t>(size);
+        }
+        return static_cast<uint64_t>(std::min<int64_t>(i + static_cast<int64_t>(radius) + 1, length)
+                                     - std::max<int64_t>(i - static_cast<int64_t>(radius), 0));
+    };
+
+    std::vector<std::vector<WindowTerm>> column_terms(width);
+    std::vector<uint64_t> column_counts(width);
+    for (int x = 0; x < width; ++x) {
+        column_counts[x] = axis_window(x, width, column_terms[x]);
+    }
+
+    std::vector<uint8_t> result(image.size());
+    std::vector<WindowTerm> row_terms;
+    for (int y = 0; y < height; ++y) {
+        uint64_t row_count = axis_window(y, height, row_terms);
+        for (int x = 0; x < width; ++x) {
+            // At most (2^32 - 1)^2, so the product fits even for Constant.
+            uint64_t count = row_count * column_counts[x];
+            for (int c = 0; c < channels; ++c) {
+                int64_t sum = 0;
+                for (const WindowTerm& row : row_terms) {
+                    const uint64_t* table_row = table.data() + static_cast<size_t>(row.index) * stride * channels;
+                    for (const WindowTerm& column : column_terms[x]) {
+                        sum += row.weight * column.weight
+                             * static_cast<int64_t>(table_row[static_cast<size_t>(column.index) * channels + c]);
+                    }
+                }
+                result[(static_cast<size_t>(y) * width + x) * channels + c] =
+                    static_cast<uint8_t>(div_round(static_cast<uint64_t>(sum), count));
+            }
+        }
+    }
+    return result;
+}
+
+std::vector<uint8_t> sharpen(const std::vector<uint8_t>& image, int width, int height,
+                             int channels, float amount, float radius, EdgeMode edge) {
+    check_buffer(image, width, height, channels);
+    if (amount < 0.0f || radius <= 0.0f) {
+        throw std::invalid_argument("Sharpen amount must not be negative and radius must be positive");
+    }
+    if (amount == 0.0f) {
+        return image;
+    }
+
+    size_t pixels = static_cast<size_t>(width) * height;
+    std::vector<uint8_t> result(image.size());
+    std::vector<float> plane(pixels);
+    for (int c = 0; c < channels; ++c) {
+        for (size_t p = 0; p < pixels; ++p) {
+            plane[p] = image[p * channels + c];
+        }
+        gaussian_blur_plane(plane, width, height, radius, edge);
+        for (size_t p = 0; p < pixels; ++p) {
+            float v = image[p * channels + c];
+            result[p * channels + c] = clamp_u8(v + amount * (v - plane[p]));
+        }
+    }
+    return result;
+}
+
+std::vector<uint8_t> sharpen_masked(const std::vector<uint8_t>& image, int width, int height,
+                                    float amount, float radius, float threshold, EdgeMode edge) {
+    check_buffer(image, width, height, 3);
+    if (amount < 0.0f || radius <= 0.0f || threshold < 0.0f) {
+        throw std::invalid_argument("Sharpen amount and threshold must not be negative and radius must be positive");
+    }
+
+    size_t pixels = static_cast<size_t>(width) * height;
+    std::vector<float> luma(pixels);
+    for (size_t p = 0; p < pixels; ++p) {
+        luma[p] = luminance(image[p * 3] / 255.0f, image[p * 3 + 1] / 255.0f, image[p * 3 + 2] / 255.0f);
+    }
+    std::vector<float> blurred = luma;
+    gaussian_blur_plane(blurred, width, height, radius, edge);
+    // The gradient of the blurred luminance responds to edges and texture but
+    // hardly to pixel-level noise, which the blur has averaged out.
+    std::vector<float> gradient = sobel_magnitude(blurred, width, height, edge);
+
+    std::vector<uint8_t> result(image.size());
+    for (size_t p = 0; p < pixels; ++p) {
+        float mask = threshold > 0.0f ? smoothstep(threshold, 2.0f * threshold, gradient[p]) : 1.0f;
+        float detail = 255.0f * amount * mask * (luma[p] - blurred[p]);
+        for (int c = 0; c < 3; ++c) {
+            result[p * 3 + c] = clamp_u8(image[p * 3 + c] + detail);
+        }
+    }
+    return result;
+}
+
+std::vector<uint8_t> denoise_wavelet(const std::vector<uint8_t>& image, int width, int height,
+                                     float strength) {
+    check_buffer(image, width, height, 3);
+    constexpr int levels = 3;
+
+    size_t pixels = static_cast<size_t>(width) * height;
+    std::vector<float> approx(pixels);
+    for (size_t p = 0; p < pixels; ++p) {
+        approx[p] = luminance(image[p * 3], image[p * 3 + 1], image[p * 3 + 2]);
+    }
+    const std::vector<float> original = approx;
+
+    // At level j the Haar low-pass averages each pixel with its neighbors at a
+    // distance of 2^j, so every level keeps the full resolution.
+    std::vector<float> denoised(pixels, 0.0f);
+    std::vector<float> next(pixels), detail(pixels);
+    for (int level = 0; level < levels; ++level) {
+        int step = 1 << level;
+        for (int y = 0; y < height; ++y) {
+            int y1 = std::min(y + step, height - 1);
+            for (int x = 0; x < width; ++x) {
+                int x1 = std::min(x + step, width - 1);
+                next[static_cast<size_t>(y) * width + x] =
+                    0.25f * (approx[static_cast<size_t>(y) * width + x] + approx[static_cast<size_t>(y) * width + x1] +
+                             approx[static_cast<size_t>(y1) * width + x] + approx[static_cast<size_t>(y1) * width + x1]);
+            }
+        }
+        for (size_t p = 0; p < pixels; ++p) {
+            detail[p] = approx[p] - next[p];
+        }
+
+        float threshold = std::max(strength, 0.0f) * 1.5f * median_abs(detail) / 0.6745f;
+        for (size_t p = 0; p < pixels; ++p) {
+            float magnitude = std::max(std::fabs(detail[p]) - threshold, 0.0f);
+            denoised[p] += std::copysign(magnitude, detail[p]);
+        }
+        approx.swap(next);
+    }
+
+    // The luminance change is added to all channels, which keeps the chroma.
+    std::vector<uint8_t> result(image.size());
+    for (size_t p = 0; p < pixels; ++p) {
+        float delta = denoised[p] + approx[p] - original[p];
+        for (int c = 0; c < 3; ++c) {
+            result[p * 3 + c] = clamp_u8(image[p * 3 + c] + delta);
+        }
+    }
+    return result;
+}
+
+std::vector<uint8_t> denoise_ycbcr(const std::vector<uint8_t>& image, int width, int height,
+                                   float luma_strength, float chroma_strength, EdgeMode edge) {
+    check_buffer(image, width, height, 3);
+
+    size_t pixels = static_cast<size_t>(width) * height;
+    std::vector<float> y_plane(pixels), cb_plane(pixels), cr_plane(pixels);
+    for (size_t p = 0; p < pixels; ++p) {
+        Color ycbcr = rgb_to_ycbcr(Color{image[p * 3] / 255.0f, image[p * 3 + 1] / 255.0f, image[p * 3 + 2] / 255.0f});
+        y_plane[p] = ycbcr[0];
+        cb_plane[p] = ycbcr[1];
+        cr_plane[p] = ycbcr[2];
+    }
+
+    gaussian_blur_plane(y_plane, width, height, luma_strength, edge);
+    gaussian_blur_plane(cb_plane, width, height, chroma_strength, edge);
+    gaussian_blur_plane(cr_plane, width, height, chroma_strength, edge);
+
+    std::vector<uint8_t> result(image.size());
+    for (size_t p = 0; p < pixels; ++p) {
+        Color rgb = ycbcr_to_rgb({y_plane[p], cb_plane[p], cr_plane[p]});
+        for (int c = 0; c < 3; ++c) {
+            result[p * 3 + c] = clamp_u8(rgb[c] * 255.0f);
+        }
+    }
+    return result;
+}
+
+std::vector<uint8_t> fix_hot_pixels(const std::vector<uint8_t>& image, int width, int height,
+                                    int channels, int threshold, EdgeMode edge) {
+    check_buffer(image, width, height, channels);
+    if (threshold < 0) {
+        throw std::invalid_argument("Hot pixel threshold must not be negative");
+    }
+
+    std::vector<uint8_t> padded = pad_image(image, width, height, channels, 1, edge);
+    size_t stride = static_cast<size_t>(width + 2) * channels;
+    std::vector<uint8_t> result(image);
+    size_t parallel_rows = std::max<size_t>(1, (size_t(1) << 16) / (static_cast<size_t>(width) * channels));
+    parallel_chunks(height, parallel_rows, [&](size_t begin, size_t end) {
+        std::array<uint8_t, 8> neighbours;
+        for (size_t y = begin; y < end; ++y) {
+            for (int x = 0; x < width; ++x) {
+                for (int c = 0; c < channels; ++c) {
+                    const uint8_t* center = padded.data() + (y + 1) * stride + static_cast<size_t>(x + 1) * channels + c;
+                    size_t n = 0;
+                    for (int dy = -1; dy <= 1; ++dy) {
+                        for (int dx = -1; dx <= 1; ++dx) {
+                            if (dx != 0 || dy != 0) {
+                                neighbours[n++] = center[dy * static_cast<std::ptrdiff_t>(stride) + dx * channels];
+                            }
+                        }
+                    }
+                    // The median of an even count is the mean of the middle two.
+                    std::nth_element(neighbours.begin(), neighbours.begin() + 4, neighbours.end());
+                    uint8_t upper = neighbours[4];
+                    uint8_t lower = *std::max_element(neighbours.begin(), neighbours.begin() + 4);
+                    uint8_t median = static_cast<uint8_t>(div_round(uint64_t(lower) + upper, 2));
+                    if (std::abs(int(*center) - int(median)) > threshold) {
+                        result[(y * width + x) * channels + c] = median;
+                    }
+                }
+            }
+        }
+    });
+    return result;
+}
+
+// --- Geometry ---
+
+std::vector<uint8_t> crop_image(const std::vector<uint8_t>& image, int width, int height,
+                                int channels, const CropRect& rect) {
+    check_buffer(image, width, height, channels);
+    if (rect.width <= 0 || rect.height <= 0 || rect.x < 0 || rect.y < 0 ||
+        rect.x > width - rect.width || rect.y > height - rect.height) {
+        throw std::invalid_argument("Crop rectangle must be non-empty and lie within the image");
+    }
+
+    size_t row_bytes = static_cast<size_t>(rect.width) * channels;
+    std::vector<uint8_t> result(row_bytes * rect.height);
+    for (int y = 0; y < rect.height; ++y) {
+        const uint8_t* src = image.data() + (static_cast<size_t>(rect.y + y) * width + rect.x) * channels;
+        std::copy(src, src + row_bytes, result.data() + y * row_bytes);
+    }
+    return result;
+}
+
+ImageBuffer orient_image(const std::vector<uint8_t>& image, int width, int height,
+                         int channels, int flip) {
+    check_buffer(image, width, height, channels);
+    if (flip < 0 || flip > 7) {
+        throw std::invalid_argument("Flip must be between 0 and 7");
+    }
+
+    bool transpose = flip & 4;
+    ImageBuffer result;
+    result.width = transpose ? height : width;
+    result.height = transpose ? width : height;
+    result.channels = channels;
+    result.data.resize(image.size());
+
+    // Same source lookup as LibRaw's flip_index: every output pixel reads the
+    // input pixel at the (possibly swapped and mirrored) coordinates.
+    for (int y = 0; y < result.height; ++y) {
+        for (int x = 0; x < result.width; ++x) {
+            int row = transpose ? x : y;
+            int col = transpose ? y : x;
+            if (flip & 2) {
+                row = height - 1 - row;
+            }
+            if (flip & 1) {
+                col = width - 1 - col;
+            }
+            const uint8_t* src = image.data() + (static_cast<size_t>(row) * width + col) * channels;
+            uint8_t* dst = result.data.data() + (static_cast<size_t>(y) * result.width + x) * channels;
+            std::copy(src, src + channels, dst);
+        }
+    }
+    return result;
+}
+
+ImageBuffer normalize_orientation(const std::vector<uint8_t>& image, int width, int height,
+                                  int channels, int orientation) {
+    static constexpr int flip_for_orientation[] = {0, 0, 1, 3, 2, 4, 6, 7, 5};
+    if (orientation < 0 || orientation > 8) {
+        throw std::invalid_argument("EXIF orientation must be between 0 and 8");
+    }
+    return orient_image(image, width, height, channels, flip_for_orientation[orientation]);
+}
+
+std::vector<uint8_t> resize_image(const std::vector<uint8_t>& image, int width, int height,
+                                  int channels, int new_width, int new_height, bool linear_light) {
+    check_buffer(image, width, height, channels);
+    if (new_width <= 0 || new_height <= 0) {
+        throw std::invalid_argument("Target dimensions must be positive");
+    }
+
+    // In linear light the samples are averaged as 0-255 linear values through
+    // a decoding table and encoded again at the end. Alpha stays as it is.
+    const std::array<float, 256>& linear = srgb_decode_table();
+    int color_channels = channels == 2 || channels == 4 ? channels - 1 : channels;
+    auto load = [&](uint8_t v, int c) { return linear_light && c < color_channels ? linear[v] : v; };
+    auto store = [&](float v, int c) {
+        if (linear_light && c < color_channels) {
+            v = 255.0f * srgb_encode(std::clamp(v / 255.0f, 0.0f, 1.0f));
+        }
+        return clamp_u8(v);
+    };
+
+    std::vector<std::vector<ResampleTap>> columns = area_weights(width, new_width);
+    std::vector<std::vector<ResampleTap>> rows = area_weights(height, new_height);
+
+    std::vector<float> tmp(static_cast<size_t>(new_width) * height * channels, 0.0f);
+    for (int y = 0; y < height; ++y) {
+        const uint8_t* src = image.data() + static_cast<size_t>(y) * width * channels;
+        float* dst = tmp.data() + static_cast<size_t>(y) * new_width * channels;
+        for (int x = 0; x < new_width; ++x) {
+            for (const ResampleTap& tap : columns[x]) {
+                for (int c = 0; c < channels; ++c) {
+                    dst[x * channels + c] += tap.weight * load(src[tap.index * channels + c], c);
+                }
+            }
+        }
+    }
+
+    size_t stride = static_cast<size_t>(new_width) * channels;
+    std::vector<uint8_t> result(stride * new_height);
+    for (int y = 0; y < new_height; ++y) {
+        for (size_t i = 0; i < stride; ++i) {
+            float sum = 0.0f;
+            for (const ResampleTap& tap : rows[y]) {
+                sum += tap.weight * tmp[tap.index * stride + i];
+            }
+            result[y * stride + i] = store(sum, static_cast<int>(i % channels));
+        }
+    }
+    return result;
+}
+
+std::vector<uint8_t> seam_carve(const std::vector<uint8_t>& image, int width, int height,
+                                int channels, int target_width, int target_height, EdgeMode edge) {
+    check_buffer(image, width, height, channels);
+    if (target_height == 0) {
+        target_height = height;
+    }
+    if (target_width <= 0 || target_width > width || target_height <= 0 || target_height > height) {
+        throw std::invalid_argument("Seam carving target dimensions must be positive and at most the image's");
+    }
+
+    std::vector<uint8_t> result = remove_vertical_seams(image, width, height, channels, target_width, edge);
+    if (target_height < height) {
+        // Horizontal seams are the vertical seams of the transposed image.
+        ImageBuffer transposed = orient_image(result, target_width, height, channels, 4);
+        transposed.data = remove_vertical_seams(std::move(transposed.data), height, target_width,
+                                                channels, target_height, edge);
+        result = orient_image(transposed.data, target_height, target_width, channels, 4).data;
+    }
+    return result;
+}
+
+float straighten_angle(float x0, float y0, float x1, float y1) {
+    if (x0 == x1 && y0 == y1) {
+        throw std::invalid_argument("Straightening needs two distinct points");
+    }
+    constexpr double pi = 3.14159265358979323846;
+    // The line's angle counter-clockwise from horizontal, folded into [-90, 90]
+    // since its direction does not matter, then measured from the nearest axis.
+    double angle = std::atan2(static_cast<double>(y0) - y1, static_cast<double>(x1) - x0) * 180.0 / pi;
+    if (angle > 90.0) {
+        angle -= 180.0;
+    } else if (angle < -90.0) {
+        angle += 180.0;
+    }
+    if (angle > 45.0) {
+        angle -= 90.0;
+    } else if (angle < -45.0) {
+        angle += 90.0;
+    }
+    return static_cast<float>(angle == 0.0 ? 0.0 : -angle);
+}
+
+std::vector<float> sample_bilinear(const std::vector<uint8_t>& image, int width, int height,
+                                   int channels, float fx, float fy) {
+    return sample_pixel(image, width, height, channels, fx, fy, Interpolation::Bilinear);
+}
+
+std::vector<float> sample_bicubic(const std::vector<uint8_t>& image, int width, int height,
+                                  int channels, float fx, float fy) {
+    return sample_pixel(image, width, height, channels, fx, fy, Interpolation::Bicubic);
+}
+
+// --- Multi-Scale ---
+
+std::vector<ImageBuffer> build_pyramid(const std::vector<uint8_t>& image, int width, int height,
+                                       int channels, int levels) {
+    check_buffer(image, width, height, channels);
+    if (levels < 1) {
+        throw std::invalid_argument("A pyramid needs at least one level");
+    }
+
+    std::vector<ImageBuffer> result;
+    for (const FloatImage& level : gaussian_pyramid(to_float_image(image, width, height, channels), levels)) {
+        result.push_back(ImageBuffer{to_u8(level.data), level.width, level.height, level.channels});
+    }
+    return result;
+}
+
+std::vector<uint8_t> blend_laplacian(const std::vector<uint8_t>& a, const std::vector<uint8_t>& b,
+                                     const std::vector<uint8_t>& mask, int width, int height,
+                                     int channels, int levels) {
+    check_buffer(a, width, height, channels);
+    check_buffer(b, width, height, channels);
+    check_buffer(mask, width, height, 1);
+    if (levels < 1) {
+        throw std::invalid_argument("A pyramid needs at least one level");
+    }
+
+    std::vector<FloatImage> pyramid_a = laplacian_pyramid(to_float_image(a, width, height, channels), levels);
+    std::vector<FloatImage> pyramid_b = laplacian_pyramid(to_float_image(b, width, height, channels), levels);
+    FloatImage weights = to_float_image(mask, width, height, 1);
+    for (float& w : weights.data) {
+        w /= 255.0f;
+    }
+    std::vector<FloatImage> pyramid_mask = gaussian_pyramid(std::move(weights), levels);
+
+    for (int i = 0; i < levels; ++i) {
+        FloatImage& level = pyramid_a[i];
+        const FloatImage& other = pyramid_b[i];
+        const FloatImage& m = pyramid_mask[i];
+        for (size_t p = 0; p < m.data.size(); ++p) {
+            for (int c = 0; c < channels; ++c) {
+                size_t idx = p * channels + c;
+                level.data[idx] = other.data[idx] + (level.data[idx] - other.data[idx]) * m.data[p];
+            }
+        }
+    }
+    return to_u8(collapse_laplacian(std::move(pyramid_a)).data);
+}
+
+// --- Stacking ---
+
+std::vector<uint8_t> average_stack(const std::vector<std::vector<uint8_t>>& frames,
+                                   int width, int height, int channels) {
+    check_frames(frames, width, height, channels);
+
+    // 32-bit sums hold more than 16 million frames of 255.
+    uint32_t count = static_cast<uint32_t>(frames.size());
+    std::vector<uint8_t> result(frames.front().size());
+    parallel_chunks(result.size(), size_t(1) << 20, [&](size_t begin, size_t end) {
+        std::vector<uint32_t> sums(end - begin, 0);
+        for (const std::vector<uint8_t>& frame : frames) {
+            for (size_t i = begin; i < end; ++i) {
+                sums[i - begin] += frame[i];
+            }
+        }
+        for (size_t i = begin; i < end; ++i) {
+            result[i] = static_cast<uint8_t>(div_round(sums[i - begin], count));
+        }
+    });
+    return result;
+}
+
+std::vector<uint8_t> stack(const std::vector<std::vector<uint8_t>>& frames,
+                           int width, int height, int channels, StackMode mode) {
+    if (mode == StackMode::Mean) {
+        return average_stack(frames, width, height, channels);
+    }
+    check_frames(frames, width, height, channels);
+
+    std::vector<uint8_t> result(frames.front().size());
+    parallel_chunks(result.size(), size_t(1) << 20, [&](size_t begin, size_t end) {
+        std::vector<uint8_t> samples(frames.size());
+        size_t middle = samples.size() / 2;
+        for (size_t i = begin; i < end; ++i) {
+            for (size_t f = 0; f < frames.size(); ++f) {
+                samples[f] = frames[f][i];
+            }
+            switch (mode) {
+                case StackMode::Max:
+                    result[i] = *std::max_element(samples.begin(), samples.end());
+                    break;
+                case StackMode::Min:
+                    result[i] = *std::min_element(samples.begin(), samples.end());
+                    break;
+                default: {
+                    std::nth_element(samples.begin(), samples.begin() + middle, samples.end());
+                    unsigned upper = samples[middle];
+                    if (samples.size() % 2 == 0) {
+                        // After nth_element the lower middle value is the largest of the first half.
+                        unsigned lower = *std::max_element(samples.begin(), samples.begin() + middle);
+                        upper = static_cast<unsigned>(div_round(lower + upper, 2));
+                    }
+                    result[i] = static_cast<uint8_t>(upper);
+                    break;
+                }
+            }
+        }
+    });
+    return result;
+}
+
+std::vector<uint8_t> focus_stack(const std::vector<std::vector<uint8_t>>& frames,
+                                 int width, int height, int channels, EdgeMode edge) {
+    check_frames(frames, width, height, channels);
+
+    // Local sharpness: absolute Laplacian of the luminance, averaged over a
+    // neighbourhood so flat spots inside detailed areas keep their frame.
+    size_t pixels = static_cast<size_t>(width) * height;
+    std::vector<std::vector<float>> sharpness;
+    for (const std::vector<uint8_t>& frame : frames) {
+        std::vector<float> luma(pixels);
+        for (size_t p = 0; p < pixels; ++p) {
+            const uint8_t* px = frame.data() + p * channels;
+            luma[p] = channels >= 3 ? luminance(px[0], px[1], px[2]) : px[0];
+        }
+        std::vector<float> response(pixels);
+        for (int y = 0; y < height; ++y) {
+            for (int x = 0; x < width; ++x) {
+                auto at = [&](int xx, int yy) {
+                    int sx = edge_index(xx, width, edge);
+                    int sy = edge_index(yy, height, edge);
+                    return sx < 0 || sy < 0 ? 0.0f : luma[static_cast<size_t>(sy) * width + sx];
+                };
+                float laplacian = at(x - 1, y) + at(x + 1, y) + at(x, y - 1) + at(x, y + 1) - 4.0f * at(x, y);
+                response[static_cast<size_t>(y) * width + x] = std::fabs(laplacian);
+            }
+        }
+        gaussian_blur_plane(response, width, height, 2.0f, edge);
+        sharpness.push_back(std::move(response));
+    }
+
+    // Hard selection of the sharpest frame, softened per frame.
+    std::vector<std::vector<float>> weights(frames.size(), std::vector<float>(pixels, 0.0f));
+    for (size_t p = 0; p < pixels; ++p) {
+        size_t best = 0;
+        for (size_t f = 1; f < frames.size(); ++f) {
+            if (sharpness[f][p] > sharpness[best][p]) {
+                best = f;
+            }
+        }
+        weights[best][p] = 1.0f;
+    }
+    for (std::vector<float>& weight : weights) {
+        gaussian_blur_plane(weight, width, height, 2.0f, edge);
+    }
+
+    std::vector<uint8_t> result(frames.front().size());
+    for (size_t p = 0; p < pixels; ++p) {
+        float total = 0.0f;
+        for (const std::vector<float>& weight : weights) {
+            total += weight[p];
+        }
+        for (int c = 0; c < channels; ++c) {
+            float sum = 0.0f;
+            for (size_t f = 0; f < frames.size(); ++f) {
+                sum += weights[f][p] * frames[f][p * channels + c];
+            }
+            result[p * channels + c] = clamp_u8(sum / total);
+        }
+    }
+    return result;
+}
+
+std::vector<uint8_t> subtract_dark_frame(const std::vector<uint8_t>& image, const std::vector<uint8_t>& dark,
+                                         int width, int height, int channels) {
+    check_buffer(image, width, height, channels);
+    check_buffer(dark, width, height, channels);
+
+    std::vector<uint8_t> result(image.size());
+    parallel_chunks(result.size(), size_t(1) << 20, [&](size_t begin, size_t end) {
+        for (size_t i = begin; i < end; ++i) {
+            result[i] = image[i] > dark[i] ? static_cast<uint8_t>(image[i] - dark[i]) : 0;
+        }
+    });
+    return result;
+}
+
+std::vector<uint8_t> flat_field_correct(const std::vector<uint8_t>& image, const std::vector<uint8_t>& flat,
+                                        int width, int height, int channels) {
+    check_buffer(image, width, height, channels);
+    check_buffer(flat, width, height, channels);
+
+    std::vector<uint64_t> sums(channels, 0);
+    for (size_t i = 0; i < flat.size(); ++i) {
+        sums[i % channels] += flat[i];
+    }
+    size_t pixels = static_cast<size_t>(width) * height;
+    std::vector<float> means(channels);
+    for (int c = 0; c < channels; ++c) {
+        if (sums[c] == 0) {
+            throw std::invalid_argument("Flat field channel " + std::to_string(c) + " is black throughout");
+        }
+        means[c] = static_cast<float>(sums[c]) / pixels;
+    }
+
+    std::vector<uint8_t> result(image.size());
+    parallel_chunks(result.size(), size_t(1) << 20, [&](size_t begin, size_t end) {
+        for (size_t i = begin; i < end; ++i) {
+            result[i] = flat[i] == 0 ? image[i] : clamp_u8(image[i] * means[i % channels] / flat[i]);
+        }
+    });
+    return result;
+}
+
+// --- HDR ---
+
+std::vector<float> merge_hdr(const std::vector<std::vector<uint16_t>>& frames,
+                             const std::vector<float>& exposures, int width, int height) {
+    if (frames.empty()) {
+        throw std::invalid_argument("An HDR merge needs at least one frame");
+    }
+    if (exposures.size() != frames.size()) {
+        throw std::invalid_argument("An HDR merge needs one exposure per frame");
+    }
+    if (width <= 0 || height <= 0) {
+        throw std::invalid_argument("Image dimensions must be positive");
+    }
+    size_t pixels = static_cast<size_t>(width) * height;
+    for (size_t f = 0; f < frames.size(); ++f) {
+        if (frames[f].size() != pixels * 3) {
+            throw std::invalid_argument("Every frame must hold width x height RGB samples");
+        }
+        if (!(exposures[f] > 0.0f) || !std::isfinite(exposures[f])) {
+            throw std::invalid_argument("Exposures must be positive");
+        }
+    }
+    size_t shortest = std::min_element(exposures.begin(), exposures.end()) - exposures.begin();
+    size_t longest = std::max_element(exposures.begin(), exposures.end()) - exposures.begin();
+
+    std::vector<float> radiance(pixels * 3);
+    parallel_chunks(pixels, size_t(1) << 18, [&](size_t begin, size_t end) {
+        for (size_t p = begin; p < end; ++p) {
+            float sums[3] = {0.0f, 0.0f, 0.0f};
+            float total = 0.0f;
+            bool saturated = false;
+            for (size_t f = 0; f < frames.size(); ++f) {
+                const uint16_t* rgb = &frames[f][p * 3];
+                float peak = std::max({rgb[0], rgb[1], rgb[2]}) / 65535.0f;
+                float weight = 1.0f - std::fabs(2.0f * peak - 1.0f);
+                saturated = saturated || peak >= 1.0f;
+                for (int c = 0; c < 3; ++c) {
+                    sums[c] += weight * (rgb[c] / 65535.0f) / exposures[f];
+                }
+                total += weight;
+            }
+            if (total <= 0.0f) {
+                // No frame has a usable value: black or saturated throughout.
+                size_t f = saturated ? shortest : longest;
+                for (int c = 0; c < 3; ++c) {
+                    radiance[p * 3 + c] = (frames[f][p * 3 + c] / 65535.0f) / exposures[f];
+                }
+                continue;
+            }
+            for (int c = 0; c < 3; ++c) {
+                radiance[p * 3 + c] = sums[c] / total;
+            }
+        }
+    });
+    return radiance;
+}
+
+std::vector<uint8_t> tonemap(const std::vector<float>& radiance, int width, int height,
+                             ToneMapOperator op, const ToneMapParams& params) {
+    if (width <= 0 || height <= 0) {
+        throw std::invalid_argument("Image dimensions must be positive");
+    }
+    size_t pixels = static_cast<size_t>(width) * height;
+    if (radiance.size() != pixels * 3) {
+        throw std::invalid_argument("Radiance must hold width x height RGB samples");
+    }
+    if (op == ToneMapOperator::Reinhard && (!(params.key > 0.0f) || params.white < 0.0f)) {
+        throw std::invalid_argument("Reinhard needs a positive key and a non-negative white point");
+    }
+    if (op == ToneMapOperator::Drago && !(params.bias >= 0.5f && params.bias <= 1.0f)) {
+        throw std::invalid_argument("Drago's bias must be in [0.5, 1]");
+    }
+    if (op == ToneMapOperator::Local && (!(params.base_stops > 0.0f) || !(params.detail >= 0.0f))) {
+        throw std::invalid_argument("Local tonemapping needs a positive base range and a non-negative detail gain");
+    }
+
+    // Negative or non-finite radiance has no meaning; treat it as black.
+    std::vector<float> luma(pixels);
+    double log_sum = 0.0;
+    float max_luma = 0.0f;
+    for (size_t p = 0; p < pixels; ++p) {
+        float l = luminance(radiance[p * 3], radiance[p * 3 + 1], radiance[p * 3 + 2]);
+        luma[p] = std::isfinite(l) ? std::max(l, 0.0f) : 0.0f;
+        log_sum += std::log(1e-6 + luma[p]);
+        max_luma = std::max(max_luma, luma[p]);
+    }
+    float log_average = static_cast<float>(std::exp(log_sum / pixels));
+    if (max_luma <= 0.0f) {
+        return std::vector<uint8_t>(pixels * 3, 0);
+    }
+
+    // The global operators work on luminance relative to the log-average.
+    std::vector<float> local;
+    if (op == ToneMapOperator::Local) {
+        local = local_tonemap_luma(luma, width, height, max_luma, params);
+    }
+    float scale = op == ToneMapOperator::Reinhard ? params.key / log_average : 1.0f / log_average;
+    float max_scaled = max_luma * scale;
+    float white = params.white > 0.0f ? params.white : max_scaled;
+    float drago_norm = 1.0f / std::log10(max_scaled + 1.0f);
+    float drago_exponent = std::log(params.bias) / std::log(0.5f);
+
+    std::vector<uint8_t> result(pixels * 3);
+    parallel_chunks(pixels, size_t(1) << 16, [&](size_t begin, size_t end) {
+        for (size_t p = begin; p < end; ++p) {
+            float l = luma[p] * scale;
+            float mapped;
+            if (op == ToneMapOperator::Local) {
+                mapped = local[p];
+            } else if (op == ToneMapOperator::Reinhard) {
+                mapped = l * (1.0f + l / (white * white)) / (1.0f + l);
+            } else {
+                float denominator = std::log(2.0f + 8.0f * std::pow(l / max_scaled, drago_exponent));
+                mapped = drago_norm * std::log(l + 1.0f) / denominator;
+            }
+            float gain = luma[p] > 0.0f ? mapped / luma[p] : 0.0f;
+            for (int c = 0; c < 3; ++c) {
+                float linear = std::clamp(radiance[p * 3 + c] * gain, 0.0f, 1.0f);
+                result[p * 3 + c] = clamp_u8(srgb_encode(std::isfinite(linear) ? linear : 0.0f) * 255.0f);
+            }
+        }
+    });
+    return result;
+}
+
+// --- Analysis ---
+
+float suggest_denoise_strength(float iso) {
+    float stops = std::log2(std::max(iso, 100.0f) / 100.0f);
+    float x = stops / 3.0f;
+    return x / (1.0f + x);
+}
+
+float suggest_exposure(const std::vector<uint8_t>& image, int width, int height) {
+    check_buffer(image, width, height, 3);
+
+    std::vector<float> luma(static_cast<size_t>(width) * height);
+    for (size_t p = 0; p < luma.size(); ++p) {
+        luma[p] = luminance(srgb_decode(image[p * 3] / 255.0f),
+                            srgb_decode(image[p * 3 + 1] / 255.0f),
+                            srgb_decode(image[p * 3 + 2] / 255.0f));
+    }
+
+    auto median = luma.begin() + luma.size() / 2;
+    std::nth_element(luma.begin(), median, luma.end());
+    float median_luma = std::max(*median, 1e-4f);
+    auto bright = luma.begin() + std::min(luma.size() - 1, luma.size() * 99 / 100);
+    std::nth_element(luma.begin(), bright, luma.end());
+    float bright_luma = std::max(*bright, 1e-4f);
+
+    float centered = std::log2(0.18f / median_luma);
+    float headroom = std::log2(1.0f / bright_luma);
+    // Only push up to the clipping point; darkening never clips.
+    float suggestion = centered > 0.0f ? std::min(centered, std::max(headroom, 0.0f)) : centered;
+    return std::clamp(suggestion, -5.0f, 5.0f);
+}
+
+std::vector<ChannelStats> image_stats(const std::vector<uint8_t>& image, int width, int height,
+                                      int channels, uint8_t low, uint8_t high) {
+    check_buffer(image, width, height, channels);
+    if (low >= high) {
+        throw std::invalid_argument("The low clipping threshold must be below the high one");
+    }
+
+    struct Accumulator {
+        uint64_t sum = 0;
+        uint8_t min = 255;
+        uint8_t max = 0;
+        size_t clipped_low = 0;
+        size_t clipped_high = 0;
+    };
+    std::vector<Accumulator> totals(channels);
+    std::mutex totals_mutex;
+
+    size_t pixels = static_cast<size_t>(width) * height;
+    parallel_chunks(pixels, size_t(1) << 20, [&](size_t begin, size_t end) {
+        std::vector<Accumulator> local(channels);
+        for (size_t p = begin; p < end; ++p) {
+            for (int c = 0; c < channels; ++c) {
+                uint8_t v = image[p * channels + c];
+                Accumulator& acc = local[c];
+                acc.sum += v;
+                acc.min = std::min(acc.min, v);
+                acc.max = std::max(acc.max, v);
+                acc.clipped_low += v <= low;
+                acc.clipped_high += v >= high;
+            }
+        }
+        std::lock_guard<std::mutex> lock(totals_mutex);
+        for (int c = 0; c < channels; ++c) {
+            totals[c].sum += local[c].sum;
+            totals[c].min = std::min(totals[c].min, local[c].min);
+            totals[c].max = std::max(totals[c].max, local[c].max);
+            totals[c].clipped_low += local[c].clipped_low;
+            totals[c].clipped_high += local[c].clipped_high;
+        }
+    });
+
+    std::vector<ChannelStats> result(channels);
+    for (int c = 0; c < channels; ++c) {
+        result[c].mean = static_cast<float>(static_cast<double>(totals[c].sum) / pixels);
+        result[c].min = totals[c].min;
+        result[c].max = totals[c].max;
+        result[c].clipped_low = totals[c].clipped_low;
+        result[c].clipped_high = totals[c].clipped_high;
+        result[c].clipped_low_percent = static_cast<float>(100.0 * totals[c].clipped_low / pixels);
+        result[c].clipped_high_percent = static_cast<float>(100.0 * totals[c].clipped_high / pixels);
+    }
+    return result;
+}
+
+uint64_t phash(const std::vector<uint8_t>& image, int width, int height) {
+    check_buffer(image, width, height, 3);
+    constexpr int size = 32;
+    constexpr int kept = 8;
+    constexpr float pi = 3.14159265358979f;
+
+    size_t pixels = static_cast<size_t>(width) * height;
+    std::vector<uint8_t> gray(pixels);
+    for (size_t p = 0; p < pixels; ++p) {
+        gray[p] = clamp_u8(luminance(image[p * 3], image[p * 3 + 1], image[p * 3 + 2]));
+    }
+    std::vector<uint8_t> small = resize_image(gray, width, height, 1, size, size);
+
+    // Only the lowest frequencies are needed, so the separable DCT-II is
+    // evaluated for those rows and columns alone.
+    float basis[kept][size];
+    for (int k = 0; k < kept; ++k) {
+        for (int n = 0; n < size; ++n) {
+            basis[k][n] = std::cos(pi * (2 * n + 1) * k / (2 * size));
+        }
+    }
+    float rows[size][kept];
+    for (int y = 0; y < size; ++y) {
+        for (int u = 0; u < kept; ++u) {
+            float sum = 0.0f;
+            for (int x = 0; x < size; ++x) {
+                sum += basis[u][x] * small[y * size + x];
+            }
+            rows[y][u] = sum;
+        }
+    }
+    std::vector<float> coefficients(kept * kept);
+    for (int v = 0; v < kept; ++v) {
+        for (int u = 0; u < kept; ++u) {
+            float sum = 0.0f;
+            for (int y = 0; y < size; ++y) {
+                sum += basis[v][y] * rows[y][u];
+            }
+            coefficients[v * kept + u] = sum;
+        }
+    }
+
+    // The DC term holds the mean brightness only and would skew the median.
+    std::vector<float> sorted(coefficients.begin() + 1, coefficients.end());
+    std::nth_element(sorted.begin(), sorted.begin() + sorted.size() / 2, sorted.end());
+    float median = sorted[sorted.size() / 2];
+
+    uint64_t hash = 0;
+    for (int i = 0; i < kept * kept; ++i) {
+        if (coefficients[i] > median) {
+            hash |= uint64_t(1) << i;
+        }
+    }
+    return hash;
+}
+
+int hash_distance(uint64_t a, uint64_t b) {
+    return static_cast<int>(std::bitset<64>(a ^ b).count());
+}
+
+std::optional<std::array<float, 3>> sample_neutral_multipliers(const std::vector<uint8_t>& image,
+                                                               int width, int height, int x, int y,
+                                                               int radius) {
+    check_buffer(image, width, height, 3);
+    if (x < 0 || x >= width || y < 0 || y >= height) {
+        throw std::invalid_argument("Picked pixel lies outside the image");
+    }
+    if (radius < 0) {
+        throw std::invalid_argument("Sampling radius must not be negative");
+    }
+
+    const std::array<float, 256>& linear = srgb_decode_table();
+    std::array<float, 3> sums = {};
+    int count = 0;
+    for (int sy = std::max(y - radius, 0); sy <= std::min(y + radius, height - 1); ++sy) {
+        for (int sx = std::max(x - radius, 0); sx <= std::min(x + radius, width - 1); ++sx) {
+            const uint8_t* pixel = image.data() + (static_cast<size_t>(sy) * width + sx) * 3;
+            for (int c = 0; c < 3; ++c) {
+                sums[c] += linear[pixel[c]];
+            }
+            ++count;
+        }
+    }
+
+    constexpr float darkest = 0.005f * 255.0f;
+    if (*std::min_element(sums.begin(), sums.end()) < darkest * count) {
+        return std::nullopt;
+    }
+    return std::array<float, 3>{sums[1] / sums[0], 1.0f, sums[1] / sums[2]};
+}
+
+// --- Scopes ---
+
+std::vector<uint8_t> render_histogram(const std::vector<uint8_t>& image, int width, int height,
+                                      int channels, int out_width, int out_height) {
+    check_buffer(image, width, height, channels);
+    if (out_width <= 0 || out_height <= 0) {
+        throw std::invalid_argument("Output dimensions must be positive");
+    }
+    constexpr uint8_t background = 32;
+    constexpr uint8_t fill = 160;
+
+    int plotted = std::min(channels, 3);
+    std::vector<std::array<uint64_t, 256>> bins(plotted);
+    for (std::array<uint64_t, 256>& channel : bins) {
+        channel.fill(0);
+    }
+    size_t pixels = static_cast<size_t>(width) * height;
+    for (size_t p = 0; p < pixels; ++p) {
+        for (int c = 0; c < plotted; ++c) {
+            ++bins[c][image[p * channels + c]];
+        }
+    }
+    uint64_t peak = 1;
+    for (const std::array<uint64_t, 256>& channel : bins) {
+        peak = std::max(peak, *std::max_element(channel.begin(), channel.end()));
+    }
+
+    std::vector<uint8_t> result(static_cast<size_t>(out_width) * out_height * 3, background);
+    for (int x = 0; x < out_width; ++x) {
+        // A column shows the fullest of the bins it covers, so narrow plots
+        // do not hide spikes.
+        int first = x * 256 / out_width;
+        int last = std::max(first + 1, (x + 1) * 256 / out_width);
+        for (int c = 0; c < plotted; ++c) {
+            uint64_t count = *std::max_element(bins[c].begin() + first, bins[c].begin() + last);
+            int bar = static_cast<int>(std::lround(static_cast<double>(count) / peak * out_height));
+            for (int y = out_height - bar; y < out_height; ++y) {
+                uint8_t* px = result.data() + (static_cast<size_t>(y) * out_width + x) * 3;
+                if (plotted == 1) {
+                    px[0] = px[1] = px[2] = background + fill;
+                } else {
+                    px[c] = background + fill;
+                }
+            }
+        }
+    }
+    return result;
+}
+
+std::vector<uint8_t> render_waveform(const std::vector<uint8_t>& image, int width, int height,
+                                     int out_height) {
+    check_buffer(image, width, height, 3);
+    if (out_height <= 0) {
+        throw std::invalid_argument("Output height must be positive");
+    }
+
+    std::vector<uint32_t> counts(static_cast<size_t>(width) * out_height, 0);
+    for (int y = 0; y < height; ++y) {
+        for (int x = 0; x < width; ++x) {
+            const uint8_t* px = image.data() + (static_cast<size_t>(y) * width + x) * 3;
+            float luma = luminance(px[0], px[1], px[2]) / 255.0f;
+            int row = static_cast<int>(std::lround((1.0f - luma) * (out_height - 1)));
+            ++counts[static_cast<size_t>(row) * width + x];
+        }
+    }
+    return render_counts(counts);
+}
+
+std::vector<uint8_t> render_vectorscope(const std::vector<uint8_t>& image, int width, int height,
+                                        int size) {
+    check_buffer(image, width, height, 3);
+    if (size <= 0) {
+        throw std::invalid_argument("Vectorscope size must be positive");
+    }
+
+    std::vector<uint32_t> counts(static_cast<size_t>(size) * size, 0);
+    size_t pixels = static_cast<size_t>(width) * height;
+    for (size_t p = 0; p < pixels; ++p) {
+        Color ycbcr = rgb_to_ycbcr(Color{image[p * 3] / 255.0f, image[p * 3 + 1] / 255.0f, image[p * 3 + 2] / 255.0f});
+        int x = std::clamp(static_cast<int>(std::lround((ycbcr[1] + 0.5f) * (size - 1))), 0, size - 1);
+        int y = std::clamp(static_cast<int>(std::lround((0.5f - ycbcr[2]) * (size - 1))), 0, size - 1);
+        ++counts[static_cast<size_t>(y) * size + x];
+    }
+    return render_counts(counts);
+}
+
+// --- Develop ---
+
+std::vector<uint8_t> develop(const std::vector<uint8_t>& image, int width, int height,
+                             const DevelopSettings& settings) {
+    check_buffer(image, width, height, 3);
+    if (settings.sharpen < 0.0f) {
+        throw std::invalid_argument("Sharpen amount must not be negative");
+    }
+
+    // White balance as channel gains, normalised so the green channel (and
+    // with it most of the brightness) stays put for temperature changes.
+    float temp = std::clamp(settings.temp, -1.0f, 1.0f);
+    float tint = std::clamp(settings.tint, -1.0f, 1.0f);
+    float exposure_gain = std::exp2(settings.exposure);
+    float gains[3] = {
+        exposure_gain * (1.0f + 0.3f * temp),
+        exposure_gain * (1.0f - 0.3f * tint),
+        exposure_gain * (1.0f - 0.3f * temp),
+    };
+    float contrast = 1.0f + std::clamp(settings.contrast, -1.0f, 1.0f);
+    float saturation = 1.0f + std::clamp(settings.saturation, -1.0f, 1.0f);
+    float highlights = std::clamp(settings.highlights, -1.0f, 1.0f);
+    float shadows = std::clamp(settings.shadows, -1.0f, 1.0f);
+
+    size_t pixels = static_cast<size_t>(width) * height;
+    std::vector<float> working(image.size());
+    for (size_t p = 0; p < pixels; ++p) {
+        float rgb[3];
+        for (int c = 0; c < 3; ++c) {
+            float linear = srgb_decode(image[p * 3 + c] / 255.0f) * gains[c];
+            rgb[c] = srgb_encode(std::max(linear, 0.0f));
+        }
+
+        // Highlights and shadows move the tones at either end of the range,
+        // with weights that fade out towards the other end.
+        float luma = luminance(rgb[0], rgb[1], rgb[2]);
+        float shadow_weight = (1.0f - std::min(luma, 1.0f)) * (1.0f - std::min(luma, 1.0f));
+        float highlight_weight = std::min(luma, 1.0f) * std::min(luma, 1.0f);
+        float tone_shift = 0.25f * (shadows * shadow_weight + highlights * highlight_weight);
+        for (float& v : rgb) {
+            v = (v + tone_shift - 0.5f) * contrast + 0.5f;
+        }
+
+        luma = luminance(rgb[0], rgb[1], rgb[2]);
+        for (int c = 0; c < 3; ++c) {
+            working[p * 3 + c] = luma + (rgb[c] - luma) * saturation;
+        }
+    }
+
+    if (settings.sharpen > 0.0f) {
+        // Unsharp mask on luminance only, so sharpening adds no color fringes.
+        std::vector<float> luma(pixels);
+        for (size_t p = 0; p < pixels; ++p) {
+            luma[p] = luminance(working[p * 3], working[p * 3 + 1], working[p * 3 + 2]);
+        }
+        std::vector<float> blurred = luma;
+        gaussian_blur_plane(blurred, width, height, 1.0f);
+        for (size_t p = 0; p < pixels; ++p) {
+            float detail = settings.sharpen * (luma[p] - blurred[p]);
+            for (int c = 0; c < 3; ++c) {
+                working[p * 3 + c] += detail;
+            }
+        }
+    }
+
+    std::vector<uint8_t> result(image.size());
+    for (size_t i = 0; i < working.size(); ++i) {
+        result[i] = clamp_u8(working[i] * 255.0f);
+    }
+    return result;
+}
@@ -0,0 +1,154 @@
+#ifndef MPR_PIXEL_UTILS_H
+#define MPR_PIXEL_UTILS_H
+
+// Internal helpers shared by the pixel processing sources. This header is not
+// installed and not part of the public interface.
+
+#include <algorithm>
+#include <cmath>
+#include <cstddef>
+#include <cstdint>
+#include <stdexcept>
+#include <string>
+#include <thread>
+#include <vector>
+
+inline void check_dimensions(int width, int height, int channels) {
+    if (width <= 0 || height <= 0 || channels <= 0) {
+        throw std::invalid_argument("Image dimensions and channel count must be positive");
+    }
+}
+
+template <typename T>
+void check_buffer(const std::vector<T>& image, int width, int height, int channels) {
+    check_dimensions(width, height, channels);
+    size_t expected = static_cast<size_t>(width) * height * channels;
+    if (image.size() != expected) {
+        throw std::invalid_argument("Buffer size " + std::to_string(image.size()) +
+                                    " does not match " + std::to_string(width) + "x" +
+                                    std::to_string(height) + "x" + std::to_string(channels));
+    }
+}
+
+// Every conversion of a computed sample to 8 bits goes through clamp_u8 or,
+// for exact integer averages, div_round; both round halves to even, so
+// x.5 results do not all drift upwards and the result does not depend on the
+// floating-point environment. NaN becomes 0.
+inline uint8_t clamp_u8(float v) {
+    if (!(v > 0.0f)) {
+        return 0;
+    }
+    if (v >= 255.0f) {
+        return 255;
+    }
+    float whole = std::floor(v);
+    float fraction = v - whole;
+    int result = static_cast<int>(whole);
+    if (fraction > 0.5f || (fraction == 0.5f && (result & 1))) {
+        ++result;
+    }
+    return static_cast<uint8_t>(result);
+}
+
+// `sum` / `count` rounded to the nearest integer, halves to even.
+inline uint64_t div_round(uint64_t sum, uint64_t count) {
+    uint64_t quotient = sum / count;
+    uint64_t twice_remainder = 2 * (sum % count);
+    if (twice_remainder > count || (twice_remainder == count && (quotient & 1))) {
+        ++quotient;
+    }
+    return quotient;
+}
+
+// sRGB transfer function between encoded and linear values in [0, 1].
+inline float srgb_decode(float v) {
+    return v <= 0.04045f ? v / 12.92f : std::pow((v + 0.055f) / 1.055f, 2.4f);
+}
+
+inline float srgb_encode(float v) {
+    return v <= 0.0031308f ? v * 12.92f : 1.055f * std::pow(v, 1.0f / 2.4f) - 0.055f;
+}
+
+// Bilinearly interpolates all channels at (fx, fy) into `out`. Pixel centres
+// sit at integer coordinates; coordinates outside the image are clamped to
+// the border, which repeats the edge pixels. The coordinates must be finite.
+inline void bilinear_at(const uint8_t* image, int width, int height, int channels,
+                        float fx, float fy, float* out) {
+    fx = std::clamp(fx, 0.0f, static_cast<float>(width - 1));
+    fy = std::clamp(fy, 0.0f, static_cast<float>(height - 1));
+    int x0 = static_cast<int>(fx);
+    int y0 = static_cast<int>(fy);
+    int x1 = std::min(x0 + 1, width - 1);
+    int y1 = std::min(y0 + 1, height - 1);
+    float tx = fx - x0;
+    float ty = fy - y0;
+
+    size_t stride = static_cast<size_t>(width) * channels;
+    const uint8_t* top = image + y0 * stride;
+    const uint8_t* bottom = image + y1 * stride;
+    for (int c = 0; c < channels; ++c) {
+        float upper = top[x0 * channels + c] + tx * (top[x1 * channels + c] - top[x0 * channels + c]);
+        float lower = bottom[x0 * channels + c] + tx * (bottom[x1 * channels + c] - bottom[x0 * channels + c]);
+        out[c] = upper + ty * (lower - upper);
+    }
+}
+
+// Catmull-Rom bicubic interpolation over the 4x4 neighbourhood of (fx, fy),
+// with the same conventions as bilinear_at. It keeps edges sharper, at the
+// cost of slight ringing next to them; results are clamped to [0, 255].
+inline void bicubic_at(const uint8_t* image, int width, int height, int channels,
+                       float fx, float fy, float* out) {
+    fx = std::clamp(fx, 0.0f, static_cast<float>(width - 1));
+    fy = std::clamp(fy, 0.0f, static_cast<float>(height - 1));
+    int x0 = static_cast<int>(fx);
+    int y0 = static_cast<int>(fy);
+    auto weights = [](float t, float* w) {
+        w[0] = 0.5f * (-t * t * t + 2.0f * t * t - t);
+        w[1] = 0.5f * (3.0f * t * t * t - 5.0f * t * t + 2.0f);
+        w[2] = 0.5f * (-3.0f * t * t * t + 4.0f * t * t + t);
+        w[3] = 0.5f * (t * t * t - t * t);
+    };
+    float wx[4];
+    float wy[4];
+    weights(fx - x0, wx);
+    weights(fy - y0, wy);
+
+    size_t stride = static_cast<size_t>(width) * channels;
+    for (int c = 0; c < channels; ++c) {
+        float sum = 0.0f;
+        for (int j = 0; j < 4; ++j) {
+            int y = std::clamp(y0 - 1 + j, 0, height - 1);
+            float row = 0.0f;
+            for (int i = 0; i < 4; ++i) {
+                int x = std::clamp(x0 - 1 + i, 0, width - 1);
+                row += wx[i] * image[y * stride + x * channels + c];
+            }
+            sum += wy[j] * row;
+        }
+        out[c] = std::clamp(sum, 0.0f, 255.0f);
+    }
+}
+
+// Splits [0, size) into up to four contiguous chunks and calls
+// `work(begin, end)` for each on its own thread. Below `parallel_threshold`
+// elements, or on a single core, everything runs on the calling thread,
+// because starting threads costs more than the work saves.
+template <typename Work>
+void parallel_chunks(size_t size, size_t parallel_threshold, Work work) {
+    unsigned workers = std::min(std::thread::hardware_concurrency(), 4u);
+    if (size < parallel_threshold || workers < 2) {
+        work(size_t(0), size);
+        return;
+    }
+
+    size_t chunk = (size + workers - 1) / workers;
+    std::vector<std::thread> threads;
+    for (size_t begin = 0; begin < size; begin += chunk) {
+        threads.emplace_back(work, begin, std::min(begin + chunk, size));
+    }
+    for (std::thread& thread : threads) {
+        thread.join();
+    }
+}
+
+#endif // MPR_PIXEL_UTILS_H
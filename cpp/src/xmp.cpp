@@ -0,0 +1,310 @@
+#include "mpr_photo_editor/xmp.h"
+#include <algorithm>
+#include <cctype>
+#include <cmath>
+#include <cstdlib>
+#include <fstream>
+#include <iterator>
+#include <optional>
+#include <stdexcept>
+
+// --- Internal Helpers ---
+namespace {
+
+// Escapes text for use in XML content and attribute values. Control
+// characters other than whitespace cannot appear in XML 1.0 at all.
+std::string xml_escape(const std::string& text) {
+    std::string result;
+    result.reserve(text.size());
+    for (char c : text) {
+        switch (c) {
+            case '&': result += "&amp;"; break;
+            case '<': result += "&lt;"; break;
+            case '>': result += "&gt;"; break;
+            case '"': result += "&quot;"; break;
+            case '\'': result += "&apos;"; break;
+            default:
+                if (static_cast<unsigned char>(c) < 0x20 && c != '\t' && c != '\n' && c != '\r') {
+                    throw std::invalid_argument("XMP text must not contain control characters");
+                }
+                result += c;
+        }
+    }
+    return result;
+}
+
+// Resolves the predefined entities and character references. Characters
+// are UTF-8 encoded; anything unrecognised is kept as it is.
+std::string xml_unescape(const std::string& text) {
+    std::string result;
+    result.reserve(text.size());
+    for (size_t i = 0; i < text.size(); ++i) {
+        size_t end = text[i] == '&' ? text.find(';', i) : std::string::npos;
+        if (end == std::string::npos) {
+            result += text[i];
+            continue;
+        }
+        std::string entity = text.substr(i + 1, end - i - 1);
+        unsigned long code = 0;
+        if (entity == "amp") code = '&';
+        else if (entity == "lt") code = '<';
+        else if (entity == "gt") code = '>';
+        else if (entity == "quot") code = '"';
+        else if (entity == "apos") code = '\'';
+        else if (entity.size() > 2 && entity[0] == '#' && (entity[1] == 'x' || entity[1] == 'X')) code = std::strtoul(entity.c_str() + 2, nullptr, 16);
+        else if (entity.size() > 1 && entity[0] == '#') code = std::strtoul(entity.c_str() + 1, nullptr, 10);
+        if (code == 0 || code > 0x10FFFF) {
+            result += text[i];
+            continue;
+        }
+        if (code < 0x80) {
+            result += static_cast<char>(code);
+        } else if (code < 0x800) {
+            result += static_cast<char>(0xC0 | (code >> 6));
+            result += static_cast<char>(0x80 | (code & 0x3F));
+        } else if (code < 0x10000) {
+            result += static_cast<char>(0xE0 | (code >> 12));
+            result += static_cast<char>(0x80 | ((code >> 6) & 0x3F));
+            result += static_cast<char>(0x80 | (code & 0x3F));
+        } else {
+            result += static_cast<char>(0xF0 | (code >> 18));
+            result += static_cast<char>(0x80 | ((code >> 12) & 0x3F));
+            result += static_cast<char>(0x80 | ((code >> 6) & 0x3F));
+            result += static_cast<char>(0x80 | (code & 0x3F));
+        }
+        i = end;
+    }
+    return result;
+}
+
+// The value of a simple property, written either as an attribute
+// (name="value") or as an element (<name>value</name>).
+std::optional<std::string> find_property(const std::string& xmp, const std::string& name) {
+    for (size_t pos = xmp.find(name + "="); pos != std::string::npos; pos = xmp.find(name + "=", pos + 1)) {
+        size_t quote = pos + name.size() + 1;
+        bool attribute = pos > 0 && std::isspace(static_cast<unsigned char>(xmp[pos - 1])) &&
+                         quote < xmp.size() && (xmp[quote] == '"' || xmp[quote] == '\'');
+        size_t end = attribute ? xmp.find(xmp[quote], quote + 1) : std::string::npos;
+        if (end != std::string::npos) {
+            return xml_unescape(xmp.substr(quote + 1, end - quote - 1));
+        }
+    }
+    size_t open = xmp.find("<" + name + ">");
+    if (open != std::string::npos) {
+        size_t start = open + name.size() + 2;
+        size_t end = xmp.find("</" + name + ">", start);
+        if (end != std::string::npos) {
+            return xml_unescape(xmp.substr(start, end - start));
+        }
+    }
+    return std::nullopt;
+}
+
+// The items of the rdf:Bag (or rdf:Seq) inside the element `name`.
+std::vector<std::string> find_list(const std::string& xmp, const std::string& name) {
+    std::vector<std::string> items;
+    size_t open = xmp.find("<" + name + ">");
+    size_t close = open == std::string::npos ? open : xmp.find("</" + name + ">", open);
+    if (close == std::string::npos) {
+        return items;
+    }
+    std::string list = xmp.substr(open, close - open);
+    for (size_t pos = list.find("<rdf:li"); pos != std::string::npos; pos = list.find("<rdf:li", pos + 1)) {
+        // Items may carry attributes, such as xml:lang.
+        size_t start = list.find('>', pos);
+        size_t end = start == std::string::npos ? start : list.find("</rdf:li>", start);
+        if (end == std::string::npos) {
+            break;
+        }
+        items.push_back(xml_unescape(list.substr(start + 1, end - start - 1)));
+    }
+    return items;
+}
+
+// The end of the tag that starts at `open`, skipping '>' inside quoted
+// attribute values; npos if the tag is not closed.
+size_t tag_end(const std::string& xmp, size_t open) {
+    char quote = 0;
+    for (size_t i = open; i < xmp.size(); ++i) {
+        if (quote) {
+            quote = xmp[i] == quote ? 0 : quote;
+        } else if (xmp[i] == '"' || xmp[i] == '\'') {
+            quote = xmp[i];
+        } else if (xmp[i] == '>') {
+            return i;
+        }
+    }
+    return std::string::npos;
+}
+
+// Erases [start, end) together with the whitespace in front of it, so no
+// empty lines or double spaces are left behind.
+void erase_with_indent(std::string& xmp, size_t start, size_t end) {
+    while (start > 0 && std::isspace(static_cast<unsigned char>(xmp[start - 1]))) {
+        --start;
+    }
+    xmp.erase(start, end - start);
+}
+
+// Removes every occurrence of the property `name`, written as an attribute
+// or as an element (including a list such as dc:subject).
+void remove_property(std::string& xmp, const std::string& name) {
+    for (size_t pos = xmp.find(name + "="); pos != std::string::npos; pos = xmp.find(name + "=", pos)) {
+        size_t quote = pos + name.size() + 1;
+        bool attribute = pos > 0 && std::isspace(static_cast<unsigned char>(xmp[pos - 1])) &&
+                         quote < xmp.size() && (xmp[quote] == '"' || xmp[quote] == '\'');
+        size_t end = attribute ? xmp.find(xmp[quote], quote + 1) : std::string::npos;
+        if (end == std::string::npos) {
+            ++pos;
+            continue;
+        }
+        size_t start = pos;
+        erase_with_indent(xmp, start, end + 1);
+        pos = start > 0 ? start - 1 : 0;
+    }
+    std::string open = "<" + name;
+    for (size_t pos = xmp.find(open); pos != std::string::npos; pos = xmp.find(open, pos)) {
+        size_t after = pos + open.size();
+        if (after >= xmp.size() || (xmp[after] != '>' && xmp[after] != '/' &&
+                                    !std::isspace(static_cast<unsigned char>(xmp[after])))) {
+            ++pos;
+            continue;
+        }
+        size_t end = tag_end(xmp, pos);
+        if (end == std::string::npos) {
+            break;
+        }
+        if (xmp[end - 1] != '/') {
+            std::string close = "</" + name + ">";
+            end = xmp.find(close, end);
+            if (end == std::string::npos) {
+                break;
+            }
+            end += close.size() - 1;
+        }
+        erase_with_indent(xmp, pos, end + 1);
+        pos = pos > 0 ? pos - 1 : 0;
+    }
+}
+
+// Replaces the rating, label, and keywords of an XMP packet, and leaves
+// everything else in it as it was. The new values go into the first
+// rdf:Description, along with the namespace declarations it lacks.
+void set_catalog_properties(std::string& xmp, int rating, const std::vector<std::string>& keywords,
+                            const std::string& label) {
+    remove_property(xmp, "xmp:Rating");
+    remove_property(xmp, "xmp:Label");
+    remove_property(xmp, "dc:subject");
+
+    size_t open = xmp.find("<rdf:Description");
+    size_t end = open == std::string::npos ? open : tag_end(xmp, open);
+    if (end == std::string::npos) {
+        throw std::runtime_error("XMP sidecar has no rdf:Description to update");
+    }
+    // Declarations on the element itself or on the ones enclosing it.
+    std::string in_scope = xmp.substr(0, end);
+    std::string attributes;
+    if (in_scope.find("xmlns:xmp=") == std::string::npos) {
+        attributes += "\n    xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\"";
+    }
+    if (!keywords.empty() && in_scope.find("xmlns:dc=") == std::string::npos) {
+        attributes += "\n    xmlns:dc=\"http://purl.org/dc/elements/1.1/\"";
+    }
+    attributes += "\n    xmp:Rating=\"" + std::to_string(rating) + "\"";
+    if (!label.empty()) {
+        attributes += "\n    xmp:Label=\"" + xml_escape(label) + "\"";
+    }
+
+    std::string subject;
+    if (!keywords.empty()) {
+        subject += "\n   <dc:subject>\n    <rdf:Bag>\n";
+        for (const std::string& keyword : keywords) {
+            subject += "     <rdf:li>" + xml_escape(keyword) + "</rdf:li>\n";
+        }
+        subject += "    </rdf:Bag>\n   </dc:subject>";
+    }
+
+    bool self_closing = xmp[end - 1] == '/';
+    size_t attributes_at = self_closing ? end - 1 : end;
+    if (self_closing) {
+        subject += "\n  </rdf:Description>";
+        xmp.replace(end - 1, 2, ">" + subject);
+    } else {
+        xmp.insert(end + 1, subject);
+    }
+    xmp.insert(attributes_at, attributes);
+}
+
+} // namespace
+
+std::string xmp_sidecar_path(const std::string& raw_path) {
+    // Only a dot after the last directory separator starts an extension.
+    size_t name_start = raw_path.find_last_of("/\\");
+    size_t dot = raw_path.rfind('.');
+    if (dot == std::string::npos || (name_start != std::string::npos && dot < name_start)) {
+        return raw_path + ".xmp";
+    }
+    return raw_path.substr(0, dot) + ".xmp";
+}
+
+std::string write_xmp_sidecar(const std::string& raw_path, int rating,
+                              const std::vector<std::string>& keywords,
+                              const std::string& label) {
+    if (rating < -1 || rating > 5) {
+        throw std::invalid_argument("Rating must be between -1 (rejected) and 5");
+    }
+
+    // An existing sidecar, such as one from Lightroom with its develop
+    // settings, is updated rather than replaced.
+    std::string path = xmp_sidecar_path(raw_path);
+    std::string xmp;
+    std::ifstream existing(path, std::ios::binary);
+    if (existing) {
+        xmp.assign(std::istreambuf_iterator<char>(existing), {});
+        existing.close();
+    } else {
+        xmp =
+            "<?xpacket begin=\"\xEF\xBB\xBF\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n"
+            "<x:xmpmeta xmlns:x=\"adobe:ns:meta/\" x:xmptk=\"MPR Photo Editor\">\n"
+            " <rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\n"
+            "  <rdf:Description rdf:about=\"\"\n"
+            "    xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\"\n"
+            "    xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n"
+            "  </rdf:Description>\n"
+            " </rdf:RDF>\n"
+            "</x:xmpmeta>\n"
+            "<?xpacket end=\"w\"?>\n";
+    }
+    set_catalog_properties(xmp, rating, keywords, label);
+
+    std::ofstream file(path, std::ios::binary | std::ios::trunc);
+    if (!file) {
+        throw std::runtime_error("Could not open " + path + " for writing");
+    }
+    file.write(xmp.data(), static_cast<std::streamsize>(xmp.size()));
+    file.close();
+    if (!file) {
+        throw std::runtime_error("Failed to write " + path);
+    }
+    return path;
+}
+
+XmpSidecar read_xmp_sidecar(const std::string& raw_path) {
+    XmpSidecar result;
+    std::ifstream file(xmp_sidecar_path(raw_path), std::ios::binary);
+    if (!file) {
+        return result;
+    }
+    std::string xmp(std::istreambuf_iterator<char>(file), {});
+
+    if (std::optional<std::string> rating = find_property(xmp, "xmp:Rating")) {
+        // Some writers store ratings as decimals, such as "3.0".
+        double value = std::strtod(rating->c_str(), nullptr);
+        result.rating = std::clamp(static_cast<int>(std::lround(value)), -1, 5);
+    }
+    if (std::optional<std::string> label = find_property(xmp, "xmp:Label")) {
+        result.label = *label;
+    }
+    result.keywords = find_list(xmp, "dc:subject");
+    return result;
+}
@@ -7,10 +7,119 @@ try:
 
     # Re-export the functions from the C++ backend
     get_libraw_version = cpp_backend_python_bindings.get_libraw_version
+    get_supported_extensions = cpp_backend_python_bindings.get_supported_extensions
+    get_supported_camera_count = cpp_backend_python_bindings.get_supported_camera_count
+    get_supported_cameras = cpp_backend_python_bindings.get_supported_cameras
     load_raw_image = cpp_backend_python_bindings.load_raw_image
+    load_raw_image_from_stream = cpp_backend_python_bindings.load_raw_image_from_stream
     release_raw_image = cpp_backend_python_bindings.release_raw_image
+    get_open_image_count = cpp_backend_python_bindings.get_open_image_count
+    set_max_decode_pixels = cpp_backend_python_bindings.set_max_decode_pixels
+    get_max_decode_pixels = cpp_backend_python_bindings.get_max_decode_pixels
     get_thumbnail = cpp_backend_python_bindings.get_thumbnail
+    list_thumbnails = cpp_backend_python_bindings.list_thumbnails
+    extract_thumbnail_index = cpp_backend_python_bindings.extract_thumbnail_index
     get_metadata = cpp_backend_python_bindings.get_metadata
+    decode_image = cpp_backend_python_bindings.decode_image
+    decode_stripes = cpp_backend_python_bindings.decode_stripes
+    split_tone = cpp_backend_python_bindings.split_tone
+    color_balance = cpp_backend_python_bindings.color_balance
+    adjust_vibrance = cpp_backend_python_bindings.adjust_vibrance
+    adjust_brightness = cpp_backend_python_bindings.adjust_brightness
+    adjust_brightness_inplace = cpp_backend_python_bindings.adjust_brightness_inplace
+    adjust_contrast = cpp_backend_python_bindings.adjust_contrast
+    adjust_contrast_inplace = cpp_backend_python_bindings.adjust_contrast_inplace
+    adjust_gamma = cpp_backend_python_bindings.adjust_gamma
+    adjust_gamma_inplace = cpp_backend_python_bindings.adjust_gamma_inplace
+    invert = cpp_backend_python_bindings.invert
+    invert_inplace = cpp_backend_python_bindings.invert_inplace
+    srgb_to_linear = cpp_backend_python_bindings.srgb_to_linear
+    srgb_to_linear_inplace = cpp_backend_python_bindings.srgb_to_linear_inplace
+    linear_to_srgb = cpp_backend_python_bindings.linear_to_srgb
+    linear_to_srgb_inplace = cpp_backend_python_bindings.linear_to_srgb_inplace
+    EdgeMode = cpp_backend_python_bindings.EdgeMode
+    box_blur = cpp_backend_python_bindings.box_blur
+    sharpen = cpp_backend_python_bindings.sharpen
+    sharpen_masked = cpp_backend_python_bindings.sharpen_masked
+    crop_image = cpp_backend_python_bindings.crop_image
+    resize_image = cpp_backend_python_bindings.resize_image
+    seam_carve = cpp_backend_python_bindings.seam_carve
+    straighten_angle = cpp_backend_python_bindings.straighten_angle
+    orient_image = cpp_backend_python_bindings.orient_image
+    normalize_orientation = cpp_backend_python_bindings.normalize_orientation
+    sample_bilinear = cpp_backend_python_bindings.sample_bilinear
+    sample_bicubic = cpp_backend_python_bindings.sample_bicubic
+    build_pyramid = cpp_backend_python_bindings.build_pyramid
+    blend_laplacian = cpp_backend_python_bindings.blend_laplacian
+    read_iso = cpp_backend_python_bindings.read_iso
+    read_focal_length = cpp_backend_python_bindings.read_focal_length
+    read_capture_flags = cpp_backend_python_bindings.read_capture_flags
+    read_dng_opcodes = cpp_backend_python_bindings.read_dng_opcodes
+    load_raw_bayer = cpp_backend_python_bindings.load_raw_bayer
+    decode_to_tiff_bytes = cpp_backend_python_bindings.decode_to_tiff_bytes
+    decode_linear16 = cpp_backend_python_bindings.decode_linear16
+    load_raw_f32 = cpp_backend_python_bindings.load_raw_f32
+    load_raw_wb_compare = cpp_backend_python_bindings.load_raw_wb_compare
+    make_contact_thumb = cpp_backend_python_bindings.make_contact_thumb
+    thumbnail_or_halfsize = cpp_backend_python_bindings.thumbnail_or_halfsize
+    load_best_preview = cpp_backend_python_bindings.load_best_preview
+    set_cache_size = cpp_backend_python_bindings.set_cache_size
+    cache_hits = cpp_backend_python_bindings.cache_hits
+    clear_cache = cpp_backend_python_bindings.clear_cache
+    cache_stats = cpp_backend_python_bindings.cache_stats
+    DecodeTimeout = cpp_backend_python_bindings.DecodeTimeout
+    load_raw_with_timeout = cpp_backend_python_bindings.load_raw_with_timeout
+    average_stack = cpp_backend_python_bindings.average_stack
+    StackMode = cpp_backend_python_bindings.StackMode
+    stack = cpp_backend_python_bindings.stack
+    focus_stack = cpp_backend_python_bindings.focus_stack
+    subtract_dark_frame = cpp_backend_python_bindings.subtract_dark_frame
+    flat_field_correct = cpp_backend_python_bindings.flat_field_correct
+    merge_hdr = cpp_backend_python_bindings.merge_hdr
+    ToneMapOperator = cpp_backend_python_bindings.ToneMapOperator
+    tonemap = cpp_backend_python_bindings.tonemap
+    suggest_denoise_strength = cpp_backend_python_bindings.suggest_denoise_strength
+    suggest_exposure = cpp_backend_python_bindings.suggest_exposure
+    sample_neutral_multipliers = cpp_backend_python_bindings.sample_neutral_multipliers
+    image_stats = cpp_backend_python_bindings.image_stats
+    phash = cpp_backend_python_bindings.phash
+    hash_distance = cpp_backend_python_bindings.hash_distance
+    render_histogram = cpp_backend_python_bindings.render_histogram
+    render_waveform = cpp_backend_python_bindings.render_waveform
+    render_vectorscope = cpp_backend_python_bindings.render_vectorscope
+    apply_python_filter = cpp_backend_python_bindings.apply_python_filter
+    DevelopSettings = cpp_backend_python_bindings.DevelopSettings
+    develop = cpp_backend_python_bindings.develop
+    develop_from_json = cpp_backend_python_bindings.develop_from_json
+    develop_batch = cpp_backend_python_bindings.develop_batch
+    begin_develop = cpp_backend_python_bindings.begin_develop
+    finish_develop = cpp_backend_python_bindings.finish_develop
+    develop_settings_to_json = cpp_backend_python_bindings.develop_settings_to_json
+    denoise_wavelet = cpp_backend_python_bindings.denoise_wavelet
+    denoise_ycbcr = cpp_backend_python_bindings.denoise_ycbcr
+    fix_hot_pixels = cpp_backend_python_bindings.fix_hot_pixels
+    rgb_to_ycbcr = cpp_backend_python_bindings.rgb_to_ycbcr
+    ycbcr_to_rgb = cpp_backend_python_bindings.ycbcr_to_rgb
+    rgb_to_hsl = cpp_backend_python_bindings.rgb_to_hsl
+    hsl_to_rgb = cpp_backend_python_bindings.hsl_to_rgb
+    rgb_to_hsv = cpp_backend_python_bindings.rgb_to_hsv
+    hsv_to_rgb = cpp_backend_python_bindings.hsv_to_rgb
+    rgb_to_lab = cpp_backend_python_bindings.rgb_to_lab
+    lab_to_rgb = cpp_backend_python_bindings.lab_to_rgb
+    temp_tint_to_multipliers = cpp_backend_python_bindings.temp_tint_to_multipliers
+    multipliers_to_temp_tint = cpp_backend_python_bindings.multipliers_to_temp_tint
+    preview_jpeg_base64 = cpp_backend_python_bindings.preview_jpeg_base64
+    ChromaSubsampling = cpp_backend_python_bindings.ChromaSubsampling
+    encode_jpeg = cpp_backend_python_bindings.encode_jpeg
+    encode_tiff = cpp_backend_python_bindings.encode_tiff
+    write_tiff = cpp_backend_python_bindings.write_tiff
+    srgb_icc_profile = cpp_backend_python_bindings.srgb_icc_profile
+    ExportFormat = cpp_backend_python_bindings.ExportFormat
+    export_region = cpp_backend_python_bindings.export_region
+    xmp_sidecar_path = cpp_backend_python_bindings.xmp_sidecar_path
+    write_xmp_sidecar = cpp_backend_python_bindings.write_xmp_sidecar
+    read_xmp_sidecar = cpp_backend_python_bindings.read_xmp_sidecar
+    PyImage = cpp_backend_python_bindings.PyImage
 
 except ImportError as e:
     raise ImportError(
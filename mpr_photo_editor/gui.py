@@ -332,7 +332,7 @@ class MainWindow(QWidget):
             self,
             "Open RAW Image",
             "",
-            "RAW Images (*.cr2 *.nef *.arw *.dng *.rw2 *.orf *.raf *.srw *.pef);;All Files (*)"
+            helper.raw_file_filter()
         )
         if file_path:
             self.image_container.add_image()
@@ -3,6 +3,8 @@ from PySide6.QtGui import QScreen
 from PySide6.QtWidgets import QGraphicsRectItem
 from PySide6.QtGui import QColor
 
+from mpr_photo_editor import backend
+
 
 class _DPIHelper:
     _scale = 1.0
@@ -30,6 +32,11 @@ def dp(value: float) -> float:
 def scale() -> float:
     return _DPIHelper.scale()
 
+def raw_file_filter() -> str:
+    """File dialog filter matching the RAW formats the backend can open."""
+    patterns = " ".join(f"*.{ext}" for ext in backend.get_supported_extensions())
+    return f"Raw Images ({patterns});;All Files (*)"
+
 
 
 # help debugging
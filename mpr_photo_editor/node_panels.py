@@ -7,6 +7,7 @@ from PySide6.QtCore import Qt, Signal
 from mpr_photo_editor.model import Model
 from mpr_photo_editor.controller import Controller
 from mpr_photo_editor import backend
+from mpr_photo_editor import helper
 
 
 class CollapsibleBox(QWidget):
@@ -217,7 +218,7 @@ class _ImageLoaderPanel(QWidget):
     def _on_select_file(self):
         new_filepath, _ = QFileDialog.getOpenFileName(
             None, "Select Image File", "",
-            "Raw Images (*.cr2 *.nef *.arw *.dng *.rw2 *.orf *.raf *.srw *.pef);;All Files (*)")
+            helper.raw_file_filter())
         if new_filepath:
             QApplication.setOverrideCursor(Qt.CursorShape.WaitCursor)
             try:
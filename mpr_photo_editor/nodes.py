@@ -8,7 +8,7 @@ from PySide6.QtCore import QRectF, QPointF, Qt, QEvent, Signal
 
 from mpr_photo_editor.controller import Controller
 from mpr_photo_editor.model import Model
-from mpr_photo_editor.helper import dp
+from mpr_photo_editor.helper import dp, raw_file_filter
 # from mpr_photo_editor.helper import BackgroundRectHelper
 
 
@@ -329,7 +329,7 @@ class NodeImageLoader(NodeBase):
 
         file_path, _ = QFileDialog.getOpenFileName(
             None, "Select Image File", "",
-            "Raw Images (*.cr2 *.nef *.arw *.dng *.rw2 *.orf *.raf *.srw *.pef);;All Files (*)")
+            raw_file_filter())
 
         if file_path and self.node_id:
             self.get_scene().controller.update_node_setting(self.node_id, "filepath", file_path)
@@ -0,0 +1,128 @@
+// Tests of the core library through its C++ interface alone, without Python
+// or Qt. Each test returns normally on success; CHECK records failures and
+// the process exits non-zero if there were any.
+
+#include "mpr_photo_editor/color.h"
+#include "mpr_photo_editor/image_io.h"
+#include "mpr_photo_editor/image_manager.h"
+#include "mpr_photo_editor/image_ops.h"
+#include <cmath>
+#include <cstdint>
+#include <cstdlib>
+#include <iostream>
+#include <stdexcept>
+#include <string>
+#include <vector>
+
+namespace {
+
+int failures = 0;
+
+#define CHECK(condition)                                                                  \
+    do {                                                                                  \
+        if (!(condition)) {                                                               \
+            std::cerr << __FILE__ << ":" << __LINE__ << ": CHECK failed: " #condition "\n"; \
+            ++failures;                                                                   \
+        }                                                                                 \
+    } while (false)
+
+template <typename Exception, typename Work>
+bool throws(Work work) {
+    try {
+        work();
+    } catch (const Exception&) {
+        return true;
+    }
+    return false;
+}
+
+// An RGB image whose pixels all differ.
+std::vector<uint8_t> gradient(int width, int height) {
+    std::vector<uint8_t> image;
+    for (int i = 0; i < width * height; ++i) {
+        image.push_back(static_cast<uint8_t>(i * 30));
+        image.push_back(static_cast<uint8_t>(255 - i * 30));
+        image.push_back(static_cast<uint8_t>(i * 10));
+    }
+    return image;
+}
+
+void test_orient_image() {
+    std::vector<uint8_t> image = gradient(4, 2);
+    ImageBuffer rotated = orient_image(image, 4, 2, 3, 6);
+    CHECK(rotated.width == 2 && rotated.height == 4);
+
+    ImageBuffer half_turn = orient_image(image, 4, 2, 3, 3);
+    ImageBuffer back = orient_image(half_turn.data, half_turn.width, half_turn.height, 3, 3);
+    CHECK(back.data == image);
+    CHECK(throws<std::invalid_argument>([&] { orient_image(image, 4, 2, 3, 8); }));
+}
+
+void test_resize_keeps_flat_images_flat() {
+    std::vector<uint8_t> image(16 * 8 * 3, 77);
+    std::vector<uint8_t> resized = resize_image(image, 16, 8, 3, 5, 3);
+    CHECK(resized == std::vector<uint8_t>(5 * 3 * 3, 77));
+}
+
+void test_jpeg_round_trip() {
+    std::vector<uint8_t> image(32 * 16 * 3, 128);
+    ImageBuffer decoded = decode_jpeg(encode_jpeg(image, 32, 16, 3, 90));
+    CHECK(decoded.width == 32 && decoded.height == 16 && decoded.channels == 3);
+    CHECK(decoded.data.size() == image.size());
+    CHECK(throws<std::invalid_argument>([&] { encode_jpeg(image, 32, 16, 2, 90); }));
+}
+
+void test_tiff_header() {
+    std::vector<uint8_t> tiff = encode_tiff(gradient(4, 2), 4, 2, 3, 8);
+    CHECK(tiff.size() > 4 * 2 * 3);
+    CHECK((tiff[0] == 'I' && tiff[1] == 'I') || (tiff[0] == 'M' && tiff[1] == 'M'));
+}
+
+void test_lab_round_trip() {
+    Color rgb = {0.8f, 0.3f, 0.1f};
+    Color back = lab_to_rgb(rgb_to_lab(rgb));
+    for (int c = 0; c < 3; ++c) {
+        CHECK(std::fabs(back[c] - rgb[c]) < 1e-3f);
+    }
+}
+
+void test_image_manager() {
+    ImageManager& manager = ImageManager::instance();
+    CHECK(!manager.get_libraw_version().empty());
+    CHECK(manager.get_supported_camera_count() > 0);
+    CHECK(throws<std::runtime_error>([&] { manager.load_raw_image("does-not-exist.nef"); }));
+    CHECK(throws<std::runtime_error>([&] { manager.get_metadata(0); }));
+
+    // Like the Python tests, decoding real sensor data needs a sample file.
+    const char* sample = std::getenv("MPR_SAMPLE_RAW");
+    if (!sample || !*sample) {
+        std::cout << "MPR_SAMPLE_RAW is not set; skipping the decode test\n";
+        return;
+    }
+    uint64_t id = manager.load_raw_image(sample);
+    DecodeOptions options;
+    options.half_size = true;
+    DecodedImage decoded = manager.decode_image(id, options);
+    manager.release_raw_image(id);
+    CHECK(decoded.bits == 8 && decoded.image.channels == 3);
+    CHECK(decoded.image.data.size() ==
+          static_cast<size_t>(decoded.image.width) * decoded.image.height * decoded.image.channels);
+}
+
+} // namespace
+
+int main() {
+    test_orient_image();
+    test_resize_keeps_flat_images_flat();
+    test_jpeg_round_trip();
+    test_tiff_header();
+    test_lab_round_trip();
+    test_image_manager();
+
+    if (failures > 0) {
+        std::cerr << failures << " check(s) failed\n";
+        return EXIT_FAILURE;
+    }
+    std::cout << "All core tests passed\n";
+    return EXIT_SUCCESS;
+}
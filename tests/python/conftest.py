@@ -0,0 +1,52 @@
+import os
+
+import pytest
+
+
+@pytest.fixture
+def sample_raw() -> str:
+    """
+    Path to a sample RAW file for tests that need real sensor data. The file
+    is not part of the repository; point MPR_SAMPLE_RAW at one to run them.
+    """
+    path = os.environ.get("MPR_SAMPLE_RAW", "")
+    if not os.path.isfile(path):
+        pytest.skip("MPR_SAMPLE_RAW is not set to an existing RAW file")
+    return path
+
+
+@pytest.fixture
+def sample_portrait_raw() -> str:
+    """
+    Path to a RAW file shot in portrait orientation, with an embedded JPEG
+    preview. Point MPR_SAMPLE_PORTRAIT_RAW at one to run the tests using it.
+    """
+    path = os.environ.get("MPR_SAMPLE_PORTRAIT_RAW", "")
+    if not os.path.isfile(path):
+        pytest.skip("MPR_SAMPLE_PORTRAIT_RAW is not set to an existing RAW file")
+    return path
+
+
+@pytest.fixture
+def sample_dng_gain_map() -> str:
+    """
+    Path to a DNG file whose OpcodeList2 holds a vignetting gain map, which
+    brightens the corners. Point MPR_SAMPLE_DNG_GAIN_MAP at one to run the
+    tests using it.
+    """
+    path = os.environ.get("MPR_SAMPLE_DNG_GAIN_MAP", "")
+    if not os.path.isfile(path):
+        pytest.skip("MPR_SAMPLE_DNG_GAIN_MAP is not set to an existing DNG file")
+    return path
+
+
+@pytest.fixture
+def sample_lossless_raw() -> str:
+    """
+    Path to a RAW file stored uncompressed or with lossless compression. Point
+    MPR_SAMPLE_LOSSLESS_RAW at one to run the tests using it.
+    """
+    path = os.environ.get("MPR_SAMPLE_LOSSLESS_RAW", "")
+    if not os.path.isfile(path):
+        pytest.skip("MPR_SAMPLE_LOSSLESS_RAW is not set to an existing RAW file")
+    return path
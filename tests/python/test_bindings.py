@@ -1,3 +1,14 @@
+import array
+import io
+import os
+import shutil
+import statistics
+import struct
+import threading
+import time
+
+import pytest
+
 from mpr_photo_editor import backend
 
 
@@ -9,4 +20,956 @@ def test_get_libraw_version():
     version = backend.get_libraw_version()
     assert isinstance(version, str)
     assert len(version) > 0
-    assert "." in version
\ No newline at end of file
+    assert "." in version
+
+
+def test_decode_image_reports_diagnostics(sample_raw):
+    """
+    Tests that decoding with diagnostics enabled reports a positive decode
+    time and the size of the returned buffer.
+    """
+    image_id = backend.load_raw_image(sample_raw)
+    try:
+        result = backend.decode_image(image_id, diagnostics=True)
+    finally:
+        backend.release_raw_image(image_id)
+
+    stats = result["diagnostics"]
+    assert stats["process_ms"] > 0
+    assert stats["buffer_size"] == len(result["data"])
+
+
+def test_failed_load_does_not_leak_a_handle(tmp_path):
+    """
+    Tests that a raw image that cannot be opened raises an error without
+    leaving a handle behind.
+    """
+    before = backend.get_open_image_count()
+    for _ in range(3):
+        with pytest.raises(RuntimeError):
+            backend.load_raw_image(str(tmp_path / "missing.nef"))
+    assert backend.get_open_image_count() == before
+
+
+def test_released_handles_are_freed(sample_raw):
+    """
+    Tests that repeatedly loading and releasing a raw image returns the
+    handle count to where it started.
+    """
+    before = backend.get_open_image_count()
+    for _ in range(3):
+        image_id = backend.load_raw_image(sample_raw)
+        assert backend.get_open_image_count() == before + 1
+        backend.release_raw_image(image_id)
+    assert backend.get_open_image_count() == before
+
+
+def test_files_over_the_pixel_limit_are_rejected(sample_raw):
+    """
+    Tests that with a tiny pixel limit a raw file fails to load, and to
+    decode through the file-based functions, without leaving a handle
+    behind, and that removing the limit makes it load again.
+    """
+    before = backend.get_open_image_count()
+    backend.set_max_decode_pixels(100)
+    try:
+        assert backend.get_max_decode_pixels() == 100
+        with pytest.raises(RuntimeError, match="limit of 100 pixels"):
+            backend.load_raw_image(sample_raw)
+        with pytest.raises(RuntimeError, match="limit"):
+            backend.load_raw_f32(sample_raw, half_size=True)
+        assert backend.get_open_image_count() == before
+    finally:
+        backend.set_max_decode_pixels(0)
+
+    image_id = backend.load_raw_image(sample_raw)
+    backend.release_raw_image(image_id)
+
+
+def test_load_from_a_stream_matches_load_from_the_file(sample_raw):
+    """
+    Tests that a raw file read from a BytesIO decodes to the same image as
+    the file on disk, and that streams yielding text are rejected.
+    """
+    with open(sample_raw, "rb") as f:
+        stream = io.BytesIO(f.read())
+    from_stream = backend.load_raw_image_from_stream(stream)
+    from_file = backend.load_raw_image(sample_raw)
+    try:
+        assert backend.decode_image(from_stream, half_size=True) == \
+            backend.decode_image(from_file, half_size=True)
+    finally:
+        backend.release_raw_image(from_stream)
+        backend.release_raw_image(from_file)
+
+    with pytest.raises(TypeError):
+        backend.load_raw_image_from_stream(io.StringIO("not raw"))
+    with pytest.raises(RuntimeError):
+        backend.load_raw_image_from_stream(io.BytesIO(b"not a raw file"))
+
+
+def test_read_iso(sample_raw):
+    """
+    Tests that the ISO speed can be read straight from a raw file.
+    """
+    assert backend.read_iso(sample_raw) > 0
+
+
+def test_read_focal_length_matches_metadata(sample_raw):
+    """
+    Tests that the focal lengths read straight from a raw file are positive
+    or None, and agree with the metadata of the loaded image.
+    """
+    focal = backend.read_focal_length(sample_raw)
+    image_id = backend.load_raw_image(sample_raw)
+    try:
+        metadata = backend.get_metadata(image_id)
+    finally:
+        backend.release_raw_image(image_id)
+
+    for key in ("focal_length", "focal_length_35mm"):
+        assert focal[key] is None or focal[key] > 0
+        assert metadata[key] == (focal[key] or 0.0)
+
+
+def test_read_capture_flags(sample_raw):
+    """
+    Tests that the capture flags of a raw file hold a boolean flash flag and
+    a known metering mode name, matching the metadata of the loaded image.
+    """
+    flags = backend.read_capture_flags(sample_raw)
+    assert isinstance(flags["flash_fired"], bool)
+    assert flags["metering_mode"] in {
+        "unknown", "average", "center_weighted", "spot", "multi_spot", "pattern", "partial", "other",
+    }
+
+    image_id = backend.load_raw_image(sample_raw)
+    try:
+        metadata = backend.get_metadata(image_id)
+    finally:
+        backend.release_raw_image(image_id)
+    assert (metadata["flash_fired"], metadata["metering_mode"]) == (flags["flash_fired"], flags["metering_mode"])
+
+
+def test_lossless_raw_is_not_reported_as_lossy(sample_lossless_raw, sample_raw):
+    """
+    Tests that the metadata of a losslessly stored raw file reports no lossy
+    compression, and that the flag is a boolean for any file.
+    """
+    for path, expected in ((sample_lossless_raw, False), (sample_raw, None)):
+        image_id = backend.load_raw_image(path)
+        try:
+            lossy = backend.get_metadata(image_id)["lossy_compression"]
+        finally:
+            backend.release_raw_image(image_id)
+        assert isinstance(lossy, bool)
+        assert expected is None or lossy == expected
+
+
+def test_decode_image_reports_progress(sample_raw):
+    """
+    Tests that a progress callback passed to decode_image is invoked with a
+    stage name and a fraction in [0, 1].
+    """
+    calls = []
+    image_id = backend.load_raw_image(sample_raw)
+    try:
+        backend.decode_image(image_id, progress=lambda stage, fraction: calls.append((stage, fraction)))
+    finally:
+        backend.release_raw_image(image_id)
+
+    assert len(calls) > 0
+    assert all(isinstance(stage, str) and 0.0 <= fraction <= 1.0 for stage, fraction in calls)
+
+
+def test_decode_brightness_scales_output(sample_raw):
+    """
+    Tests that raising the decode brightness raises the mean output level,
+    and that a gamma curve with the wrong number of elements is rejected.
+    """
+    image_id = backend.load_raw_image(sample_raw)
+    try:
+        normal = backend.decode_image(image_id)
+        bright = backend.decode_image(image_id, bright=2.0)
+        with pytest.raises(TypeError):
+            backend.decode_image(image_id, gamma=(0.45, 4.5, 1.0))
+    finally:
+        backend.release_raw_image(image_id)
+
+    assert statistics.fmean(bright["data"]) > 1.2 * statistics.fmean(normal["data"])
+
+
+def test_use_camera_matrix_changes_colors(sample_raw):
+    """
+    Tests that with camera white balance on, turning off the camera's color
+    matrix changes the colors of the decode, while the default decode is
+    the one with LibRaw's default flags.
+    """
+    image_id = backend.load_raw_image(sample_raw)
+    try:
+        default = backend.decode_image(image_id, half_size=True)
+        explicit = backend.decode_image(image_id, half_size=True, use_camera_wb=False, use_camera_matrix=True)
+        camera = backend.decode_image(image_id, half_size=True, use_camera_wb=True)
+        no_matrix = backend.decode_image(image_id, half_size=True, use_camera_wb=True, use_camera_matrix=False)
+    finally:
+        backend.release_raw_image(image_id)
+
+    assert default["data"] == explicit["data"]
+    assert no_matrix["data"] != camera["data"]
+
+
+def test_user_flip_overrides_the_camera_orientation(sample_raw):
+    """
+    Tests that forcing a quarter turn swaps the dimensions of an unrotated
+    decode, a half turn keeps them, and flip codes out of range are refused.
+    """
+    image_id = backend.load_raw_image(sample_raw)
+    try:
+        upright = backend.decode_image(image_id, half_size=True, user_flip=0)
+        turned = backend.decode_image(image_id, half_size=True, user_flip=5)
+        reversed_ = backend.decode_image(image_id, half_size=True, user_flip=3)
+        with pytest.raises(ValueError):
+            backend.decode_image(image_id, half_size=True, user_flip=8)
+    finally:
+        backend.release_raw_image(image_id)
+
+    assert (turned["width"], turned["height"]) == (upright["height"], upright["width"])
+    assert (reversed_["width"], reversed_["height"]) == (upright["width"], upright["height"])
+    assert reversed_["data"] != upright["data"]
+
+
+def test_default_decode_reports_8_bit_rgb(sample_raw):
+    """
+    Tests that a default decode reports the format LibRaw produced: 8-bit
+    sRGB with three channels, and a buffer of matching size.
+    """
+    image_id = backend.load_raw_image(sample_raw)
+    try:
+        result = backend.decode_image(image_id, half_size=True)
+    finally:
+        backend.release_raw_image(image_id)
+
+    assert result["bits"] == 8
+    assert result["channels"] == 3
+    assert len(result["data"]) == result["width"] * result["height"] * 3
+
+
+@pytest.mark.parametrize("bits", [8, 16])
+def test_planar_decode_reinterleaves_to_interleaved_decode(sample_raw, bits):
+    """
+    Tests that a planar decode holds the same samples as an interleaved one,
+    one channel plane after another, for 8- and 16-bit output.
+    """
+    image_id = backend.load_raw_image(sample_raw)
+    try:
+        interleaved = backend.decode_image(image_id, half_size=True, bits=bits)
+        planar = backend.decode_image(image_id, half_size=True, bits=bits, planar=True)
+    finally:
+        backend.release_raw_image(image_id)
+
+    assert interleaved["layout"] == "interleaved"
+    assert planar["layout"] == "planar"
+    assert len(planar["data"]) == len(interleaved["data"])
+
+    sample = bits // 8
+    channels = planar["channels"]
+    plane = planar["width"] * planar["height"] * sample
+    planes = [planar["data"][c * plane:(c + 1) * plane] for c in range(channels)]
+    reinterleaved = bytearray(len(planar["data"]))
+    for c in range(channels):
+        for s in range(sample):
+            reinterleaved[c * sample + s::channels * sample] = planes[c][s::sample]
+    assert bytes(reinterleaved) == interleaved["data"]
+
+
+@pytest.mark.parametrize("bits", [8, 16])
+def test_striped_decode_concatenates_to_full_decode(sample_raw, bits):
+    """
+    Tests that the stripes of a striped decode arrive top to bottom, each
+    holding exactly its rows, and together hold exactly the buffer of a
+    regular decode at both bit depths.
+    """
+    stripes = []
+    image_id = backend.load_raw_image(sample_raw)
+    try:
+        full = backend.decode_image(image_id, half_size=True, bits=bits)
+        result = backend.decode_stripes(
+            image_id, lambda y, stripe, rows: stripes.append((y, stripe, rows)), stripe_height=100, half_size=True,
+            bits=bits
+        )
+    finally:
+        backend.release_raw_image(image_id)
+
+    assert "data" not in result
+    assert (result["width"], result["height"], result["bits"]) == (full["width"], full["height"], bits)
+    row_size = full["width"] * full["channels"] * bits // 8
+    assert [y for y, _, _ in stripes] == list(range(0, full["height"], 100))
+    assert [rows for _, _, rows in stripes] == [min(100, full["height"] - y) for y, _, _ in stripes]
+    assert all(len(stripe) == rows * row_size for _, stripe, rows in stripes)
+    assert b"".join(stripe for _, stripe, _ in stripes) == full["data"]
+
+
+def test_decode_16_bit_reports_maximum(sample_raw):
+    """
+    Tests that a 16-bit decode returns two bytes per sample together with a
+    white level that fits the 16-bit range.
+    """
+    image_id = backend.load_raw_image(sample_raw)
+    try:
+        result = backend.decode_image(image_id, bits=16)
+    finally:
+        backend.release_raw_image(image_id)
+
+    assert result["bits"] == 16
+    assert len(result["data"]) == result["width"] * result["height"] * result["channels"] * 2
+    assert 0 < result["maximum"] <= 65535
+    assert 0 <= result["data_maximum"] <= 65535
+
+
+def test_decoded_buffer_is_complete_and_stable(sample_raw):
+    """
+    Tests that the buffer copied out of LibRaw has the expected size and is
+    identical across two decodes of the same image.
+    """
+    image_id = backend.load_raw_image(sample_raw)
+    try:
+        first = backend.decode_image(image_id, diagnostics=True)
+        second = backend.decode_image(image_id)
+    finally:
+        backend.release_raw_image(image_id)
+
+    expected_size = first["width"] * first["height"] * first["channels"]
+    assert len(first["data"]) == expected_size
+    assert first["diagnostics"]["buffer_size"] == expected_size
+    assert first["data"] == second["data"]
+
+
+def test_four_color_decode_reports_its_channels(sample_raw):
+    """
+    Tests that a four-color decode either comes back with a buffer matching
+    its reported channel count or fails with a message naming the format.
+    """
+    image_id = backend.load_raw_image(sample_raw)
+    try:
+        result = backend.decode_image(image_id, half_size=True, four_color_rgb=True)
+    except RuntimeError as e:
+        assert "channel" in str(e)
+        return
+    finally:
+        backend.release_raw_image(image_id)
+
+    assert result["channels"] in (1, 3, 4)
+    assert len(result["data"]) == result["width"] * result["height"] * result["channels"]
+
+
+def test_get_supported_extensions():
+    """
+    Tests that the backend reports a non-empty list of RAW extensions that
+    includes the common formats, and a LibRaw camera count.
+    """
+    extensions = backend.get_supported_extensions()
+    assert extensions
+    for ext in ("cr2", "nef", "arw", "dng"):
+        assert ext in extensions
+    assert all(ext == ext.lower() and not ext.startswith(".") for ext in extensions)
+    assert backend.get_supported_camera_count() > 0
+
+
+def test_get_supported_cameras_matches_the_count():
+    """
+    Tests that the camera list has one non-empty name per supported model.
+    """
+    cameras = backend.get_supported_cameras()
+    assert len(cameras) == backend.get_supported_camera_count()
+    assert all(cameras)
+
+
+def _jpeg_size(data: bytes) -> tuple[int, int]:
+    """Reads (width, height) from the first SOF marker of a JPEG file."""
+    pos = 2
+    while pos < len(data):
+        marker, length = data[pos + 1], int.from_bytes(data[pos + 2:pos + 4], "big")
+        if 0xC0 <= marker <= 0xCF and marker not in (0xC4, 0xC8, 0xCC):
+            height = int.from_bytes(data[pos + 5:pos + 7], "big")
+            width = int.from_bytes(data[pos + 7:pos + 9], "big")
+            return width, height
+        pos += 2 + length
+    raise ValueError("No SOF marker found")
+
+
+def test_portrait_thumbnail_is_oriented(sample_portrait_raw):
+    """
+    Tests that the thumbnail of a portrait shot comes back with swapped
+    dimensions when oriented, and untouched when orientation is disabled.
+    """
+    image_id = backend.load_raw_image(sample_portrait_raw)
+    try:
+        raw_thumb = backend.get_thumbnail(image_id, apply_orientation=False)
+        oriented_thumb = backend.get_thumbnail(image_id)
+    finally:
+        backend.release_raw_image(image_id)
+
+    raw_width, raw_height = _jpeg_size(raw_thumb)
+    assert raw_width > raw_height
+    assert _jpeg_size(oriented_thumb) == (raw_height, raw_width)
+
+
+def test_list_thumbnails_describes_every_preview(sample_raw):
+    """
+    Tests that a raw file lists at least one embedded preview, that every
+    listed preview can be extracted, and that an index past the end is
+    rejected.
+    """
+    thumbnails = backend.list_thumbnails(sample_raw)
+    assert len(thumbnails) >= 1
+    for index, info in enumerate(thumbnails):
+        assert info["size"] > 0
+        data = backend.extract_thumbnail_index(sample_raw, index, apply_orientation=False)
+        assert len(data) > 0
+        if info["format"] == "jpeg":
+            assert data[:2] == b"\xff\xd8"
+
+    with pytest.raises(IndexError):
+        backend.extract_thumbnail_index(sample_raw, len(thumbnails))
+
+
+def test_metadata_dimensions_match_the_upright_decode(sample_portrait_raw):
+    """
+    Tests that the dimensions reported for a portrait shot are those of the
+    rotated pixel buffer a decode returns, not the sensor's.
+    """
+    image_id = backend.load_raw_image(sample_portrait_raw)
+    try:
+        metadata = backend.get_metadata(image_id)
+        decoded = backend.decode_image(image_id)
+    finally:
+        backend.release_raw_image(image_id)
+
+    assert (metadata["width"], metadata["height"]) == (decoded["width"], decoded["height"])
+    assert metadata["height"] > metadata["width"]
+    assert len(decoded["data"]) == decoded["width"] * decoded["height"] * decoded["channels"]
+
+
+def test_make_contact_thumb_fits_max_dim(sample_raw):
+    """
+    Tests that the contact sheet thumbnail is a JPEG whose longer side is
+    at most max_dim.
+    """
+    thumb = backend.make_contact_thumb(sample_raw, 256, 80)
+    assert thumb[:2] == b"\xff\xd8"
+    width, height = _jpeg_size(thumb)
+    assert max(width, height) <= 256
+    assert min(width, height) > 0
+
+
+def test_thumbnail_or_halfsize_falls_back_to_a_decode(sample_raw):
+    """
+    Tests that a large enough embedded preview is returned as is, and that
+    one smaller than min_dim is replaced by a half-size decode.
+    """
+    image_id = backend.load_raw_image(sample_raw)
+    try:
+        embedded = _jpeg_size(backend.get_thumbnail(image_id))
+        half = backend.decode_image(image_id, half_size=True)
+    finally:
+        backend.release_raw_image(image_id)
+
+    assert _jpeg_size(backend.thumbnail_or_halfsize(sample_raw, max(embedded))) == embedded
+    too_small = backend.thumbnail_or_halfsize(sample_raw, max(embedded) + 1)
+    assert _jpeg_size(too_small) == (half["width"], half["height"])
+    with pytest.raises(ValueError):
+        backend.thumbnail_or_halfsize(sample_raw, 0)
+
+
+def test_load_best_preview_picks_the_fastest_sufficient_source(sample_raw):
+    """
+    Tests that a small target is served from the embedded preview and a
+    target larger than a half-size decode needs a full decode.
+    """
+    image_id = backend.load_raw_image(sample_raw)
+    try:
+        metadata = backend.get_metadata(image_id)
+    finally:
+        backend.release_raw_image(image_id)
+    full_dim = max(metadata["width"], metadata["height"])
+
+    small = backend.load_best_preview(sample_raw, 16)
+    assert small["source"] == "thumbnail"
+    assert _jpeg_size(small["data"]) == (small["width"], small["height"])
+
+    large = backend.load_best_preview(sample_raw, full_dim)
+    assert large["source"] == "full"
+    assert max(large["width"], large["height"]) >= full_dim
+    assert _jpeg_size(large["data"]) == (large["width"], large["height"])
+
+    with pytest.raises(ValueError):
+        backend.load_best_preview(sample_raw, 0)
+
+
+def test_preview_cache_serves_repeated_requests(sample_raw, tmp_path):
+    """
+    Tests that asking twice for the same preview is served from the cache,
+    and that a file with a new modification time is decoded again.
+    """
+    path = tmp_path / ("copy" + os.path.splitext(sample_raw)[1])
+    shutil.copy(sample_raw, path)
+    try:
+        first = backend.load_best_preview(str(path), 16)
+        hits = backend.cache_hits()
+        second = backend.load_best_preview(str(path), 16)
+        assert backend.cache_hits() == hits + 1
+        assert second == first
+
+        stat = os.stat(path)
+        os.utime(path, ns=(stat.st_atime_ns, stat.st_mtime_ns + 1_000_000_000))
+        backend.load_best_preview(str(path), 16)
+        assert backend.cache_hits() == hits + 1
+
+        backend.set_cache_size(0)
+        backend.load_best_preview(str(path), 16)
+        assert backend.cache_hits() == hits + 1
+    finally:
+        backend.set_cache_size(256)
+
+    with pytest.raises(ValueError):
+        backend.set_cache_size(-1)
+
+
+def test_set_cache_size_rejects_non_finite_sizes():
+    """
+    Tests that infinite and NaN cache sizes are rejected, while sizes beyond
+    the address space are accepted as no limit.
+    """
+    try:
+        for size in (float("inf"), float("nan")):
+            with pytest.raises(ValueError):
+                backend.set_cache_size(size)
+        backend.set_cache_size(2.0**80)
+    finally:
+        backend.set_cache_size(256)
+
+
+def test_clear_cache_empties_the_preview_cache(sample_raw):
+    """
+    Tests that cache_stats counts a cached preview and its bytes, and that
+    clear_cache resets the entry count and size to zero but keeps the counts
+    of hits and misses.
+    """
+    backend.load_best_preview(sample_raw, 16)
+    stats = backend.cache_stats()
+    assert stats["entries"] >= 1
+    assert stats["bytes"] > 0
+    assert stats["misses"] >= 1
+
+    backend.clear_cache()
+    cleared = backend.cache_stats()
+    assert (cleared["entries"], cleared["bytes"]) == (0, 0)
+    assert (cleared["hits"], cleared["misses"]) == (stats["hits"], stats["misses"])
+
+
+def test_load_raw_with_timeout_aborts_slow_decodes(sample_raw):
+    """
+    Tests that a full decode with a tiny time limit is cancelled with a
+    TimeoutError and leaves no open image behind, while a generous limit
+    returns the decoded image.
+    """
+    open_before = backend.get_open_image_count()
+    with pytest.raises(TimeoutError, match="took longer than 1 ms"):
+        backend.load_raw_with_timeout(sample_raw, 1)
+    assert backend.get_open_image_count() == open_before
+
+    result = backend.load_raw_with_timeout(sample_raw, 600_000, half_size=True)
+    assert len(result["data"]) == result["width"] * result["height"] * result["channels"]
+
+    with pytest.raises(ValueError):
+        backend.load_raw_with_timeout(sample_raw, 0)
+
+
+def test_develop_batch_returns_one_jpeg_per_file(sample_raw, tmp_path):
+    """
+    Tests that a batch develop returns a JPEG per input file, in order, and
+    that a file that cannot be opened fails the batch with its name.
+    """
+    jpegs = backend.develop_batch([sample_raw, sample_raw], {"exposure": 0.5, "contrast": 0.2})
+    assert len(jpegs) == 2
+    assert all(jpeg[:2] == b"\xff\xd8" for jpeg in jpegs)
+    assert jpegs[0] == jpegs[1]
+
+    missing = str(tmp_path / "missing.nef")
+    with pytest.raises(RuntimeError, match="missing.nef"):
+        backend.develop_batch([sample_raw, missing], backend.DevelopSettings())
+
+
+def test_two_phase_develop_preview_matches_the_final_look(sample_raw):
+    """
+    Tests that the half-size preview of a develop session looks like the
+    full-resolution result scaled down to its size, and that finishing ends
+    the session.
+    """
+    open_before = backend.get_open_image_count()
+    started = backend.begin_develop(sample_raw, {"exposure": 0.5, "saturation": 0.3})
+    assert backend.get_open_image_count() == open_before + 1
+
+    final = backend.finish_develop(started["session"])
+    assert backend.get_open_image_count() == open_before
+    assert abs(final["width"] - 2 * started["width"]) <= 2
+    assert abs(final["height"] - 2 * started["height"]) <= 2
+
+    scaled = backend.resize_image(final["data"], final["width"], final["height"], 3,
+                                  started["width"], started["height"])
+    differences = [abs(a - b) for a, b in zip(scaled, started["data"])]
+    assert statistics.mean(differences) < 8
+
+    with pytest.raises(RuntimeError):
+        backend.finish_develop(started["session"])
+    image_id = backend.load_raw_image(sample_raw)
+    try:
+        with pytest.raises(RuntimeError, match="not a develop session"):
+            backend.finish_develop(image_id)
+    finally:
+        backend.release_raw_image(image_id)
+
+
+def test_decodes_from_two_threads_overlap(sample_raw):
+    """
+    Tests that decode_image releases the GIL and does not serialise different
+    images: while one thread decodes, the other gets to start its own decode.
+    """
+    image_ids = [backend.load_raw_image(sample_raw) for _ in range(2)]
+    spans = {}
+
+    def decode(image_id):
+        times = []
+        backend.decode_image(image_id, progress=lambda stage, fraction: times.append(time.perf_counter()))
+        spans[image_id] = (times[0], times[-1])
+
+    try:
+        threads = [threading.Thread(target=decode, args=(image_id,)) for image_id in image_ids]
+        for thread in threads:
+            thread.start()
+        for thread in threads:
+            thread.join()
+    finally:
+        for image_id in image_ids:
+            backend.release_raw_image(image_id)
+
+    (first_start, first_end), (second_start, second_end) = spans.values()
+    assert first_start < second_end and second_start < first_end
+
+
+def test_concurrent_loads_and_decodes_agree(sample_raw):
+    """
+    Stress test: many threads loading, decoding, and releasing the same file
+    at once all succeed and all produce identical pixels.
+    """
+    results = []
+    errors = []
+
+    def load_and_decode():
+        try:
+            image_id = backend.load_raw_image(sample_raw)
+            try:
+                results.append(backend.decode_image(image_id, half_size=True)["data"])
+                backend.read_iso(sample_raw)
+            finally:
+                backend.release_raw_image(image_id)
+        except Exception as e:
+            errors.append(e)
+
+    threads = [threading.Thread(target=load_and_decode) for _ in range(16)]
+    for thread in threads:
+        thread.start()
+    for thread in threads:
+        thread.join()
+
+    assert errors == []
+    assert len(results) == 16
+    assert all(data == results[0] for data in results)
+
+
+def test_load_raw_bayer_matches_sensor_dimensions(sample_raw):
+    """
+    Tests that the Bayer data holds one 16-bit sample per photosite of the
+    full sensor, with the visible area inside it and a 2x2 CFA pattern.
+    """
+    bayer = backend.load_raw_bayer(sample_raw)
+
+    assert len(bayer["data"]) == bayer["raw_width"] * bayer["raw_height"] * 2
+    assert bayer["left_margin"] + bayer["width"] <= bayer["raw_width"]
+    assert bayer["top_margin"] + bayer["height"] <= bayer["raw_height"]
+    assert len(bayer["cfa_pattern"]) == 4
+    assert set(bayer["cfa_pattern"]) <= set("RGBE")
+
+
+def test_dcb_iterations_change_dcb_output(sample_raw):
+    """
+    Tests that the number of DCB refinement passes affects the result when
+    DCB demosaicing is selected, and that invalid settings are rejected.
+    """
+    image_id = backend.load_raw_image(sample_raw)
+    try:
+        single = backend.decode_image(image_id, quality=4, dcb_iterations=1)
+        several = backend.decode_image(image_id, quality=4, dcb_iterations=4, dcb_enhance=True)
+        with pytest.raises(ValueError):
+            backend.decode_image(image_id, quality=7)
+        with pytest.raises(ValueError):
+            backend.decode_image(image_id, dcb_iterations=-2)
+    finally:
+        backend.release_raw_image(image_id)
+
+    assert len(single["data"]) == len(several["data"])
+    assert single["data"] != several["data"]
+
+
+def test_median_passes_change_output(sample_raw):
+    """
+    Tests that median filter passes after demosaicing change the decoded
+    pixels, and that a negative pass count is rejected.
+    """
+    image_id = backend.load_raw_image(sample_raw)
+    try:
+        plain = backend.decode_image(image_id)
+        filtered = backend.decode_image(image_id, med_passes=3)
+        with pytest.raises(ValueError):
+            backend.decode_image(image_id, med_passes=-1)
+    finally:
+        backend.release_raw_image(image_id)
+
+    assert len(plain["data"]) == len(filtered["data"])
+    assert plain["data"] != filtered["data"]
+
+
+@pytest.mark.parametrize("fraction", [0.001, 0.3, 0.9])
+def test_truncated_files_fail_cleanly(sample_raw, tmp_path, fraction):
+    """
+    Tests that a partially downloaded raw file either still yields a
+    complete buffer or fails with a RuntimeError naming the failed stage,
+    and never brings down the process. After a failed decode the handle
+    either still reports its metadata or refuses with a RuntimeError,
+    rather than returning the zeroed fields of a recycled processor.
+    """
+    with open(sample_raw, "rb") as f:
+        data = f.read()
+    truncated = tmp_path / ("truncated" + os.path.splitext(sample_raw)[1])
+    truncated.write_bytes(data[:int(len(data) * fraction)])
+
+    try:
+        image_id = backend.load_raw_image(str(truncated))
+    except RuntimeError as e:
+        assert "Failed to" in str(e)
+        return
+    try:
+        result = backend.decode_image(image_id, half_size=True)
+    except RuntimeError as e:
+        assert "Failed to" in str(e)
+        try:
+            meta = backend.get_metadata(image_id)
+        except RuntimeError as e:
+            assert "fatal LibRaw error" in str(e)
+        else:
+            assert meta["make"] and meta["width"] > 0 and meta["height"] > 0
+        return
+    finally:
+        backend.release_raw_image(image_id)
+    assert len(result["data"]) == result["width"] * result["height"] * result["channels"]
+
+
+def test_auto_bright_threshold_changes_the_stretch(sample_raw):
+    """
+    Tests that letting auto-brightness clip more pixels brightens the
+    output and clips more of it, and that out-of-range thresholds are
+    rejected.
+    """
+    image_id = backend.load_raw_image(sample_raw)
+    try:
+        gentle = backend.decode_image(image_id, half_size=True, auto_bright_thr=0.001)
+        strong = backend.decode_image(image_id, half_size=True, auto_bright_thr=0.1)
+        for threshold in (0.0, 0.8):
+            with pytest.raises(ValueError):
+                backend.decode_image(image_id, half_size=True, auto_bright_thr=threshold)
+    finally:
+        backend.release_raw_image(image_id)
+
+    assert statistics.fmean(strong["data"]) > statistics.fmean(gentle["data"])
+    assert strong["data"].count(255) > gentle["data"].count(255)
+
+
+def test_linear_decode_skips_white_balance_and_gamma(sample_raw):
+    """
+    Tests that a linear decode differs from the default one and, without
+    gamma curve or brightness stretch, comes out darker; and that a default
+    decode afterwards is unaffected by it.
+    """
+    image_id = backend.load_raw_image(sample_raw)
+    try:
+        default = backend.decode_image(image_id, half_size=True)
+        linear = backend.decode_image(image_id, half_size=True, linear=True)
+        default_again = backend.decode_image(image_id, half_size=True)
+    finally:
+        backend.release_raw_image(image_id)
+
+    assert len(linear["data"]) == len(default["data"])
+    assert linear["data"] != default["data"]
+    assert statistics.fmean(linear["data"]) < statistics.fmean(default["data"])
+    assert default_again["data"] == default["data"]
+
+
+def test_decode_linear16_scales_linearly(sample_raw):
+    """
+    Tests that the linear decode returns 16-bit RGB samples and that
+    doubling the exposure roughly doubles the values that do not clip.
+    """
+    normal = backend.decode_linear16(sample_raw)
+    doubled = backend.decode_linear16(sample_raw, bright=2.0)
+
+    assert normal["channels"] == 3
+    assert len(normal["data"]) == normal["width"] * normal["height"] * 3 * 2
+    samples = array.array("H", normal["data"])
+    doubled_samples = array.array("H", doubled["data"])
+    assert normal["max_value"] == max(samples)
+
+    ratios = [d / n for n, d in zip(samples[::97], doubled_samples[::97])
+              if 256 < n < normal["max_value"] // 4]
+    assert ratios
+    assert statistics.median(ratios) == pytest.approx(2.0, rel=0.1)
+
+
+def test_load_raw_f32_normalizes_to_unit_range(sample_raw):
+    """
+    Tests that a normalized float decode lies in [0, 1] and equals the
+    unnormalized one divided by 65535.
+    """
+    normalized = backend.load_raw_f32(sample_raw, half_size=True)
+    scaled = backend.load_raw_f32(sample_raw, normalize=False, half_size=True)
+
+    assert len(normalized["data"]) == normalized["width"] * normalized["height"] * normalized["channels"] * 4
+    values = array.array("f", normalized["data"])
+    scaled_values = array.array("f", scaled["data"])
+    assert 0.0 <= min(values) and max(values) <= 1.0
+    assert max(scaled_values) > 1.0
+    assert all(v == pytest.approx(s / 65535.0) for v, s in zip(values[::101], scaled_values[::101]))
+
+
+def test_wb_compare_differs_when_multipliers_disagree(sample_raw):
+    """
+    Tests that the camera and auto white balance versions have the same
+    size and differ whenever their applied multipliers differ.
+    """
+    comparison = backend.load_raw_wb_compare(sample_raw)
+    camera, auto = comparison["camera"], comparison["auto"]
+
+    assert (camera["width"], camera["height"]) == (auto["width"], auto["height"])
+    assert camera["bits"] == auto["bits"] == 8
+    assert len(camera["multipliers"]) == 4
+    if camera["multipliers"] == auto["multipliers"]:
+        pytest.skip("Camera and auto white balance agree for this sample")
+    assert camera["data"] != auto["data"]
+
+
+def test_load_errors_describe_the_cause(tmp_path):
+    """
+    Tests that LibRaw failures carry a description of the cause instead of
+    a bare "failed" message.
+    """
+    missing = tmp_path / "missing.nef"
+    with pytest.raises(RuntimeError, match="does not exist"):
+        backend.load_raw_image(str(missing))
+
+    not_raw = tmp_path / "notes.nef"
+    not_raw.write_text("not a raw file")
+    with pytest.raises(RuntimeError, match="unsupported file format"):
+        backend.load_raw_image(str(not_raw))
+
+
+def test_demosaic_pack_qualities_explain_missing_packs(sample_raw):
+    """
+    Tests that requesting a demosaic algorithm from the removed GPL demosaic
+    packs says so in the error message.
+    """
+    image_id = backend.load_raw_image(sample_raw)
+    try:
+        with pytest.raises(ValueError, match="demosaic packs"):
+            backend.decode_image(image_id, quality=6)
+    finally:
+        backend.release_raw_image(image_id)
+
+
+def _opcode_list(*opcodes: tuple[int, bytes]) -> bytes:
+    data = struct.pack(">I", len(opcodes))
+    for opcode_id, parameters in opcodes:
+        data += struct.pack(">IIII", opcode_id, 0x01030000, 1, len(parameters)) + parameters
+    return data
+
+
+def _write_dng_opcodes(path, list2: bytes, list3: bytes) -> None:
+    """
+    Writes a TIFF laid out like a DNG: IFD0 is a preview whose SubIFD is the
+    full-resolution image carrying the opcode lists.
+    """
+    sub_ifd = 8 + 2 + 2 * 12 + 4
+    list2_offset = sub_ifd + 2 + 3 * 12 + 4
+    data = b"II*\x00" + struct.pack("<I", 8)
+    data += struct.pack("<H", 2) + struct.pack("<HHII", 254, 4, 1, 1) + struct.pack("<HHII", 330, 4, 1, sub_ifd)
+    data += struct.pack("<I", 0)
+    data += struct.pack("<H", 3) + struct.pack("<HHII", 254, 4, 1, 0)
+    data += struct.pack("<HHII", 51009, 7, len(list2), list2_offset)
+    data += struct.pack("<HHII", 51022, 7, len(list3), list2_offset + len(list2))
+    data += struct.pack("<I", 0) + list2 + list3
+    path.write_bytes(data)
+
+
+def test_read_dng_opcodes_finds_gain_maps_and_warps(tmp_path):
+    """
+    Tests that the opcode lists of the full-resolution image are parsed: a
+    gain map from OpcodeList2, a lens warp from OpcodeList3, and the ID of an
+    unsupported opcode.
+    """
+    gains = [2.0, 1.0, 2.0, 1.0, 1.0, 1.0, 2.0, 1.0, 2.0]
+    gain_map = struct.pack(">10I", 0, 0, 100, 150, 0, 3, 1, 1, 3, 3)
+    gain_map += struct.pack(">4dI", 0.5, 0.5, 0.0, 0.0, 1) + struct.pack(">9f", *gains)
+    warp = struct.pack(">I6d2d", 1, 1.0, 0.02, 0.0, 0.0, 0.001, 0.0, 0.4, 0.6)
+    path = tmp_path / "opcodes.dng"
+    _write_dng_opcodes(path, _opcode_list((9, gain_map), (4, bytes(8))), _opcode_list((1, warp)))
+
+    opcodes = backend.read_dng_opcodes(str(path))
+    assert len(opcodes["gain_maps"]) == 1
+    gain_map = opcodes["gain_maps"][0]
+    assert gain_map["list"] == 2
+    assert gain_map["area"] == [0, 0, 100, 150]
+    assert (gain_map["planes"], gain_map["points"]) == (3, [3, 3])
+    assert (gain_map["min_gain"], gain_map["max_gain"]) == (1.0, 2.0)
+    assert opcodes["warps"] == [{"list": 3, "coefficients": [[1.0, 0.02, 0.0, 0.0, 0.001, 0.0]], "center": [0.4, 0.6]}]
+    assert opcodes["skipped"] == [4]
+
+    truncated = tmp_path / "truncated.dng"
+    _write_dng_opcodes(truncated, _opcode_list((9, struct.pack(">10I", *range(10)))), b"")
+    with pytest.raises(ValueError):
+        backend.read_dng_opcodes(str(truncated))
+
+
+def test_dng_gain_map_brightens_the_corners(sample_dng_gain_map):
+    """
+    Tests that decoding with the DNG opcodes applied brightens the corners of
+    an image with a vignetting gain map by more than its center.
+    """
+    assert backend.read_dng_opcodes(sample_dng_gain_map)["gain_maps"]
+    image_id = backend.load_raw_image(sample_dng_gain_map)
+    try:
+        plain = backend.decode_image(image_id, half_size=True)
+        corrected = backend.decode_image(image_id, half_size=True, dng_opcodes=True)
+    finally:
+        backend.release_raw_image(image_id)
+
+    width, height = plain["width"], plain["height"]
+    patch = max(4, min(width, height) // 20)
+
+    def mean(data, x0, y0):
+        return statistics.fmean(
+            data[(y * width + x) * 3 + c] for y in range(y0, y0 + patch) for x in range(x0, x0 + patch) for c in range(3)
+        )
+
+    corner = (0, 0)
+    center = ((width - patch) // 2, (height - patch) // 2)
+    corner_gain = mean(corrected["data"], *corner) / max(mean(plain["data"], *corner), 1.0)
+    center_gain = mean(corrected["data"], *center) / max(mean(plain["data"], *center), 1.0)
+    assert corner_gain > center_gain * 1.02
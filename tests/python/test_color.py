@@ -0,0 +1,88 @@
+import itertools
+
+import pytest
+
+from mpr_photo_editor import backend
+
+# A coarse grid over the RGB cube, including black, white, and the primaries.
+RGB_SAMPLES = [tuple(c / 4 for c in rgb) for rgb in itertools.product(range(5), repeat=3)]
+
+CONVERSIONS = [
+    (backend.rgb_to_ycbcr, backend.ycbcr_to_rgb),
+    (backend.rgb_to_hsl, backend.hsl_to_rgb),
+    (backend.rgb_to_hsv, backend.hsv_to_rgb),
+    (backend.rgb_to_lab, backend.lab_to_rgb),
+]
+
+
+@pytest.mark.parametrize("forward, backward", CONVERSIONS)
+def test_pixel_round_trip(forward, backward):
+    """
+    Tests that converting a pixel into each color space and back returns
+    the original RGB value within a small tolerance.
+    """
+    for rgb in RGB_SAMPLES:
+        result = backward(forward(rgb))
+        assert result == pytest.approx(rgb, abs=1e-4)
+
+
+@pytest.mark.parametrize("forward, backward", CONVERSIONS)
+def test_buffer_round_trip(forward, backward):
+    """
+    Tests that the whole-buffer conversions round-trip an 8-bit image to
+    within one level per channel.
+    """
+    image = bytes(int(c * 255) for rgb in RGB_SAMPLES for c in rgb)
+    width, height = len(RGB_SAMPLES), 1
+
+    converted = forward(image, width, height)
+    assert len(converted) == len(image)
+
+    result = backward(converted, width, height)
+    assert all(abs(a - b) <= 1 for a, b in zip(result, image))
+
+
+def test_known_values():
+    """
+    Tests a few reference values: red's hue, white's Lab lightness, and
+    gray's chroma.
+    """
+    assert backend.rgb_to_hsv((1.0, 0.0, 0.0)) == pytest.approx((0.0, 1.0, 1.0))
+    assert backend.rgb_to_hsl((0.0, 0.0, 1.0)) == pytest.approx((240.0, 1.0, 0.5))
+    assert backend.rgb_to_lab((1.0, 1.0, 1.0)) == pytest.approx((100.0, 0.0, 0.0), abs=0.01)
+    assert backend.rgb_to_ycbcr((0.5, 0.5, 0.5)) == pytest.approx((0.5, 0.0, 0.0), abs=1e-6)
+
+
+@pytest.mark.parametrize("temperature", [2850.0, 5000.0, 6504.0, 10000.0])
+@pytest.mark.parametrize("tint", [-30.0, 0.0, 30.0])
+def test_temp_tint_round_trip(temperature, tint):
+    """
+    Tests that converting a temperature and tint to multipliers and back
+    recovers them within a small tolerance.
+    """
+    multipliers = backend.temp_tint_to_multipliers(temperature, tint)
+    assert multipliers[1] == 1.0
+    back_temperature, back_tint = backend.multipliers_to_temp_tint(multipliers)
+    assert back_temperature == pytest.approx(temperature, rel=0.01)
+    assert back_tint == pytest.approx(tint, abs=1.0)
+
+
+def test_temp_tint_multipliers_follow_the_light():
+    """
+    Tests that warm light is neutralized by lowering red and raising blue,
+    cool light the other way round, and that unit multipliers describe
+    daylight near D65.
+    """
+    red, _, blue = backend.temp_tint_to_multipliers(2850.0)
+    assert red < 1.0 < blue
+    red, _, blue = backend.temp_tint_to_multipliers(12000.0)
+    assert red > 1.0 > blue
+
+    temperature, tint = backend.multipliers_to_temp_tint((1.0, 1.0, 1.0))
+    assert temperature == pytest.approx(6500.0, rel=0.01)
+    assert tint == pytest.approx(9.5, abs=1.0)
+
+    with pytest.raises(ValueError):
+        backend.temp_tint_to_multipliers(1000.0)
+    with pytest.raises(ValueError):
+        backend.multipliers_to_temp_tint((1.0, 0.0, 1.0))
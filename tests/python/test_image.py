@@ -0,0 +1,132 @@
+import threading
+
+import pytest
+
+from mpr_photo_editor import backend
+
+
+def _gradient(width: int, height: int, channels: int = 3) -> bytes:
+    return bytes((x * 255 // (width - 1)) for _ in range(height) for x in range(width) for _ in range(channels))
+
+
+def test_chained_edits_match_the_free_functions(tmp_path):
+    """
+    Tests that chaining brightness, contrast, and crop on a PyImage returns
+    the same object each time and gives the same pixels as calling the free
+    functions one after another.
+    """
+    width, height = 32, 16
+    data = _gradient(width, height)
+    image = backend.PyImage(data, width, height, 3)
+
+    result = image.brightness(0.1).contrast(0.3).crop(4, 2, 20, 10)
+    assert result is image
+    assert (image.width, image.height, image.channels) == (20, 10, 3)
+
+    expected = backend.adjust_brightness(data, width, height, 3, 0.1)
+    expected = backend.adjust_contrast(expected, width, height, 3, 0.3)
+    expected = backend.crop_image(expected, width, height, 3, (4, 2, 20, 10))
+    assert image.data == expected
+
+    path = tmp_path / "chained.jpg"
+    assert image.save(str(path)) is image
+    assert path.read_bytes()[:2] == b"\xff\xd8"
+
+
+def test_save_picks_the_format_from_the_extension(tmp_path):
+    """
+    Tests that .tif files get a TIFF holding the pixels, and unknown
+    extensions are refused.
+    """
+    image = backend.PyImage(_gradient(8, 4), 8, 4, 3)
+    path = tmp_path / "image.TIFF"
+    image.save(str(path))
+    assert path.read_bytes() == backend.encode_tiff(image.data, 8, 4, 3)
+
+    with pytest.raises(ValueError, match="format"):
+        image.save(str(tmp_path / "image.bmp"))
+
+
+def test_pyimage_checks_buffer_size():
+    """
+    Tests that a PyImage cannot be built from a buffer that does not match
+    its dimensions.
+    """
+    with pytest.raises(ValueError):
+        backend.PyImage(bytes(10), 2, 2, 3)
+
+
+def test_pyimage_from_handle_decodes_the_raw_image(sample_raw):
+    """
+    Tests that a PyImage made from a raw image handle holds its 8-bit decode.
+    """
+    image_id = backend.load_raw_image(sample_raw)
+    try:
+        image = backend.PyImage.from_handle(image_id, half_size=True)
+        decoded = backend.decode_image(image_id, half_size=True)
+    finally:
+        backend.release_raw_image(image_id)
+
+    assert (image.width, image.height) == (decoded["width"], decoded["height"])
+    assert image.data == decoded["data"]
+
+
+def test_render_fuses_point_operations():
+    """
+    Tests that brightness, contrast, and gamma queued together render in a
+    single pass over the pixels with the same result as rendering after each
+    edit, which takes one pass per edit.
+    """
+    width, height = 32, 16
+    data = _gradient(width, height)
+
+    fused = backend.PyImage(data, width, height, 3)
+    fused.brightness(0.1).contrast(0.3).gamma(1.8)
+    assert fused.buffer_passes == 0
+    assert fused.render() is fused
+    assert fused.buffer_passes == 1
+
+    stepwise = backend.PyImage(data, width, height, 3)
+    stepwise.brightness(0.1).render().contrast(0.3).render().gamma(1.8).render()
+    assert stepwise.buffer_passes == 3
+
+    assert fused.data == stepwise.data
+
+
+def test_edits_are_checked_when_queued():
+    """
+    Tests that invalid edits raise immediately rather than on render, and
+    that queued crops report their size before rendering.
+    """
+    image = backend.PyImage(_gradient(8, 4), 8, 4, 3)
+    with pytest.raises(ValueError):
+        image.gamma(0.0)
+    image.crop(1, 1, 4, 2)
+    assert (image.width, image.height) == (4, 2)
+    with pytest.raises(ValueError):
+        image.crop(2, 0, 4, 2)
+
+
+def test_edits_queued_while_another_thread_renders_are_kept():
+    """
+    Tests that edits queued from one thread while another renders the same
+    PyImage are neither lost nor applied twice.
+    """
+    width, height = 256, 256
+    image = backend.PyImage(bytes(width * height * 3), width, height, 3)
+    done = threading.Event()
+
+    def render():
+        while not done.is_set():
+            image.render()
+
+    renderer = threading.Thread(target=render)
+    renderer.start()
+    try:
+        for _ in range(200):
+            image.brightness(1 / 255)
+    finally:
+        done.set()
+        renderer.join()
+
+    assert set(image.data) == {200}
@@ -0,0 +1,372 @@
+import base64
+import random
+import struct
+
+import pytest
+
+from mpr_photo_editor import backend
+
+
+def test_preview_jpeg_base64_decodes_to_jpeg():
+    """
+    Tests that the base64 preview decodes back to a complete JPEG file.
+    """
+    width, height = 20, 10
+    image = bytes((x * 12) % 256 for x in range(width * height * 3))
+
+    encoded = backend.preview_jpeg_base64(image, width, height, 80)
+    assert isinstance(encoded, str)
+
+    data = base64.b64decode(encoded, validate=True)
+    assert data[:2] == b"\xff\xd8"
+    assert data[-2:] == b"\xff\xd9"
+    assert b"JFIF" in data[:32]
+
+
+def _jpeg_sampling_factors(data: bytes) -> list:
+    """
+    Returns the (horizontal, vertical) sampling factors of each component
+    from the start-of-frame segment of a JPEG file.
+    """
+    pos = 2
+    while pos < len(data):
+        marker, length = struct.unpack(">xBH", data[pos:pos + 4])
+        if marker in (0xC0, 0xC1, 0xC2):
+            count = data[pos + 9]
+            factors = data[pos + 11:pos + 11 + 3 * count:3]
+            return [(f >> 4, f & 0x0F) for f in factors]
+        pos += 2 + length
+    raise AssertionError("No start-of-frame segment")
+
+
+def test_encode_jpeg_defaults_to_420_subsampling():
+    """
+    Tests that color JPEGs are subsampled 4:2:0 unless asked otherwise.
+    """
+    image = bytes((i * 13) % 256 for i in range(16 * 16 * 3))
+    jpeg = backend.encode_jpeg(image, 16, 16, 3)
+    assert _jpeg_sampling_factors(jpeg) == [(2, 2), (1, 1), (1, 1)]
+
+
+def test_encode_jpeg_444_keeps_more_chroma_than_420():
+    """
+    Tests that full chroma resolution gives a larger file than 4:2:0 for a
+    colorful image, with 4:2:2 in between.
+    """
+    rng = random.Random(7)
+    width, height = 64, 64
+    image = bytes(rng.randrange(256) for _ in range(width * height * 3))
+
+    sizes = {}
+    for subsampling, factors in [
+        (backend.ChromaSubsampling.S444, (1, 1)),
+        (backend.ChromaSubsampling.S422, (2, 1)),
+        (backend.ChromaSubsampling.S420, (2, 2)),
+    ]:
+        jpeg = backend.encode_jpeg(image, width, height, 3, 90, subsampling)
+        assert _jpeg_sampling_factors(jpeg)[0] == factors
+        sizes[subsampling] = len(jpeg)
+
+    assert sizes[backend.ChromaSubsampling.S444] > sizes[backend.ChromaSubsampling.S422]
+    assert sizes[backend.ChromaSubsampling.S422] > sizes[backend.ChromaSubsampling.S420]
+
+
+def _read_exif(data: bytes) -> dict:
+    """
+    Collects the tags of IFD0 and the EXIF sub-IFD from the APP1 segment of
+    a JPEG file. ASCII values are returned without their terminator.
+    """
+    pos = 2
+    while pos < len(data):
+        marker, length = struct.unpack(">xBH", data[pos:pos + 4])
+        segment = data[pos + 4:pos + 2 + length]
+        if marker == 0xE1 and segment[:6] == b"Exif\0\0":
+            break
+        pos += 2 + length
+    else:
+        raise AssertionError("No EXIF segment")
+
+    tiff = segment[6:]
+    order = {b"II": "<", b"MM": ">"}[tiff[:2]]
+    formats = {2: "s", 3: "H", 4: "I", 5: "II"}
+    sizes = {2: 1, 3: 2, 4: 4, 5: 8}
+
+    def read_ifd(offset):
+        (count,) = struct.unpack(order + "H", tiff[offset:offset + 2])
+        tags = {}
+        for i in range(count):
+            entry = offset + 2 + i * 12
+            tag, kind, n, raw = struct.unpack(order + "HHI4s", tiff[entry:entry + 12])
+            size = sizes[kind] * n
+            value = raw[:size] if size <= 4 else tiff[struct.unpack(order + "I", raw)[0]:][:size]
+            tags[tag] = value.rstrip(b"\0").decode() if kind == 2 else struct.unpack(order + formats[kind] * n, value)
+        return tags
+
+    tags = read_ifd(struct.unpack(order + "I", tiff[4:8])[0])
+    tags.update(read_ifd(tags[0x8769][0]))
+    return tags
+
+
+def test_encode_jpeg_embeds_supplied_exif():
+    """
+    Tests that metadata passed to the JPEG encoder ends up in the file's
+    EXIF segment, and that no EXIF is written without it.
+    """
+    image = bytes((i * 13) % 256 for i in range(8 * 8 * 3))
+    metadata = {
+        "make": "Nikon",
+        "model": "Z 6",
+        "iso": 800.0,
+        "shutter": 1 / 250,
+        "aperture": 5.6,
+        "timestamp": 1700000000,
+    }
+    tags = _read_exif(backend.encode_jpeg(image, 8, 8, 3, 90, metadata=metadata))
+
+    assert tags[0x8827] == (800,)
+    assert tags[0x010F] == "Nikon"
+    assert tags[0x0110] == "Z 6"
+    assert tags[0x829A] == (1, 250)
+    assert tags[0x829D] == (56, 10)
+    assert len(tags[0x9003]) == 19
+
+    with pytest.raises(AssertionError, match="No EXIF"):
+        _read_exif(backend.encode_jpeg(image, 8, 8, 3, 90))
+
+
+@pytest.mark.parametrize("shutter, rational", [(1 / 250, (1, 250)), (0.5, (1, 2)), (0.7, (7, 10)), (2.5, (25, 10))])
+def test_encode_jpeg_exif_exposure_time(shutter, rational):
+    """
+    Tests that exposure times whose reciprocal is a whole number are written
+    as 1/n, and all others in tenths of a second.
+    """
+    image = bytes(8 * 8 * 3)
+    tags = _read_exif(backend.encode_jpeg(image, 8, 8, 3, metadata={"shutter": shutter}))
+    assert tags[0x829A] == rational
+
+
+def test_encode_jpeg_exif_skips_missing_values():
+    """
+    Tests that a partial metadata dict only writes the values it holds.
+    """
+    image = bytes(8 * 8 * 3)
+    tags = _read_exif(backend.encode_jpeg(image, 8, 8, 3, metadata={"iso": 100}))
+    assert tags[0x8827] == (100,)
+    assert 0x010F not in tags and 0x829A not in tags
+
+
+def _read_tiff(data: bytes) -> dict:
+    """
+    Parses the first IFD of an uncompressed single-strip TIFF into a dict of
+    tag values plus the strip contents under "pixels".
+    """
+    order = {b"II": "<", b"MM": ">"}[data[:2]]
+    magic, ifd = struct.unpack(order + "HI", data[2:8])
+    assert magic == 42
+    sizes = {2: 1, 3: 2, 4: 4, 5: 8, 7: 1}
+    formats = {2: "s", 3: "H", 4: "I", 5: "II"}
+    (count,) = struct.unpack(order + "H", data[ifd:ifd + 2])
+    tags = {}
+    for i in range(count):
+        tag, kind, n, raw = struct.unpack(order + "HHI4s", data[ifd + 2 + i * 12:ifd + 14 + i * 12])
+        size = sizes[kind] * n
+        value = raw[:size] if size <= 4 else data[struct.unpack(order + "I", raw)[0]:][:size]
+        if kind in (2, 7):
+            tags[tag] = value
+        else:
+            tags[tag] = struct.unpack(order + formats[kind] * n, value)
+    offset, length = tags[273][0], tags[279][0]
+    tags["pixels"] = data[offset:offset + length]
+    return tags
+
+
+@pytest.mark.parametrize("channels, bits", [(1, 8), (3, 8), (4, 8), (3, 16)])
+def test_encode_tiff_writes_a_readable_baseline_tiff(channels, bits):
+    """
+    Tests that encoded TIFFs carry the right dimensions, sample layout, and
+    the unmodified pixel data.
+    """
+    width, height = 5, 3
+    image = bytes((i * 7) % 256 for i in range(width * height * channels * bits // 8))
+
+    tags = _read_tiff(backend.encode_tiff(image, width, height, channels, bits))
+    assert (tags[256][0], tags[257][0]) == (width, height)
+    assert tags[258] == (bits,) * channels
+    assert tags[259] == (1,)
+    assert tags[277] == (channels,)
+    assert tags["pixels"] == image
+
+
+@pytest.mark.parametrize("width, height, channels, bits", [(5, 3, 3, 8), (1500, 1000, 3, 8), (700, 900, 4, 16)])
+def test_write_tiff_matches_encode_tiff(tmp_path, width, height, channels, bits):
+    """
+    Tests that streaming a TIFF to disk in strips, including images larger
+    than one strip, writes exactly the bytes encode_tiff returns.
+    """
+    image = bytes((i * 7) % 256 for i in range(width * height * channels * bits // 8))
+    path = tmp_path / "export.tif"
+    backend.write_tiff(str(path), image, width, height, channels, bits)
+    assert path.read_bytes() == backend.encode_tiff(image, width, height, channels, bits)
+
+
+def test_write_tiff_reports_unwritable_paths(tmp_path):
+    """
+    Tests that writing into a directory that does not exist fails clearly.
+    """
+    with pytest.raises(RuntimeError, match="for writing"):
+        backend.write_tiff(str(tmp_path / "missing" / "export.tif"), bytes(3), 1, 1, 3)
+
+
+def _jpeg_size(data: bytes) -> tuple:
+    """
+    Returns (width, height) from the start-of-frame segment of a JPEG file.
+    """
+    assert data[:2] == b"\xff\xd8"
+    pos = 2
+    while pos < len(data):
+        marker, length = struct.unpack(">xBH", data[pos:pos + 4])
+        if marker in (0xC0, 0xC1, 0xC2):
+            height, width = struct.unpack(">HH", data[pos + 5:pos + 9])
+            return width, height
+        pos += 2 + length
+    raise AssertionError("No start-of-frame segment")
+
+
+@pytest.mark.parametrize("sharpen_amount", [0.0, 0.8])
+def test_export_region_encodes_the_requested_size(sharpen_amount):
+    """
+    Tests that exporting a region yields a valid JPEG or TIFF of the
+    requested output size.
+    """
+    width, height = 64, 48
+    image = bytes((i * 7) % 256 for i in range(width * height * 3))
+    crop = (8, 4, 40, 30)
+
+    jpeg = backend.export_region(image, width, height, 3, crop, 20, 15, sharpen_amount,
+                                 backend.ExportFormat.JPEG, 90)
+    assert jpeg[-2:] == b"\xff\xd9"
+    assert _jpeg_size(jpeg) == (20, 15)
+
+    tiff = _read_tiff(backend.export_region(image, width, height, 3, crop, 20, 15, sharpen_amount,
+                                            backend.ExportFormat.TIFF, 90))
+    assert (tiff[256][0], tiff[257][0]) == (20, 15)
+    assert len(tiff["pixels"]) == 20 * 15 * 3
+
+
+def test_export_region_without_resize_or_sharpen_keeps_the_crop():
+    """
+    Tests that a TIFF export at the crop's own size holds exactly the
+    cropped pixels.
+    """
+    width, height = 6, 4
+    image = bytes(range(width * height))
+    tiff = _read_tiff(backend.export_region(image, width, height, 1, (2, 1, 3, 2), 3, 2, 0.0,
+                                            backend.ExportFormat.TIFF, 90))
+    assert tiff["pixels"] == bytes([8, 9, 10, 14, 15, 16])
+
+
+def test_export_region_rejects_crops_outside_the_image():
+    """
+    Tests that a crop rectangle reaching past the image border is refused.
+    """
+    with pytest.raises(ValueError):
+        backend.export_region(bytes(4 * 4 * 3), 4, 4, 3, (2, 2, 3, 3), 2, 2)
+
+
+def test_srgb_icc_profile_is_a_display_profile():
+    """
+    Tests that the bundled sRGB profile has a consistent ICC header.
+    """
+    profile = backend.srgb_icc_profile()
+    assert struct.unpack(">I", profile[:4])[0] == len(profile)
+    assert profile[12:20] == b"mntrRGB "
+    assert profile[36:40] == b"acsp"
+
+
+def test_encode_jpeg_embeds_icc_profile():
+    """
+    Tests that a JPEG encoded with a profile carries it in an APP2
+    ICC_PROFILE segment, and one encoded without does not.
+    """
+    profile = backend.srgb_icc_profile()
+    image = bytes((i * 13) % 256 for i in range(8 * 8 * 3))
+    jpeg = backend.encode_jpeg(image, 8, 8, 3, icc_profile=profile)
+    assert b"\xff\xe2" + struct.pack(">H", len(profile) + 16) + b"ICC_PROFILE\0\x01\x01" + profile in jpeg
+    assert b"ICC_PROFILE" not in backend.encode_jpeg(image, 8, 8, 3)
+
+
+@pytest.mark.parametrize("channels, bits", [(3, 8), (4, 8), (3, 16)])
+def test_encode_tiff_embeds_icc_profile(tmp_path, channels, bits):
+    """
+    Tests that TIFFs encoded or written with a profile carry it in the ICC
+    profile tag and keep their pixel data intact.
+    """
+    profile = backend.srgb_icc_profile()
+    width, height = 5, 3
+    image = bytes((i * 7) % 256 for i in range(width * height * channels * bits // 8))
+
+    encoded = backend.encode_tiff(image, width, height, channels, bits, profile)
+    tags = _read_tiff(encoded)
+    assert tags[34675] == profile
+    assert tags["pixels"] == image
+    assert 34675 not in _read_tiff(backend.encode_tiff(image, width, height, channels, bits))
+
+    path = tmp_path / "export.tif"
+    backend.write_tiff(str(path), image, width, height, channels, bits, profile)
+    assert path.read_bytes() == encoded
+
+
+def _jfif_density(data: bytes) -> tuple:
+    """
+    Returns (unit, x density, y density) from the JFIF APP0 segment.
+    """
+    assert data[2:4] == b"\xff\xe0" and data[6:11] == b"JFIF\0"
+    return struct.unpack(">BHH", data[13:18])
+
+
+@pytest.mark.parametrize("dpi", [72, 300])
+def test_exports_record_the_requested_dpi(tmp_path, dpi):
+    """
+    Tests that JPEG and TIFF exports store the requested resolution in dots
+    per inch.
+    """
+    width, height = 8, 6
+    image = bytes((i * 7) % 256 for i in range(width * height * 3))
+
+    assert _jfif_density(backend.encode_jpeg(image, width, height, 3, dpi=dpi)) == (1, dpi, dpi)
+
+    tags = _read_tiff(backend.encode_tiff(image, width, height, 3, dpi=dpi))
+    assert tags[282] == (dpi, 1) and tags[283] == (dpi, 1)
+    assert tags[296] == (2,)
+
+    path = tmp_path / "export.tif"
+    backend.write_tiff(str(path), image, width, height, 3, dpi=dpi)
+    assert _read_tiff(path.read_bytes())[282] == (dpi, 1)
+
+    exported = backend.export_region(image, width, height, 3, (0, 0, width, height), width, height, dpi=dpi)
+    assert _jfif_density(exported) == (1, dpi, dpi)
+
+
+def test_exports_default_to_72_dpi():
+    """
+    Tests that exports without an explicit resolution say 72 dpi, and that
+    resolutions JFIF cannot store are refused.
+    """
+    image = bytes(4 * 4 * 3)
+    assert _jfif_density(backend.encode_jpeg(image, 4, 4, 3)) == (1, 72, 72)
+    assert _read_tiff(backend.encode_tiff(image, 4, 4, 3))[282] == (72, 1)
+    with pytest.raises(ValueError):
+        backend.encode_tiff(image, 4, 4, 3, dpi=0)
+
+
+def test_decode_to_tiff_bytes_is_a_valid_tiff(sample_raw):
+    """
+    Tests that a raw file decodes into a complete 16-bit RGB TIFF that
+    carries the sRGB profile.
+    """
+    tags = _read_tiff(backend.decode_to_tiff_bytes(sample_raw))
+    width, height = tags[256][0], tags[257][0]
+    assert tags[258] == (16, 16, 16)
+    assert tags[262] == (2,)
+    assert tags[34675] == backend.srgb_icc_profile()
+    assert len(tags["pixels"]) == width * height * 3 * 2
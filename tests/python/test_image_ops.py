@@ -0,0 +1,1069 @@
+import array
+import ctypes
+import json
+import random
+import statistics
+import time
+
+import pytest
+
+from mpr_photo_editor import backend
+
+
+def test_split_tone_tints_shadows_and_highlights_differently():
+    """
+    Tests that split toning gives shadows the shadow hue (blue) and
+    highlights the highlight hue (orange).
+    """
+    image = bytes([40, 40, 40, 215, 215, 215])
+    result = backend.split_tone(image, 2, 1, 240.0, 0.5, 30.0, 0.5, 0.0)
+    assert len(result) == len(image)
+
+    shadow, highlight = result[0:3], result[3:6]
+    assert shadow[2] > shadow[0]
+    assert highlight[0] > highlight[2]
+
+
+def test_color_balance_midtone_shift_spares_deep_shadows():
+    """
+    Tests that shifting the midtones towards red visibly changes a midtone
+    pixel while leaving a deep shadow pixel almost untouched.
+    """
+    image = bytes([10, 10, 10, 128, 128, 128])
+    result = backend.color_balance(image, 2, 1, (0.0, 0.0, 0.0), (1.0, 0.0, 0.0), (0.0, 0.0, 0.0))
+
+    shadow, midtone = result[0:3], result[3:6]
+    assert midtone[0] - 128 > 30
+    assert abs(shadow[0] - 10) <= 3
+    assert shadow[1] == 10 and shadow[2] == 10
+
+
+def test_vibrance_boosts_muted_colors_more_than_saturated_ones():
+    """
+    Tests that vibrance raises the chroma of a muted pixel by a larger factor
+    than that of an already saturated one, and leaves gray alone.
+    """
+    image = bytes([230, 30, 30, 140, 110, 110, 128, 128, 128])
+    result = backend.adjust_vibrance(image, 3, 1, 0.8)
+
+    def chroma(pixel):
+        return max(pixel) - min(pixel)
+
+    saturated_gain = chroma(result[0:3]) / chroma(image[0:3])
+    muted_gain = chroma(result[3:6]) / chroma(image[3:6])
+    assert muted_gain > 1.4
+    assert saturated_gain < 1.15
+    assert result[6:9] == image[6:9]
+
+
+@pytest.mark.parametrize("name, args", [
+    ("adjust_brightness", (0.2,)),
+    ("adjust_brightness", (-0.5,)),
+    ("adjust_contrast", (0.6,)),
+    ("adjust_contrast", (-0.4,)),
+    ("adjust_gamma", (2.2,)),
+    ("adjust_gamma", (0.5,)),
+    ("invert", ()),
+])
+def test_point_ops_inplace_matches_copy(name, args):
+    """
+    Tests that the in-place variant of a point operation leaves a writable
+    buffer with exactly the bytes the allocating variant returns.
+    """
+    width, height = 16, 16
+    image = bytes(i % 256 for i in range(width * height * 3))
+    copy = getattr(backend, name)(image, width, height, 3, *args)
+
+    buffer = bytearray(image)
+    assert getattr(backend, name + "_inplace")(buffer, width, height, 3, *args) is None
+    assert bytes(buffer) == copy
+    assert copy != image
+
+
+def test_rounding_takes_halves_to_even():
+    """
+    Tests that results exactly halfway between two codes round to the even
+    one, including 127.5 and 255.5, for float operations and for exact
+    integer averages alike.
+    """
+    half = 0.5 / 255  # A brightness offset of exactly half a code.
+    image = bytes([0, 126, 127, 254, 255])
+    assert backend.adjust_brightness(image, 5, 1, 1, half) == bytes([0, 126, 128, 254, 255])
+
+    frames = [bytes([126, 127]), bytes([127, 128])]
+    assert backend.average_stack(frames, 2, 1, 1) == bytes([126, 128])
+    assert backend.stack(frames, 2, 1, 1, backend.StackMode.MEDIAN) == bytes([126, 128])
+
+
+def test_srgb_transfer_functions_match_the_reference_curve():
+    """
+    Tests that the sRGB conversions follow the piecewise sRGB curve at known
+    points (code 128 is 21.6% of the light, 188 is half of it), that linear
+    values survive a round trip through sRGB, and that sRGB codes do too
+    outside the deep shadows.
+    """
+    codes = bytes(range(256))
+    linear = backend.srgb_to_linear(codes, 256, 1, 1)
+    encoded = backend.linear_to_srgb(codes, 256, 1, 1)
+    assert [linear[v] for v in (0, 10, 128, 188, 255)] == [0, 1, 55, 128, 255]
+    assert [encoded[v] for v in (0, 1, 55, 128, 255)] == [0, 13, 128, 188, 255]
+
+    assert all(abs(a - b) <= 1 for a, b in zip(backend.srgb_to_linear(encoded, 256, 1, 1), codes))
+    round_trip = backend.linear_to_srgb(linear, 256, 1, 1)
+    assert all(abs(round_trip[v] - v) <= 1 for v in range(64, 256))
+
+    buffer = bytearray(codes)
+    backend.srgb_to_linear_inplace(buffer, 256, 1, 1)
+    assert bytes(buffer) == linear
+
+
+def test_point_ops_inplace_rejects_read_only_and_mismatched_buffers():
+    """
+    Tests that the in-place variants refuse buffers they cannot modify,
+    buffers whose size disagrees with the dimensions, and one-byte samples
+    other than uint8.
+    """
+    with pytest.raises((TypeError, ValueError, BufferError)):
+        backend.invert_inplace(bytes(12), 2, 2, 3)
+    with pytest.raises(ValueError):
+        backend.invert_inplace(bytearray(11), 2, 2, 3)
+    with pytest.raises(ValueError):
+        backend.invert_inplace(array.array("b", bytes(12)), 2, 2, 3)
+    with pytest.raises(ValueError):
+        backend.invert_inplace((ctypes.c_bool * 12)(), 2, 2, 3)
+    unsigned = array.array("B", bytes(12))
+    backend.invert_inplace(unsigned, 2, 2, 3)
+    assert unsigned.tobytes() == bytes([255] * 12)
+
+
+def test_sharpen_masked_spares_flat_noise_but_sharpens_detail():
+    """
+    Tests that masked sharpening leaves a flat, noisy region nearly alone
+    while sharpening a textured region as much as unmasked sharpening does.
+    """
+    rng = random.Random(3)
+    width, height = 64, 32
+    image = bytearray()
+    for y in range(height):
+        for x in range(width):
+            if x < width // 2:
+                v = 128 + rng.randint(-8, 8)
+            else:
+                v = 190 if (x // 4 + y // 4) % 2 else 60
+            image += bytes((v, v, v))
+    image = bytes(image)
+
+    def mean_change(result, columns):
+        changes = [abs(result[(y * width + x) * 3] - image[(y * width + x) * 3])
+                   for y in range(height) for x in columns]
+        return statistics.fmean(changes)
+
+    flat, detail = range(0, width // 2 - 3), range(width // 2 + 3, width)
+    masked = backend.sharpen_masked(image, width, height, 1.0, 1.0, 0.05)
+    unmasked = backend.sharpen_masked(image, width, height, 1.0, 1.0, 0.0)
+
+    assert mean_change(unmasked, flat) > 2.0
+    assert mean_change(masked, flat) < 0.25 * mean_change(unmasked, flat)
+    assert mean_change(masked, detail) > 0.9 * mean_change(unmasked, detail)
+    assert mean_change(masked, detail) > 10 * mean_change(masked, flat) + 10
+
+
+def test_box_blur_keeps_constant_image_and_is_radius_independent():
+    """
+    Tests that box-blurring a constant image returns the same constant, and
+    that a large radius, even one far beyond the image, costs about as much
+    as a small one.
+    """
+    width, height = 256, 256
+    image = bytes([77] * width * height * 3)
+
+    # The fastest of several runs is the least disturbed by other processes.
+    def fastest(radius):
+        times = []
+        for _ in range(5):
+            start = time.perf_counter()
+            result = backend.box_blur(image, width, height, 3, radius)
+            times.append(time.perf_counter() - start)
+        return result, min(times)
+
+    small, small_time = fastest(1)
+    large, large_time = fastest(100)
+    huge, huge_time = fastest(100000)
+
+    assert small == large == huge == image
+    assert large_time < small_time * 5 + 0.05
+    assert huge_time < small_time * 5 + 0.05
+
+
+def test_box_blur_edge_modes_differ_only_at_the_border():
+    """
+    Tests that reflecting, clamping, wrapping, and a constant border give
+    different results next to the border of a ramp. Clamping averages only
+    the pixels inside the image; the constant border darkens the whole
+    single-row image, and the others agree inside.
+    """
+    width = 8
+    image = bytes(x * 30 for x in range(width))
+    results = {
+        edge: backend.box_blur(image, width, 1, 1, 1, edge)
+        for edge in (backend.EdgeMode.CLAMP, backend.EdgeMode.REFLECT,
+                     backend.EdgeMode.WRAP, backend.EdgeMode.CONSTANT)
+    }
+    clamp = results[backend.EdgeMode.CLAMP]
+    reflect = results[backend.EdgeMode.REFLECT]
+
+    assert clamp[0] == round((0 + 30) / 2)
+    assert reflect[0] == round((30 + 0 + 30) / 3)
+    assert results[backend.EdgeMode.WRAP][0] == round((210 + 0 + 30) / 3)
+    assert len({result[0] for result in results.values()}) == 4
+    assert reflect[1:-1] == clamp[1:-1] == results[backend.EdgeMode.WRAP][1:-1]
+    assert results[backend.EdgeMode.CONSTANT][1] < clamp[1]
+    assert backend.box_blur(image, width, 1, 1, 1) == clamp
+
+
+def test_box_blur_radius_far_beyond_the_image():
+    """
+    Tests that a radius far larger than the image averages the whole image
+    when clamping or wrapping, fades to black with a constant border, and is
+    refused for a reflected border once its window sums could overflow.
+    """
+    width, height = 10, 10
+    image = bytes((x * 20 + y) % 256 for y in range(height) for x in range(width))
+    mean = sum(image) / len(image)
+
+    clamp = backend.box_blur(image, width, height, 1, 10**6)
+    wrap = backend.box_blur(image, width, height, 1, 10**6, backend.EdgeMode.WRAP)
+    constant = backend.box_blur(image, width, height, 1, 10**6, backend.EdgeMode.CONSTANT)
+
+    assert clamp == bytes([round(mean)]) * len(image)
+    assert all(abs(value - mean) <= 1 for value in wrap)
+    assert constant == bytes(len(image))
+    with pytest.raises(ValueError):
+        backend.box_blur(image, width, height, 1, 2**31 - 1, backend.EdgeMode.REFLECT)
+
+
+def test_sharpen_reads_the_border_according_to_the_edge_mode():
+    """
+    Tests that sharpening a uniform image leaves it unchanged when the blur
+    repeats, mirrors, or tiles the image, while a black border makes the
+    pixels next to it stand out.
+    """
+    width, height = 16, 12
+    image = bytes([100] * (width * height))
+    for edge in (backend.EdgeMode.CLAMP, backend.EdgeMode.REFLECT, backend.EdgeMode.WRAP):
+        assert backend.sharpen(image, width, height, 1, 1.0, 1.0, edge) == image
+
+    constant = backend.sharpen(image, width, height, 1, 1.0, 1.0, backend.EdgeMode.CONSTANT)
+    assert constant[0] > 100
+    assert constant[(height // 2) * width + width // 2] == 100
+
+
+def test_masked_sharpen_and_ycbcr_denoise_read_the_border_according_to_the_edge_mode():
+    """
+    Tests that the blur and Sobel mask of masked sharpening and the blurs of
+    YCbCr denoising leave a uniform image unchanged unless a black border is
+    read, which only changes the pixels next to it.
+    """
+    width, height = 16, 12
+    image = bytes([128] * (width * height * 3))
+    center = ((height // 2) * width + width // 2) * 3
+    for edge in (backend.EdgeMode.CLAMP, backend.EdgeMode.REFLECT, backend.EdgeMode.WRAP):
+        assert backend.sharpen_masked(image, width, height, 1.0, 1.0, 0.05, edge) == image
+        assert backend.denoise_ycbcr(image, width, height, 1.0, 2.0, edge) == image
+
+    sharpened = backend.sharpen_masked(image, width, height, 1.0, 1.0, 0.05, backend.EdgeMode.CONSTANT)
+    denoised = backend.denoise_ycbcr(image, width, height, 1.0, 2.0, backend.EdgeMode.CONSTANT)
+    assert sharpened[0] > 128
+    assert denoised[0] < 128
+    assert sharpened[center] == denoised[center] == 128
+
+
+def test_resize_image_averages_covered_pixels():
+    """
+    Tests that halving a 4x2 image averages each 2x2 block, and that a
+    constant image stays constant at a non-integer scale.
+    """
+    image = bytes([0, 100, 10, 20,
+                   200, 100, 30, 40])
+    assert backend.resize_image(image, 4, 2, 1, 2, 1) == bytes([100, 25])
+
+    flat = bytes([77] * (9 * 7 * 3))
+    resized = backend.resize_image(flat, 9, 7, 3, 4, 3)
+    assert resized == bytes([77] * (4 * 3 * 3))
+
+
+def test_linear_light_resize_keeps_the_brightness_of_fine_detail():
+    """
+    Tests that downsampling a black and white checkerboard in linear light
+    gives half the light (sRGB code 188), brighter than the code 128 of
+    averaging the encoded values, and leaves alpha averaged as is.
+    """
+    checker = bytes(255 * ((x + y) % 2) for y in range(8) for x in range(8) for _ in range(3))
+    assert backend.resize_image(checker, 8, 8, 3, 1, 1) == bytes([128] * 3)
+    linear = backend.resize_image(checker, 8, 8, 3, 1, 1, linear_light=True)
+    assert all(abs(v - 188) <= 1 for v in linear)
+
+    rgba = bytes(v for y in range(2) for x in range(2) for v in [255 * ((x + y) % 2)] * 4)
+    assert backend.resize_image(rgba, 2, 2, 4, 1, 1, linear_light=True)[3] == 128
+
+
+def test_seam_carve_keeps_a_detailed_object_intact():
+    """
+    Tests that narrowing (and shortening) a slightly noisy flat image with a
+    high-contrast checkered block removes background only: every column and
+    row of the block survives unchanged.
+    """
+    width, height = 60, 40
+    rng = random.Random(1)
+
+    def pixel(x, y):
+        if 35 <= x < 45 and 10 <= y < 30:
+            return 250 if (x + y) % 2 else 10
+        return 120 + rng.randrange(5)
+
+    image = bytes(v for y in range(height) for x in range(width) for v in [pixel(x, y)] * 3)
+    block = [bytes(v for x in range(35, 45) for v in [250 if (x + y) % 2 else 10] * 3)
+             for y in range(10, 30)]
+
+    narrowed = backend.seam_carve(image, width, height, 3, 40)
+    assert len(narrowed) == 40 * height * 3
+    rows = [narrowed[y * 40 * 3:(y + 1) * 40 * 3] for y in range(height)]
+    assert all(block[y - 10] in rows[y] for y in range(10, 30))
+
+    smaller = backend.seam_carve(image, width, height, 3, 50, 30)
+    assert len(smaller) == 50 * 30 * 3
+    rows = [smaller[y * 50 * 3:(y + 1) * 50 * 3] for y in range(30)]
+    assert sum(any(b in row for b in block) for row in rows) == 20
+
+    with pytest.raises(ValueError):
+        backend.seam_carve(image, width, height, 3, width + 1)
+
+
+@pytest.mark.parametrize("points, expected", [
+    ((0, 10, 10, 0), -45.0),
+    ((0, 0, 10, 10), 45.0),
+    ((10, 0, 0, 1), -5.71),
+    ((5, 0, 5, 10), 0.0),
+    ((0, 0, 1, -10), 5.71),
+])
+def test_straighten_angle_levels_the_line(points, expected):
+    """
+    Tests that the rotation levels a line rising or falling at 45 degrees,
+    a slightly tilted horizon drawn in either direction, and vertical and
+    nearly vertical lines.
+    """
+    assert backend.straighten_angle(*points) == pytest.approx(expected, abs=0.01)
+
+
+def test_straighten_angle_rejects_a_single_point():
+    """
+    Tests that two equal points, which define no line, are rejected.
+    """
+    with pytest.raises(ValueError):
+        backend.straighten_angle(3, 4, 3, 4)
+
+
+@pytest.mark.parametrize("flip, expected", [
+    (0, (bytes([1, 2, 3, 4, 5, 6]), 3, 2)),
+    (3, (bytes([6, 5, 4, 3, 2, 1]), 3, 2)),
+    (5, (bytes([3, 6, 2, 5, 1, 4]), 2, 3)),
+    (6, (bytes([4, 1, 5, 2, 6, 3]), 2, 3)),
+])
+def test_orient_image_matches_libraw_flip(flip, expected):
+    """
+    Tests that a 3x2 image is rotated like LibRaw does for the no-op,
+    180 degree, counter-clockwise, and clockwise flip values.
+    """
+    image = bytes([1, 2, 3, 4, 5, 6])
+    assert backend.orient_image(image, 3, 2, 1, flip) == expected
+
+
+@pytest.mark.parametrize("orientation, expected", [
+    (0, (bytes([1, 2, 3, 4, 5, 6]), 3, 2)),
+    (1, (bytes([1, 2, 3, 4, 5, 6]), 3, 2)),
+    (2, (bytes([3, 2, 1, 6, 5, 4]), 3, 2)),
+    (3, (bytes([6, 5, 4, 3, 2, 1]), 3, 2)),
+    (4, (bytes([4, 5, 6, 1, 2, 3]), 3, 2)),
+    (5, (bytes([1, 4, 2, 5, 3, 6]), 2, 3)),
+    (6, (bytes([4, 1, 5, 2, 6, 3]), 2, 3)),
+    (7, (bytes([6, 3, 5, 2, 4, 1]), 2, 3)),
+    (8, (bytes([3, 6, 2, 5, 1, 4]), 2, 3)),
+])
+def test_normalize_orientation_applies_exif_codes(orientation, expected):
+    """
+    Tests that a 3x2 image whose pixels all differ is turned upright as the
+    EXIF specification describes for every orientation code.
+    """
+    image = bytes([1, 2, 3, 4, 5, 6])
+    assert backend.normalize_orientation(image, 3, 2, 1, orientation) == expected
+
+
+def test_normalize_orientation_rejects_unknown_codes():
+    """
+    Tests that orientation codes outside 0-8 are rejected.
+    """
+    with pytest.raises(ValueError):
+        backend.normalize_orientation(bytes(6), 3, 2, 1, 9)
+
+
+def test_sample_bilinear_interpolates_and_clamps():
+    """
+    Tests that sampling at a pixel centre returns that pixel exactly, that
+    midpoints average their neighbours, and that outside points take the
+    edge value.
+    """
+    image = bytes([0, 100, 10, 20,
+                   200, 100, 30, 40])
+    assert backend.sample_bilinear(image, 4, 2, 1, 2.0, 1.0) == [30.0]
+    assert backend.sample_bilinear(image, 4, 2, 1, 0.5, 0.0) == [50.0]
+    assert backend.sample_bilinear(image, 4, 2, 1, 0.5, 0.5) == [100.0]
+    assert backend.sample_bilinear(image, 4, 2, 1, -3.0, 5.0) == [200.0]
+    assert backend.sample_bilinear(image, 4, 2, 1, 9.0, -1.0) == [20.0]
+
+    rgb = bytes([10, 20, 30, 50, 60, 70])
+    assert backend.sample_bilinear(rgb, 2, 1, 3, 0.25, 0.0) == [20.0, 30.0, 40.0]
+    with pytest.raises(ValueError):
+        backend.sample_bilinear(image, 4, 2, 1, float("nan"), 0.0)
+
+
+def test_sample_bicubic_keeps_edges_sharper():
+    """
+    Tests that bicubic sampling reproduces pixel centres exactly and, next
+    to a hard edge, stays closer to the original step than bilinear does.
+    """
+    edge = bytes([0, 0, 0, 255, 255, 255])
+    for x in range(6):
+        assert backend.sample_bicubic(edge, 6, 1, 1, float(x), 0.0) == [float(edge[x])]
+
+    def bicubic(x):
+        return backend.sample_bicubic(edge, 6, 1, 1, x, 0.0)[0]
+
+    def bilinear(x):
+        return backend.sample_bilinear(edge, 6, 1, 1, x, 0.0)[0]
+
+    assert bicubic(2.25) < bilinear(2.25)
+    assert bicubic(2.75) > bilinear(2.75)
+
+    for x in (-2.0, 0.4, 2.6, 7.0):
+        assert 0.0 <= bicubic(x) <= 255.0
+
+
+@pytest.mark.parametrize("op", [
+    lambda image: backend.split_tone(image, 4, 4, 240.0, 0.5, 30.0, 0.5),
+    lambda image: backend.color_balance(image, 4, 4, (0.2, 0.0, 0.0), (0.0, 0.2, 0.0), (0.0, 0.0, 0.2)),
+    lambda image: backend.box_blur(image, 4, 4, 3, 0),
+    lambda image: backend.adjust_brightness(image, 4, 4, 3, 0.0),
+    lambda image: backend.invert(image, 4, 4, 3),
+    lambda image: backend.denoise_wavelet(image, 4, 4, 1.0),
+    lambda image: backend.denoise_ycbcr(image, 4, 4, 0.0, 0.0),
+    lambda image: backend.fix_hot_pixels(image, 4, 4, 3, 255),
+    lambda image: backend.resize_image(image, 4, 4, 3, 4, 4),
+    lambda image: backend.orient_image(image, 4, 4, 3, 0)[0],
+    lambda image: backend.blend_laplacian(image, image, bytes(16), 4, 4, 3, 2),
+])
+def test_ops_return_new_buffers_and_leave_input_unchanged(op):
+    """
+    Tests that operations never hand back or modify their input, even when
+    the result equals it, and refuse mutable buffers that could alias.
+    """
+    image = bytes((i * 37) % 256 for i in range(4 * 4 * 3))
+    original = bytes(image)
+
+    result = op(image)
+    assert result is not image
+    assert image == original
+
+    with pytest.raises(TypeError):
+        op(bytearray(image))
+
+
+def test_build_pyramid_halves_each_level():
+    """
+    Tests that a 3-level pyramid of an 8x8 image has 8x8, 4x4, and 2x2 levels
+    with correctly sized buffers.
+    """
+    image = bytes(range(64))
+    pyramid = backend.build_pyramid(image, 8, 8, 1, 3)
+
+    assert [(w, h) for _, w, h in pyramid] == [(8, 8), (4, 4), (2, 2)]
+    assert pyramid[0][0] == image
+    assert [len(buf) for buf, _, _ in pyramid] == [64, 16, 4]
+
+
+def test_blend_laplacian_smooths_hard_seam():
+    """
+    Tests that blending black and white images with a hard half/half mask
+    gives a gradual, monotonic transition instead of a single step.
+    """
+    width, height = 32, 4
+    black = bytes(width * height)
+    white = bytes([255] * width * height)
+    mask = bytes(([255] * (width // 2) + [0] * (width // 2)) * height)
+
+    result = backend.blend_laplacian(black, white, mask, width, height, 1, 4)
+    row = list(result[:width])
+
+    assert row[0] < 10 and row[-1] > 245
+    assert all(b >= a for a, b in zip(row, row[1:]))
+    assert max(b - a for a, b in zip(row, row[1:])) < 128
+    assert sum(1 for v in row if 10 < v < 245) >= 4
+
+
+def test_average_stack_of_identical_and_opposite_frames():
+    """
+    Tests that averaging identical frames returns that frame, that black and
+    white average to middle gray, and that mismatched frames are rejected.
+    """
+    frame = bytes(range(0, 240, 10))
+    assert backend.average_stack([frame] * 5, 4, 2, 3) == frame
+
+    black = bytes(12)
+    white = bytes([255] * 12)
+    assert backend.average_stack([black, white], 2, 2, 3) == bytes([128] * 12)
+
+    with pytest.raises(ValueError):
+        backend.average_stack([black, white[:-3]], 2, 2, 3)
+    with pytest.raises(ValueError):
+        backend.average_stack([], 2, 2, 3)
+
+
+def test_stack_modes_combine_frames_per_sample():
+    """
+    Tests that max and min stacking pick the per-sample extremes of two
+    frames, and that a median stack drops a value present in one frame only.
+    """
+    first = bytes([10, 200, 30, 40, 250, 60])
+    second = bytes([100, 20, 130, 5, 50, 160])
+    assert backend.stack([first, second], 2, 1, 3, backend.StackMode.MAX) == bytes(map(max, first, second))
+    assert backend.stack([first, second], 2, 1, 3, backend.StackMode.MIN) == bytes(map(min, first, second))
+
+    background = bytes([50] * 6)
+    bird = bytes([50, 50, 50, 0, 0, 0])
+    median = backend.stack([background, bird, background], 2, 1, 3, backend.StackMode.MEDIAN)
+    assert median == background
+    assert backend.stack([first, second], 2, 1, 3, backend.StackMode.MEAN) == \
+        backend.average_stack([first, second], 2, 1, 3)
+
+
+def test_focus_stack_merges_sharp_halves():
+    """
+    Tests that two frames, each sharp in a different half, merge into an
+    image close to the fully sharp original.
+    """
+    rng = random.Random(11)
+    width, height = 64, 32
+    sharp = bytearray()
+    for y in range(height):
+        for x in range(width):
+            value = (200 if (x // 2 + y // 2) % 2 else 50) + rng.randrange(20)
+            sharp += bytes([value] * 3)
+    sharp = bytes(sharp)
+    blurred = backend.box_blur(sharp, width, height, 3, 3)
+
+    def half_sharp(left: bool) -> bytes:
+        frame = bytearray(blurred)
+        for y in range(height):
+            start = (y * width + (0 if left else width // 2)) * 3
+            frame[start:start + width // 2 * 3] = sharp[start:start + width // 2 * 3]
+        return bytes(frame)
+
+    def mean_error(image: bytes) -> float:
+        return statistics.mean(abs(a - b) for a, b in zip(image, sharp))
+
+    left, right = half_sharp(True), half_sharp(False)
+    merged = backend.focus_stack([left, right], width, height, 3)
+    assert mean_error(merged) < 0.1 * min(mean_error(left), mean_error(right))
+
+
+def test_subtract_dark_frame_clamps_at_zero():
+    """
+    Tests that subtracting a frame from itself leaves black, that a dimmer
+    dark frame leaves a positive residual with hot pixels removed, and that
+    frames of different sizes are rejected.
+    """
+    rng = random.Random(5)
+    width, height = 8, 4
+    image = bytes(rng.randrange(20, 256) for _ in range(width * height * 3))
+    assert backend.subtract_dark_frame(image, image, width, height, 3) == bytes(len(image))
+
+    dark = bytearray(v // 4 for v in image)
+    dark[0] = 255  # A hot pixel brighter than the image there.
+    result = backend.subtract_dark_frame(image, bytes(dark), width, height, 3)
+    assert result[0] == 0
+    assert all(0 < r == v - d for r, v, d in zip(result[1:], image[1:], dark[1:]))
+
+    with pytest.raises(ValueError):
+        backend.subtract_dark_frame(image, bytes(dark[:-3]), width, height, 3)
+
+
+def test_flat_field_correct_removes_vignetting():
+    """
+    Tests that a uniform image divided by a uniform flat is unchanged, that a
+    flat with darker corners undoes the same darkening of a uniform scene,
+    and that black flat samples leave the image alone.
+    """
+    width, height = 6, 4
+    uniform = bytes([120] * (width * height * 3))
+    flat = bytes([200] * (width * height * 3))
+    assert backend.flat_field_correct(uniform, flat, width, height, 3) == uniform
+
+    def falloff(x, y):
+        return 1.0 - 0.05 * (abs(x - 2.5) + abs(y - 1.5))
+
+    vignetted_flat = bytes(round(200 * falloff(x, y)) for y in range(height) for x in range(width) for _ in range(3))
+    vignetted = bytes(round(160 * falloff(x, y)) for y in range(height) for x in range(width) for _ in range(3))
+    corrected = backend.flat_field_correct(vignetted, vignetted_flat, width, height, 3)
+    assert max(corrected) - min(corrected) <= 2
+
+    holed_flat = bytes([0] * 3) + flat[3:]
+    assert backend.flat_field_correct(uniform, holed_flat, width, height, 3)[:3] == uniform[:3]
+    with pytest.raises(ValueError):
+        backend.flat_field_correct(uniform, bytes(len(uniform)), width, height, 3)
+
+
+def test_merge_hdr_takes_each_region_from_the_usable_frame():
+    """
+    Tests that a highlight saturated in the long exposure comes from the
+    short one, and that a shadow near black in the short exposure mostly
+    comes from the long one.
+    """
+    # Pixel 0 is a highlight, pixel 1 a shadow; the long frame has 8x exposure.
+    short = array.array("H", [26214] * 3 + [1000] * 3).tobytes()
+    long = array.array("H", [65535] * 3 + [5243] * 3).tobytes()
+    merged = array.array("f", backend.merge_hdr([short, long], [1.0, 8.0], 2, 1))
+    assert len(merged) == 6
+
+    assert merged[0] == pytest.approx(26214 / 65535)
+    shadow_from_short = 1000 / 65535
+    shadow_from_long = 5243 / 65535 / 8
+    assert abs(merged[3] - shadow_from_long) < 0.25 * abs(merged[3] - shadow_from_short)
+
+    with pytest.raises(ValueError):
+        backend.merge_hdr([short, long], [1.0], 2, 1)
+    with pytest.raises(ValueError):
+        backend.merge_hdr([short, long[:-2]], [1.0, 8.0], 2, 1)
+
+
+@pytest.mark.parametrize("operator", [backend.ToneMapOperator.REINHARD, backend.ToneMapOperator.DRAGO])
+def test_tonemap_compresses_a_wide_range_into_8_bits(operator):
+    """
+    Tests that a gradient spanning eight decades of radiance maps to a
+    monotonic 8-bit ramp that uses the range without crushing or clipping
+    most of it.
+    """
+    width = 64
+    levels = [10 ** (-4 + 8 * x / (width - 1)) for x in range(width)]
+    radiance = array.array("f", [v for v in levels for _ in range(3)]).tobytes()
+    result = backend.tonemap(radiance, width, 1, operator)
+    assert len(result) == width * 3
+
+    gray = result[::3]
+    assert all(b >= a for a, b in zip(gray, gray[1:]))
+    assert gray[-1] > 250
+    assert sum(v in (0, 255) for v in gray) < width // 3
+    assert result[0::3] == result[1::3] == result[2::3]
+
+    with pytest.raises(ValueError):
+        backend.tonemap(radiance[:-4], width, 1, operator)
+
+
+def test_local_tonemap_keeps_more_texture_than_reinhard():
+    """
+    Tests that the local operator keeps a fine checker texture visible in
+    both a very bright and a very dark region, where Reinhard flattens it.
+    """
+    width, height = 64, 32
+    levels = []
+    for y in range(height):
+        for x in range(width):
+            base = 1000.0 if x < width // 2 else 0.01
+            levels.append(base * (1.2 if (x + y) % 2 else 1.0))
+    radiance = array.array("f", [v for v in levels for _ in range(3)]).tobytes()
+
+    def texture(image: bytes) -> int:
+        gray = image[::3]
+        return sum(
+            abs(gray[y * width + x] - gray[y * width + x + 1])
+            for y in range(height)
+            for x in range(width - 1)
+            if x + 1 != width // 2
+        )
+
+    local = backend.tonemap(radiance, width, height, backend.ToneMapOperator.LOCAL)
+    reinhard = backend.tonemap(radiance, width, height, backend.ToneMapOperator.REINHARD)
+    assert len(local) == width * height * 3
+    assert texture(local) > 2 * texture(reinhard)
+
+    with pytest.raises(ValueError):
+        backend.tonemap(radiance, width, height, backend.ToneMapOperator.LOCAL, base_stops=0.0)
+
+
+def test_suggest_denoise_strength_grows_with_iso():
+    """
+    Tests that higher ISO values yield higher suggested denoise strengths.
+    """
+    strengths = [backend.suggest_denoise_strength(iso) for iso in (100, 400, 1600, 6400, 25600)]
+    assert strengths[0] == 0.0
+    assert all(b > a for a, b in zip(strengths, strengths[1:]))
+    assert strengths[-1] < 1.0
+
+
+def test_suggest_exposure_brightens_dark_and_darkens_bright_images():
+    """
+    Tests that a dark image gets a positive exposure suggestion, a bright one
+    a negative suggestion, and a middle gray image roughly none.
+    """
+    width, height = 8, 8
+    dark = bytes([30] * width * height * 3)
+    bright = bytes([235] * width * height * 3)
+    gray = bytes([118] * width * height * 3)
+
+    assert backend.suggest_exposure(dark, width, height) > 0.5
+    assert backend.suggest_exposure(bright, width, height) < -0.5
+    assert abs(backend.suggest_exposure(gray, width, height)) < 0.1
+
+
+def test_image_stats_counts_clipped_pixels():
+    """
+    Tests that clipped pixels are counted per channel against the given
+    thresholds, and that the percentages, means, and ranges follow.
+    """
+    # 100 RGB pixels: 4 with red maxed out, 10 fully black, the rest mid gray.
+    pixels = [(255, 128, 128)] * 4 + [(0, 0, 0)] * 10 + [(128, 128, 128)] * 86
+    image = bytes(v for pixel in pixels for v in pixel)
+
+    red, green, blue = backend.image_stats(image, 10, 10, 3)
+    assert red["clipped_high"] == 4
+    assert red["clipped_high_percent"] == pytest.approx(4.0)
+    assert green["clipped_high"] == blue["clipped_high"] == 0
+    assert red["clipped_low"] == green["clipped_low"] == blue["clipped_low"] == 10
+    assert (red["min"], red["max"]) == (0, 255)
+    assert red["mean"] == pytest.approx((4 * 255 + 86 * 128) / 100)
+
+    lenient = backend.image_stats(image, 10, 10, 3, low=5, high=128)
+    assert lenient[1]["clipped_high"] == 90
+    with pytest.raises(ValueError):
+        backend.image_stats(image, 10, 10, 3, low=200, high=100)
+
+
+def test_sample_neutral_multipliers_picks_without_changing_the_image():
+    """
+    Tests that a gray area needs no correction, a warm one is cooled
+    (red lowered, blue raised, green kept at 1), and a nearly black one gives
+    no multipliers at all.
+    """
+    width, height = 9, 3
+    pixels = [(128, 128, 128)] * 3 + [(200, 150, 100)] * 3 + [(3, 3, 3)] * 3
+    image = bytes(v for y in range(height) for x in range(width) for v in pixels[x])
+
+    assert backend.sample_neutral_multipliers(image, width, height, 1, 1) == pytest.approx((1.0, 1.0, 1.0))
+    red, green, blue = backend.sample_neutral_multipliers(image, width, height, 4, 1)
+    assert red < 0.7 and green == 1.0 and blue > 2.0
+    assert backend.sample_neutral_multipliers(image, width, height, 7, 1) is None
+
+    with pytest.raises(ValueError):
+        backend.sample_neutral_multipliers(image, width, height, width, 0)
+
+
+def _patterned_image(width: int, height: int) -> bytes:
+    """An RGB image of diagonal ramps overlaid with a coarse checkerboard."""
+    pixels = []
+    for y in range(height):
+        for x in range(width):
+            v = (x * 3 + y * 2) % 200 + ((x // 16 + y // 16) % 2) * 40
+            pixels += [v, v // 2, 255 - v]
+    return bytes(pixels)
+
+
+def test_phash_is_stable_under_brightening():
+    """
+    Tests that an image and a slightly brightened copy hash within a few
+    bits of each other, while an unrelated image hashes far away.
+    """
+    width, height = 96, 64
+    image = _patterned_image(width, height)
+    brightened = bytes(min(v + 12, 255) for v in image)
+    rng = random.Random(7)
+    noise = bytes(rng.randrange(256) for _ in range(width * height * 3))
+
+    reference = backend.phash(image, width, height)
+    assert 0 <= reference < 2**64
+    assert backend.hash_distance(reference, backend.phash(brightened, width, height)) <= 4
+    assert backend.hash_distance(reference, backend.phash(noise, width, height)) > 12
+
+
+@pytest.mark.parametrize(
+    "a, b, expected",
+    [
+        (0, 0, 0),
+        (0xDEADBEEF, 0xDEADBEEF, 0),
+        (0, 1, 1),
+        (0b1011, 0b0110, 3),
+        (0, 2**64 - 1, 64),
+        (2**63, 1, 2),
+    ],
+)
+def test_hash_distance_counts_differing_bits(a, b, expected):
+    """
+    Tests the Hamming distance of known hash pairs, including identical
+    hashes and the full 64-bit range.
+    """
+    assert backend.hash_distance(a, b) == expected
+    assert backend.hash_distance(b, a) == expected
+
+
+def test_render_histogram_has_the_requested_size():
+    """
+    Tests that the rendered histogram is an RGB buffer of the requested
+    size that shows more than the background, with the bar of a flat red
+    channel in the rightmost column and the other channels on the left.
+    """
+    width, height = 16, 8
+    image = bytes([255, 0, 0] * width * height)
+    out_width, out_height = 128, 40
+
+    result = backend.render_histogram(image, width, height, 3, out_width, out_height)
+    assert len(result) == out_width * out_height * 3
+    assert len(set(result)) > 1
+
+    def pixel(x: int, y: int) -> bytes:
+        return result[(y * out_width + x) * 3:(y * out_width + x + 1) * 3]
+
+    assert pixel(out_width - 1, 0)[0] > pixel(out_width - 1, 0)[1]
+    assert pixel(0, 0)[1] > pixel(0, 0)[0]
+    assert pixel(out_width // 2, 0) == pixel(out_width // 2, out_height - 1)
+
+    with pytest.raises(ValueError):
+        backend.render_histogram(image, width, height, 3, 0, out_height)
+
+
+def test_render_waveform_of_a_ramp_is_a_diagonal():
+    """
+    Tests that an image whose columns get brighter from left to right
+    produces a single trace per column, rising diagonally from the bottom
+    left to the top right.
+    """
+    width, height, out_height = 64, 20, 64
+    columns = [x * 255 // (width - 1) for x in range(width)]
+    image = bytes(v for _ in range(height) for v in columns for _ in range(3))
+
+    result = backend.render_waveform(image, width, height, out_height)
+    assert len(result) == width * out_height * 3
+
+    for x in range(width):
+        lit = [y for y in range(out_height) if result[(y * width + x) * 3] > 0]
+        assert len(lit) == 1
+        assert abs(lit[0] - (out_height - 1) * (width - 1 - x) / (width - 1)) <= 1
+
+
+def test_render_vectorscope_puts_red_in_the_upper_left():
+    """
+    Tests that all the energy of a pure red image lands in the upper-left
+    quadrant of the vectorscope, and that gray lands in the center.
+    """
+    size = 65
+
+    def quadrant_energy(scope: bytes) -> dict:
+        energy = {"upper_left": 0, "elsewhere": 0}
+        for y in range(size):
+            for x in range(size):
+                key = "upper_left" if x < size // 2 and y < size // 2 else "elsewhere"
+                energy[key] += scope[(y * size + x) * 3]
+        return energy
+
+    red = backend.render_vectorscope(bytes([255, 0, 0] * 16), 4, 4, size)
+    assert len(red) == size * size * 3
+    energy = quadrant_energy(red)
+    assert energy["upper_left"] > 0
+    assert energy["elsewhere"] == 0
+
+    gray = backend.render_vectorscope(bytes([128] * 3 * 16), 4, 4, size)
+    center = (size // 2 * size + size // 2) * 3
+    assert gray[center] == 255
+    assert sum(gray) == 3 * 255
+
+
+def _noisy_edge_image(width: int, height: int, seed: int) -> bytes:
+    """A gray image with a dark left and bright right half plus noise."""
+    rng = random.Random(seed)
+    pixels = []
+    for _ in range(height):
+        for x in range(width):
+            v = (60 if x < width // 2 else 200) + rng.randint(-15, 15)
+            pixels += [v, v, v]
+    return bytes(pixels)
+
+
+def test_denoise_wavelet_smooths_flat_areas_and_keeps_edges():
+    """
+    Tests that wavelet denoising reduces the noise in flat regions while the
+    step between the two halves stays sharp.
+    """
+    width, height = 32, 32
+    image = _noisy_edge_image(width, height, seed=1)
+    result = backend.denoise_wavelet(image, width, height, 1.0)
+
+    def column(data: bytes, x: int) -> list[int]:
+        return [data[(y * width + x) * 3] for y in range(height)]
+
+    for x in (6, 25):
+        assert statistics.pstdev(column(result, x)) < 0.6 * statistics.pstdev(column(image, x))
+
+    edge = statistics.mean(column(result, 16)) - statistics.mean(column(result, 15))
+    assert edge > 100
+
+
+def _ycbcr_planes(data: bytes) -> tuple[list[float], list[float]]:
+    """Splits an RGB buffer into its luma and (Cb, Cr interleaved) chroma values."""
+    luma, chroma = [], []
+    for i in range(0, len(data), 3):
+        r, g, b = data[i], data[i + 1], data[i + 2]
+        luma.append(0.299 * r + 0.587 * g + 0.114 * b)
+        chroma.append(-0.168736 * r - 0.331264 * g + 0.5 * b)
+        chroma.append(0.5 * r - 0.418688 * g - 0.081312 * b)
+    return luma, chroma
+
+
+def test_denoise_ycbcr_reduces_chroma_noise_more_than_luma_detail():
+    """
+    Tests that with a stronger chroma than luma setting, random color speckle
+    is reduced much more than a luminance stripe pattern.
+    """
+    rng = random.Random(2)
+    width, height = 32, 32
+    pixels = []
+    for y in range(height):
+        for _ in range(width):
+            base = 90 if y % 4 < 2 else 160
+            shift = rng.randint(-25, 25)
+            pixels += [base + shift, base - shift, base + shift // 2]
+    image = bytes(pixels)
+
+    result = backend.denoise_ycbcr(image, width, height, 0.5, 3.0)
+
+    luma_before, chroma_before = _ycbcr_planes(image)
+    luma_after, chroma_after = _ycbcr_planes(result)
+    luma_kept = statistics.pstdev(luma_after) / statistics.pstdev(luma_before)
+    chroma_kept = statistics.pstdev(chroma_after) / statistics.pstdev(chroma_before)
+    assert chroma_kept < 0.5
+    assert luma_kept > chroma_kept
+
+
+def test_fix_hot_pixels_repairs_a_hot_pixel_and_keeps_the_gradient():
+    """
+    Tests that a single hot pixel in a smooth gradient is replaced by the
+    value of its surroundings while every other pixel stays as it was.
+    """
+    width, height = 8, 6
+    gradient = bytearray()
+    for y in range(height):
+        for x in range(width):
+            gradient += bytes([20 + 20 * x, 30 + 10 * y, 100])
+    image = bytearray(gradient)
+    hot = (3 * width + 4) * 3
+    image[hot:hot + 3] = bytes([255, 255, 255])
+
+    result = backend.fix_hot_pixels(bytes(image), width, height, 3, 40)
+    assert result == bytes(gradient)
+    assert backend.fix_hot_pixels(bytes(gradient), width, height, 3, 0) == bytes(gradient)
+    with pytest.raises(ValueError):
+        backend.fix_hot_pixels(bytes(gradient), width, height, 3, -1)
+
+
+def test_fix_hot_pixels_reads_the_border_according_to_the_edge_mode():
+    """
+    Tests that a uniform image is left alone when the neighbourhood repeats
+    or mirrors the edge, while a black border outvotes the corner pixels.
+    """
+    width, height = 6, 4
+    image = bytes([200] * (width * height))
+    assert backend.fix_hot_pixels(image, width, height, 1, 40, backend.EdgeMode.REFLECT) == image
+    constant = backend.fix_hot_pixels(image, width, height, 1, 40, backend.EdgeMode.CONSTANT)
+    assert constant[0] == 0
+    assert constant[width + 1] == 200
+
+
+def test_apply_python_filter_identity_and_batches():
+    """
+    Tests that an identity callback returns the input unchanged, that the
+    image is split into batches of whole pixels, and that a callback
+    returning the wrong length is rejected.
+    """
+    width, height = 5, 3
+    image = bytes(range(width * height * 3))
+    batches = []
+
+    def identity(pixels: bytes) -> bytes:
+        batches.append(len(pixels))
+        return pixels
+
+    assert backend.apply_python_filter(image, width, height, 3, identity, batch_pixels=4) == image
+    assert batches == [12, 12, 12, 9]
+
+    inverted = backend.apply_python_filter(image, width, height, 3, lambda px: bytes(255 - v for v in px))
+    assert inverted == bytes(255 - v for v in image)
+
+    with pytest.raises(ValueError):
+        backend.apply_python_filter(image, width, height, 3, lambda px: px[:-1])
+
+
+def test_develop_defaults_are_a_no_op():
+    """
+    Tests that developing with default settings, as an object or an empty
+    dict, returns the image (nearly) unchanged.
+    """
+    rng = random.Random(3)
+    width, height = 16, 8
+    image = bytes(rng.randrange(256) for _ in range(width * height * 3))
+
+    for settings in (backend.DevelopSettings(), {}):
+        result = backend.develop(image, width, height, settings)
+        assert max(abs(a - b) for a, b in zip(result, image)) <= 1
+
+
+def test_develop_applies_settings_from_dict_and_object():
+    """
+    Tests that a positive exposure brightens a gray image, that dict and
+    object settings give the same result, and that unknown keys are rejected.
+    """
+    image = bytes([128] * 3 * 4)
+    settings = backend.DevelopSettings()
+    settings.exposure = 1.0
+    settings.temp = 0.5
+
+    result = backend.develop(image, 2, 2, settings)
+    assert result == backend.develop(image, 2, 2, {"exposure": 1.0, "temp": 0.5})
+    assert result[1] > 128
+    assert result[0] > result[1] > result[2]
+
+    with pytest.raises(ValueError):
+        backend.develop(image, 2, 2, {"exposre": 1.0})
+
+
+def test_develop_from_json_matches_develop():
+    """
+    Tests that a serialized preset gives the same result as calling develop
+    with the settings directly, that unknown fields are ignored, and that
+    malformed JSON is reported as an error.
+    """
+    rng = random.Random(5)
+    width, height = 8, 8
+    image = bytes(rng.randrange(256) for _ in range(width * height * 3))
+    settings = backend.DevelopSettings()
+    settings.exposure = 0.5
+    settings.contrast = 0.2
+    settings.saturation = -0.3
+    settings.sharpen = 0.8
+
+    preset = backend.develop_settings_to_json(settings)
+    expected = backend.develop(image, width, height, settings)
+    assert backend.develop_from_json(image, width, height, preset) == expected
+
+    extended = json.loads(preset)
+    extended["vignette"] = 0.4
+    assert backend.develop_from_json(image, width, height, json.dumps(extended)) == expected
+
+    with pytest.raises(ValueError):
+        backend.develop_from_json(image, width, height, '{"exposure": 0.5')
+    with pytest.raises(ValueError):
+        backend.develop_from_json(image, width, height, "[0.5]")
@@ -0,0 +1,121 @@
+import xml.etree.ElementTree as ET
+
+import pytest
+
+from mpr_photo_editor import backend
+
+NAMESPACES = {
+    "x": "adobe:ns:meta/",
+    "rdf": "http://www.w3.org/1999/02/22-rdf-syntax-ns#",
+    "xmp": "http://ns.adobe.com/xap/1.0/",
+    "dc": "http://purl.org/dc/elements/1.1/",
+}
+
+
+def test_xmp_sidecar_path_replaces_the_extension():
+    """
+    Tests that sidecars take the raw file's name with an .xmp extension,
+    and that dots in directory names are not mistaken for one.
+    """
+    assert backend.xmp_sidecar_path("/photos/IMG_0001.CR2") == "/photos/IMG_0001.xmp"
+    assert backend.xmp_sidecar_path("/photos.2024/IMG_0001") == "/photos.2024/IMG_0001.xmp"
+
+
+def test_write_xmp_sidecar_is_parseable_and_holds_the_rating(tmp_path):
+    """
+    Tests that the written sidecar is well-formed XML next to the raw file,
+    with the rating and the keywords, including ones that need escaping.
+    """
+    raw = tmp_path / "IMG_0001.NEF"
+    raw.write_bytes(b"raw data")
+    keywords = ["beach", "Tom & Jerry", "<sunset>"]
+
+    path = backend.write_xmp_sidecar(str(raw), 4, keywords)
+    assert path == str(tmp_path / "IMG_0001.xmp")
+    assert raw.read_bytes() == b"raw data"
+
+    root = ET.parse(path).getroot()
+    description = root.find("rdf:RDF/rdf:Description", NAMESPACES)
+    assert description is not None
+    assert description.get("{%s}Rating" % NAMESPACES["xmp"]) == "4"
+    items = description.findall("dc:subject/rdf:Bag/rdf:li", NAMESPACES)
+    assert [item.text for item in items] == keywords
+
+
+def test_write_xmp_sidecar_rejects_invalid_ratings(tmp_path):
+    """
+    Tests that ratings outside -1 to 5 are rejected without writing a file.
+    """
+    raw = tmp_path / "IMG_0002.NEF"
+    with pytest.raises(ValueError):
+        backend.write_xmp_sidecar(str(raw), 6, [])
+    assert not (tmp_path / "IMG_0002.xmp").exists()
+
+
+def test_write_xmp_sidecar_keeps_the_rest_of_an_existing_sidecar(tmp_path):
+    """
+    Tests that writing over a sidecar from another program replaces only the
+    rating, label, and keywords, and keeps its develop settings.
+    """
+    sidecar = tmp_path / "IMG_0006.xmp"
+    sidecar.write_text(
+        '<x:xmpmeta xmlns:x="adobe:ns:meta/" x:xmptk="Adobe XMP Core 7.0">\n'
+        ' <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">\n'
+        '  <rdf:Description rdf:about=""\n'
+        '    xmlns:xmp="http://ns.adobe.com/xap/1.0/"\n'
+        '    xmlns:crs="http://ns.adobe.com/camera-raw-settings/1.0/"\n'
+        '    xmp:Rating="1"\n'
+        '    crs:Exposure2012="+0.50">\n'
+        "   <xmp:Label>Blue</xmp:Label>\n"
+        '   <dc:subject xmlns:dc="http://purl.org/dc/elements/1.1/">\n'
+        "    <rdf:Bag><rdf:li>old</rdf:li></rdf:Bag>\n"
+        "   </dc:subject>\n"
+        "  </rdf:Description>\n"
+        " </rdf:RDF>\n"
+        "</x:xmpmeta>\n"
+    )
+    raw = str(tmp_path / "IMG_0006.CR2")
+
+    backend.write_xmp_sidecar(raw, 5, ["new"], "Green")
+    assert backend.read_xmp_sidecar(raw) == {"rating": 5, "label": "Green", "keywords": ["new"]}
+    description = ET.parse(sidecar).getroot().find("rdf:RDF/rdf:Description", NAMESPACES)
+    assert description.get("{http://ns.adobe.com/camera-raw-settings/1.0/}Exposure2012") == "+0.50"
+
+    sidecar.write_text("not XMP")
+    with pytest.raises(RuntimeError):
+        backend.write_xmp_sidecar(raw, 3, [])
+    assert sidecar.read_text() == "not XMP"
+
+
+def test_read_xmp_sidecar_round_trips_the_writer(tmp_path):
+    """
+    Tests that reading back a sidecar written by write_xmp_sidecar returns
+    the same rating, label, and keywords.
+    """
+    raw = str(tmp_path / "IMG_0003.ARW")
+    keywords = ["family", "Tom & Jerry", "café"]
+    backend.write_xmp_sidecar(raw, -1, keywords, "Red")
+    assert backend.read_xmp_sidecar(raw) == {"rating": -1, "label": "Red", "keywords": keywords}
+
+
+def test_read_xmp_sidecar_understands_element_properties(tmp_path):
+    """
+    Tests that properties written as elements rather than attributes, as
+    some tools do, are read as well.
+    """
+    (tmp_path / "IMG_0004.xmp").write_text(
+        '<x:xmpmeta xmlns:x="adobe:ns:meta/"><rdf:RDF><rdf:Description rdf:about="">'
+        "<xmp:Rating>3</xmp:Rating><xmp:Label>Green</xmp:Label>"
+        '<dc:subject><rdf:Bag><rdf:li xml:lang="en">a &lt;b&gt;</rdf:li></rdf:Bag></dc:subject>'
+        "</rdf:Description></rdf:RDF></x:xmpmeta>"
+    )
+    sidecar = backend.read_xmp_sidecar(str(tmp_path / "IMG_0004.CR2"))
+    assert sidecar == {"rating": 3, "label": "Green", "keywords": ["a <b>"]}
+
+
+def test_read_xmp_sidecar_without_a_sidecar_is_empty(tmp_path):
+    """
+    Tests that a raw file without a sidecar reads as unrated, unlabeled,
+    and without keywords.
+    """
+    assert backend.read_xmp_sidecar(str(tmp_path / "IMG_0005.NEF")) == {"rating": 0, "label": "", "keywords": []}